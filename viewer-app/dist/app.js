@@ -27,10 +27,22 @@ const { open } = window.__TAURI__.dialog;
 
 console.log('[INIT] Tauri APIs loaded — invoke:', typeof invoke, 'listen:', typeof listen, 'open:', typeof open);
 
+// Commands fail with {kind, message}; anything else is shown as it is.
+function errorText(err) {
+  return err && err.message !== undefined ? err.message : String(err);
+}
+
 // ── State ──────────────────────────────────────────────────────────────────
 let cy = null;
 let companyData = null;
 let repoPath = null;
+// The user's settings.json; set fields override company_config.
+let userSettings = {};
+
+function withUserSettings(companyConfig) {
+  const overrides = Object.fromEntries(Object.entries(userSettings).filter(([, v]) => v != null));
+  return { ...(companyConfig || {}), ...overrides };
+}
 
 // ── Node styling ───────────────────────────────────────────────────────────
 const levelColors = {
@@ -314,6 +326,7 @@ function knowledgeSection(wsData) {
         <div style="margin: 6px 0; padding: 6px 8px; background: var(--bg-tertiary); border-radius: 4px; font-size: 12px;">
           <span style="color: ${typeColors[k.type] || '#999'}; font-weight: 600; font-size: 10px;">${k.type || 'NOTE'}</span>
           <span style="color: var(--text-secondary); font-size: 10px; margin-left: 8px;">${k.date}</span>
+          <a href="#" style="float: right; font-size: 10px;" onclick="copyKnowledgeEntry(${companyData.knowledge.indexOf(k)}); return false;">Copy</a>
           <div style="margin-top: 2px;">${k.summary}</div>
           ${k.detail ? `<div style="color: var(--text-secondary); margin-top: 2px;">${k.detail}</div>` : ''}
         </div>
@@ -322,6 +335,16 @@ function knowledgeSection(wsData) {
   `;
 }
 
+// Copy one entry as markdown, ready to paste into an email.
+window.copyKnowledgeEntry = async function (index) {
+  try {
+    const markdown = await invoke('format_entry_markdown', { entry: companyData.knowledge[index] });
+    await navigator.clipboard.writeText(markdown);
+  } catch (err) {
+    console.error('[KNOWLEDGE] copy failed:', err);
+  }
+};
+
 window.closeDetail = function () {
   document.getElementById('detail-panel').classList.remove('open');
 };
@@ -364,12 +387,30 @@ function buildSidebar(data) {
     html += `<div class="sidebar-item">Entries <span class="badge">${knowledge.length}</span></div>`;
   }
 
+  const errors = data.errors || [];
+  if (errors.length) {
+    html += `<h3>Load errors</h3>`;
+    for (const err of errors) {
+      html += `<div class="sidebar-item" style="color: #e05050; font-size: 12px;" title="${err.message}">${err.file}</div>`;
+    }
+  }
+
+  const warnings = data.warnings || [];
+  if (warnings.length) {
+    html += `<h3>Warnings <span class="badge">${warnings.length}</span></h3>`;
+    for (const warning of warnings) {
+      const where = warning.line ? `${warning.file}:${warning.line}` : warning.file;
+      html += `<div class="sidebar-item" style="color: #d0a040; font-size: 12px;" title="${warning.message}">${where}</div>`;
+    }
+  }
+
   sidebar.innerHTML = html;
 }
 
 // ── Terminal ───────────────────────────────────────────────────────────────
 let term = null;
 let termLineBuf = '';
+const TERM_ID = 'main';
 
 function initTerminal() {
   const container = document.getElementById('terminal-container');
@@ -389,18 +430,31 @@ function initTerminal() {
   const fitAddon = new FitAddon.FitAddon();
   term.loadAddon(fitAddon);
   term.open(container);
+  const fitAndResize = () => {
+    fitAddon.fit();
+    invoke('resize_terminal', { id: TERM_ID, cols: term.cols, rows: term.rows }).catch(() => {});
+  };
   fitAddon.fit();
-  window.addEventListener('resize', () => fitAddon.fit());
+  window.addEventListener('resize', fitAndResize);
 
   // Send keystrokes to the backend
+  // Once the shell has exited the backend drops the session; the next
+  // keystroke starts a new one.
+  let respawning = false;
   term.onData((data) => {
-    invoke('write_terminal', { data }).catch(() => {});
+    invoke('write_terminal', { id: TERM_ID, data }).catch((err) => {
+      if (err?.kind !== 'terminal_not_running' || respawning) return;
+      respawning = true;
+      term.writeln('\r\n\x1b[90mStarting a new shell...\x1b[0m');
+      spawnShell().finally(() => { respawning = false; });
+    });
   });
 
   // Auto-spawn the terminal process
-  // Set up event listener BEFORE spawning so we don't miss early output
+  // Output is held back until terminal_attach, which must only be called once
+  // the listener is registered, so the first prompt isn't missed
   console.log('[TERM] Setting up terminal-output listener...');
-  listen('terminal-output', (event) => {
+  const outputListener = listen(`terminal-output-${TERM_ID}`, (event) => {
     console.log('[TERM] Received event, payload length:', event.payload?.length);
     if (term && event.payload) {
       term.write(event.payload);
@@ -409,15 +463,33 @@ function initTerminal() {
     console.log('[TERM] Listener registered OK');
   });
 
-  console.log('[TERM] Spawning terminal...');
-  term.writeln('\x1b[90mConnecting to shell...\x1b[0m\r\n');
-  invoke('spawn_terminal').then((result) => {
+  listen('terminal-exit', (event) => {
+    const { id, code, signal, idle } = event.payload || {};
+    if (id !== TERM_ID || !term) return;
+    if (idle) {
+      term.writeln('\x1b[90mShell closed after being idle\x1b[0m');
+      return;
+    }
+    const status = signal ? `signal ${signal}` : `code ${code ?? '?'}`;
+    term.writeln(`\x1b[90mShell exited (${status})\x1b[0m`);
+  });
+
+  const spawnShell = () => invoke('spawn_terminal', {
+    id: TERM_ID,
+    options: userSettings.default_shell ? { shell: userSettings.default_shell } : null,
+  }).then((result) => {
     console.log('[TERM] spawn_terminal returned:', result);
-    term.writeln(`\x1b[90mShell: ${result}\x1b[0m\r\n`);
+    term.writeln(`\x1b[90mShell: ${result.message}\x1b[0m\r\n`);
+    fitAndResize();
+    return outputListener.then(() => invoke('terminal_attach', { id: TERM_ID }));
   }).catch((err) => {
     console.error('[TERM] spawn_terminal failed:', err);
-    term.writeln(`\x1b[31mFailed to start shell: ${err}\x1b[0m`);
+    term.writeln(`\x1b[31mFailed to start shell: ${errorText(err)}\x1b[0m`);
   });
+
+  console.log('[TERM] Spawning terminal...');
+  term.writeln('\x1b[90mConnecting to shell...\x1b[0m\r\n');
+  spawnShell();
 }
 
 // ── Resize handle ──────────────────────────────────────────────────────────
@@ -453,8 +525,10 @@ function initResizeHandle() {
 
 // ── Open repo ──────────────────────────────────────────────────────────────
 async function openRepo(path) {
+  const isNewRepo = path !== repoPath;
   try {
     companyData = await invoke('read_company_data', { repoPath: path });
+    companyData.company_config = withUserSettings(companyData.company_config);
     repoPath = path;
 
     const companyName = companyData.company_config?.company || 'Company';
@@ -463,11 +537,31 @@ async function openRepo(path) {
     buildSidebar(companyData);
     const elements = buildGraph(companyData);
     initCytoscape(elements);
+    if (isNewRepo) {
+      invoke('watch_repo', { repoPath: path }).catch((err) => console.warn('[WATCH] watch_repo failed:', err));
+    }
   } catch (err) {
-    alert('Error loading repo: ' + err);
+    alert('Error loading repo: ' + errorText(err));
   }
 }
 
+// Reload when files change on disk (knowledge logs edited outside the viewer).
+// One save can touch several files, so reload once per burst.
+let reloadTimer = null;
+listen('company-data-changed', (event) => {
+  console.log('[WATCH] changed:', event.payload?.paths);
+  clearTimeout(reloadTimer);
+  reloadTimer = setTimeout(() => {
+    if (repoPath) openRepo(repoPath);
+  }, 100);
+});
+
+// How long each repo command took, for spotting the slow ones in devtools.
+listen('command-timing', (event) => {
+  const { name, duration_ms, ok } = event.payload || {};
+  console.debug(`[TIMING] ${name}: ${duration_ms} ms${ok ? '' : ' (failed)'}`);
+});
+
 async function promptOpenRepo() {
   const selected = await open({ directory: true, title: 'Select Company Repo' });
   if (selected) openRepo(selected);
@@ -484,7 +578,7 @@ async function testLocalJson() {
     alert(msg);
   } catch (err) {
     console.error('[TEST] Failed:', err);
-    alert('Failed to read local file: ' + err);
+    alert('Failed to read local file: ' + errorText(err));
   }
 }
 
@@ -496,11 +590,24 @@ document.addEventListener('DOMContentLoaded', async () => {
   document.getElementById('btn-fit').addEventListener('click', () => cy?.fit());
   document.getElementById('btn-relayout').addEventListener('click', runLayout);
 
+  userSettings = await invoke('read_settings').catch((err) => {
+    console.warn('[SETTINGS] read_settings failed:', err);
+    return {};
+  });
   initTerminal();
   initResizeHandle();
 
-  const argPath = await invoke('get_repo_from_args');
-  if (argPath) {
-    openRepo(argPath);
+  invoke('get_app_version').then((v) => {
+    document.getElementById('app-title').title =
+      `v${v.version} (${v.git_sha}, built ${v.build_date}) · Tauri ${v.tauri_version}`;
+  });
+
+  const launch = await invoke('get_launch_config');
+  const theme = launch.theme || userSettings.theme;
+  if (theme) {
+    document.body.dataset.theme = theme;
+  }
+  if (launch.repo) {
+    openRepo(launch.repo);
   }
 });
@@ -0,0 +1,346 @@
+// ── Company data read straight out of a .zip snapshot ──────────────────────
+
+use crate::company::{self, COMPANY_FILES, DEFAULT_COMPANY_DIR};
+use crate::config::{self, ViewerConfig};
+use crate::error::CommandError;
+use crate::knowledge;
+use crate::telemetry;
+use std::collections::{BTreeMap, HashSet};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// read_company_data for a repo received as a `.zip`, read in place rather
+/// than extracted. The repo is the folder holding the archive's shallowest
+/// `_company` directory, so a zip of the repo's contents and a zip of the
+/// repo folder itself (as GitHub's "Download ZIP" makes) both work. The
+/// result has the same shape, with the company files, engagements and
+/// knowledge logs found as on disk, except that only `KNOWLEDGE_LOG.md` is
+/// looked for and `_meta` paths read `archive.zip!/entry`.
+///
+/// An entry whose name is absolute or climbs out with `..` (a zip-slip
+/// entry) is never read; it's reported in `errors` instead. Runs off the
+/// main thread, since a large archive takes a while to decompress.
+#[tauri::command(async)]
+pub fn read_company_data_zip(
+    app: tauri::AppHandle,
+    config: tauri::State<'_, ViewerConfig>,
+    zip_path: String,
+) -> Result<serde_json::Value, CommandError> {
+    telemetry::timed(&app, "read_company_data_zip", || {
+        read_zip(Path::new(&zip_path), &config)
+    })
+}
+
+pub fn read_zip(zip_path: &Path, config: &ViewerConfig) -> Result<serde_json::Value, CommandError> {
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+    let mut meta = serde_json::Map::new();
+    let mut snapshot = Snapshot::open(zip_path, &mut errors)?;
+    let max_bytes = config.max_file_bytes;
+
+    let mut result = serde_json::Map::new();
+    for (key, filename) in COMPANY_FILES {
+        let found = company::company_file_names(filename)
+            .into_iter()
+            .map(|name| format!("{}/{}", DEFAULT_COMPANY_DIR, name))
+            .find(|relative| snapshot.files.contains_key(relative));
+        let Some(relative) = found else {
+            result.insert(key.to_string(), serde_json::Value::Null);
+            meta.insert(key.to_string(), serde_json::Value::Null);
+            continue;
+        };
+        let name = relative.rsplit('/').next().unwrap_or(&relative).to_string();
+        let value = snapshot
+            .read(&relative, &name, max_bytes)
+            .and_then(|content| company::parse_company_text(&content, Path::new(&relative), &name))
+            .unwrap_or_else(|e| {
+                errors.push(company::file_error(&relative, e));
+                serde_json::Value::Null
+            });
+        result.insert(key.to_string(), value);
+        meta.insert(key.to_string(), snapshot.meta(&relative));
+    }
+
+    let slotignore = format!("{}/{}", DEFAULT_COMPANY_DIR, config::SlotIgnore::FILE);
+    let ignore = snapshot
+        .read(&slotignore, config::SlotIgnore::FILE, max_bytes)
+        .map(|content| config::SlotIgnore::parse(&content))
+        .unwrap_or_default();
+
+    let mut knowledge_entries = Vec::new();
+    for engagement in snapshot.engagements(config.max_scan_depth, &ignore) {
+        let eng_name = engagement.rsplit('/').next().unwrap_or(&engagement).to_string();
+        for log in snapshot.knowledge_logs(&engagement, max_bytes) {
+            let file = log.file(&eng_name);
+            let relative = format!("{}/{}", engagement, log.relative);
+            meta.insert(file.clone(), snapshot.meta(&relative));
+            match snapshot.read(&relative, &file, max_bytes) {
+                Ok(content) => {
+                    let mut parsed = knowledge::ParsedLog::default();
+                    knowledge::parse_knowledge_log(&content, &eng_name, &log.workstream, &log.path, &mut parsed);
+                    for warning in parsed.warnings {
+                        warnings.push(company::log_warning(&file, &eng_name, &log, warning));
+                    }
+                    knowledge_entries.extend(parsed.entries);
+                }
+                Err(e) => errors.push(company::file_error(&file, e)),
+            }
+        }
+    }
+    knowledge::sort_newest_first(&mut knowledge_entries);
+
+    let knowledge = serde_json::to_value(&knowledge_entries)
+        .map_err(|e| CommandError::Internal(format!("Failed to serialize knowledge entries: {}", e)))?;
+    result.insert("knowledge".to_string(), knowledge);
+    result.insert("errors".to_string(), serde_json::Value::Array(errors));
+    result.insert("warnings".to_string(), serde_json::Value::Array(warnings));
+    result.insert("_meta".to_string(), serde_json::Value::Object(meta));
+    Ok(serde_json::Value::Object(result))
+}
+
+/// An open archive, and the index of each file in it by `/`-separated path
+/// from the repo root.
+struct Snapshot {
+    zip: zip::ZipArchive<std::fs::File>,
+    /// `archive.zip!/` plus the repo folder inside it, what `_meta` paths and
+    /// entries' source files start with.
+    prefix: String,
+    files: BTreeMap<String, usize>,
+}
+
+impl Snapshot {
+    /// Open `zip_path` and find the repo in it. Entries with unsafe names are
+    /// left out, each with an error in `errors`.
+    fn open(zip_path: &Path, errors: &mut Vec<serde_json::Value>) -> Result<Self, CommandError> {
+        let file = std::fs::File::open(zip_path)
+            .map_err(|e| CommandError::io(format_args!("Failed to open {}", zip_path.display()), &e))?;
+        let mut zip = zip::ZipArchive::new(file)
+            .map_err(|e| CommandError::Parse(format!("{} is not a zip archive: {}", zip_path.display(), e)))?;
+
+        let mut names: Vec<(String, usize)> = Vec::new();
+        for index in 0..zip.len() {
+            let entry = zip
+                .by_index_raw(index)
+                .map_err(|e| CommandError::Parse(format!("Failed to read {}: {}", zip_path.display(), e)))?;
+            if entry.is_dir() {
+                continue;
+            }
+            // enclosed_name refuses absolute names, drive prefixes and `..`
+            // that would climb above the archive's root.
+            match entry.enclosed_name() {
+                Some(path) => {
+                    let segments: Vec<String> =
+                        path.components().map(|c| c.as_os_str().to_string_lossy().into_owned()).collect();
+                    names.push((segments.join("/"), index));
+                }
+                None => {
+                    let message = format!("Entry would be outside the archive: {}", entry.name());
+                    errors.push(company::file_error(entry.name(), CommandError::PathTraversal(message)));
+                }
+            }
+        }
+
+        let company = format!("{}/", DEFAULT_COMPANY_DIR);
+        let root = names
+            .iter()
+            .filter_map(|(name, _)| {
+                let (at, _) = name
+                    .match_indices(&company)
+                    .find(|&(at, _)| at == 0 || name[..at].ends_with('/'))?;
+                Some(name[..at].to_string())
+            })
+            .min_by_key(|root| (root.matches('/').count(), root.clone()))
+            .ok_or_else(|| {
+                let message = format!("No {} directory found in {}", DEFAULT_COMPANY_DIR, zip_path.display());
+                CommandError::NotFound(message)
+            })?;
+        let files = names
+            .into_iter()
+            .filter_map(|(name, index)| Some((name.strip_prefix(&root)?.to_string(), index)))
+            .collect();
+        let prefix = format!("{}!/{}", zip_path.display(), root);
+        Ok(Snapshot { zip, prefix, files })
+    }
+
+    /// The text of the file at `relative`, capped like config::read_capped
+    /// and referred to as `name` in errors.
+    fn read(&mut self, relative: &str, name: &str, max_bytes: u64) -> Result<String, CommandError> {
+        let index = *self
+            .files
+            .get(relative)
+            .ok_or_else(|| CommandError::NotFound(format!("No {} in the archive", relative)))?;
+        let failed = |e: &dyn std::fmt::Display| CommandError::Io(format!("Failed to read {}: {}", name, e));
+        let entry = self.zip.by_index(index).map_err(|e| failed(&e))?;
+        if entry.size() > max_bytes {
+            return Err(config::too_big(name, entry.size(), max_bytes));
+        }
+        let mut bytes = Vec::new();
+        // The size is the archive's claim; don't read past the limit anyway.
+        entry.take(max_bytes + 1).read_to_end(&mut bytes).map_err(|e| failed(&e))?;
+        config::decode_capped(bytes, Path::new(relative), name, max_bytes)
+    }
+
+    /// The `_meta` record for the file at `relative`. Zip times have no time
+    /// zone; they're taken as UTC.
+    fn meta(&mut self, relative: &str) -> serde_json::Value {
+        let path = format!("{}{}", self.prefix, relative);
+        let Some(entry) = self.files.get(relative).and_then(|&index| self.zip.by_index_raw(index).ok()) else {
+            return serde_json::Value::Null;
+        };
+        let modified_ms = entry.last_modified().and_then(|t| {
+            let date = chrono::NaiveDate::from_ymd_opt(t.year().into(), t.month().into(), t.day().into())?;
+            let time = date.and_hms_opt(t.hour().into(), t.minute().into(), t.second().into())?;
+            Some(time.and_utc().timestamp_millis())
+        });
+        serde_json::json!({ "path": path, "modified_ms": modified_ms, "size": entry.size() })
+    }
+
+    /// Engagement folders, as company::engagement_dirs finds them on disk:
+    /// ones holding an engagement_config.json up to `max_depth` levels down,
+    /// not inside an ignored or `.slotignore`d folder or another engagement,
+    /// sorted by path.
+    fn engagements(&self, max_depth: usize, ignore: &config::SlotIgnore) -> Vec<String> {
+        let is_engagement = |dir: &str| self.files.contains_key(&format!("{}/engagement_config.json", dir));
+        let mut dirs: Vec<String> = self
+            .files
+            .keys()
+            .filter_map(|name| name.strip_suffix("/engagement_config.json"))
+            .filter(|dir| {
+                let segments: Vec<&str> = dir.split('/').collect();
+                segments.len() <= max_depth
+                    && (1..=segments.len()).all(|depth| {
+                        let path = segments[..depth].join("/");
+                        !config::is_ignored_dir(segments[depth - 1])
+                            && !ignore.is_ignored(&path)
+                            && (depth == segments.len() || !is_engagement(&path))
+                    })
+            })
+            .map(str::to_string)
+            .collect();
+        dirs.sort_by(|a, b| Path::new(a).cmp(Path::new(b)));
+        dirs
+    }
+
+    /// The engagement's knowledge logs, as knowledge::scan_knowledge_logs
+    /// finds them with the default log name: where engagement_config.json's
+    /// `knowledge_log` says, else `KNOWLEDGE_LOG.md` in each workstream
+    /// folder.
+    fn knowledge_logs(&mut self, engagement: &str, max_bytes: u64) -> Vec<knowledge::KnowledgeLog> {
+        let config_file = format!("{}/engagement_config.json", engagement);
+        let engagement_config: serde_json::Value = self
+            .read(&config_file, "engagement_config.json", max_bytes)
+            .ok()
+            .and_then(|content| serde_json::from_str(company::strip_bom(&content)).ok())
+            .unwrap_or_default();
+        let log = |workstream: String, relative: String| knowledge::KnowledgeLog {
+            workstream,
+            path: PathBuf::from(format!("{}{}/{}", self.prefix, engagement, relative)),
+            relative,
+        };
+
+        let mut logs = Vec::new();
+        let mut declared_dirs = HashSet::new();
+        for (key, ws) in engagement_config["workstreams"].as_object().into_iter().flatten() {
+            let Some(relative) = ws["knowledge_log"].as_str() else {
+                continue;
+            };
+            declared_dirs.insert(ws["output_dir"].as_str().unwrap_or(key).to_string());
+            let relative = relative.replace('\\', "/").trim_start_matches("./").to_string();
+            match knowledge::check_log_path(&relative) {
+                Ok(()) if self.files.contains_key(&format!("{}/{}", engagement, relative)) => {
+                    logs.push(log(key.clone(), relative))
+                }
+                Ok(()) => tracing::debug!(workstream = %key, "knowledge_log not found: {}", relative),
+                Err(e) => tracing::warn!(workstream = %key, "knowledge_log skipped: {}", e),
+            }
+        }
+
+        let mut found: BTreeMap<&str, &str> = BTreeMap::new();
+        let inside = self.files.keys().filter_map(|name| name.strip_prefix(engagement)?.strip_prefix('/'));
+        for (dir, file) in inside.filter_map(|rest| rest.split_once('/')) {
+            let is_log = !file.contains('/') && file.eq_ignore_ascii_case(knowledge::DEFAULT_LOG_NAME);
+            if is_log && !config::is_ignored_dir(dir) && !declared_dirs.contains(dir) {
+                found.entry(dir).or_insert(file);
+            }
+        }
+        logs.extend(found.into_iter().map(|(dir, file)| log(dir.to_string(), format!("{}/{}", dir, file))));
+        logs.sort_by(|a, b| a.workstream.cmp(&b.workstream));
+        logs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+    use std::io::Write;
+
+    fn write_zip(path: &Path, files: &[(&str, &str)]) {
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(path).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        zip.add_directory("acme-main/", options).unwrap();
+        for (name, contents) in files {
+            zip.start_file(*name, options).unwrap();
+            zip.write_all(contents.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn reads_a_zipped_repo_without_extracting_it() {
+        let dir = TempDir::new("archive");
+        let path = dir.path().join("acme.zip");
+        write_zip(
+            &path,
+            &[
+                ("acme-main/_company/org_chart.json", r#"{"leadership": []}"#),
+                ("acme-main/_company/.slotignore", "archive-*\n"),
+                ("acme-main/alpha/engagement_config.json", "{}"),
+                ("acme-main/alpha/ops/KNOWLEDGE_LOG.md", "## 2024-01-05\n### [STATUS] first\n"),
+                ("acme-main/archive-2022/engagement_config.json", "{}"),
+                ("acme-main/archive-2022/ops/KNOWLEDGE_LOG.md", "## 2022-01-01\n### [STATUS] old\n"),
+                (
+                    "acme-main/beta/engagement_config.json",
+                    r#"{"workstreams": {"it": {"knowledge_log": "notes/it.md"}}}"#,
+                ),
+                ("acme-main/beta/notes/it.md", "## 2024-02-01\n### [RISK] second\n## someday\n"),
+                ("../../evil/_company/org_chart.json", "{}"),
+            ],
+        );
+
+        let data = read_zip(&path, &ViewerConfig::default()).unwrap();
+        assert_eq!(data["org_chart"], serde_json::json!({ "leadership": [] }));
+        assert!(data["company_config"].is_null());
+        let knowledge = data["knowledge"].as_array().unwrap();
+        let summaries: Vec<(&str, &str)> = knowledge
+            .iter()
+            .map(|e| (e["engagement"].as_str().unwrap(), e["summary"].as_str().unwrap()))
+            .collect();
+        assert_eq!(summaries, [("beta", "second"), ("alpha", "first")]);
+        assert_eq!(knowledge[0]["workstream"], "it");
+        assert!(knowledge[0]["source_file"].as_str().unwrap().ends_with("acme.zip!/acme-main/beta/notes/it.md"));
+
+        let meta = &data["_meta"]["org_chart"];
+        assert!(meta["path"].as_str().unwrap().ends_with("acme.zip!/acme-main/_company/org_chart.json"));
+        assert_eq!(meta["size"], 18);
+        assert!(data["_meta"]["beta/notes/it.md"].is_object());
+        assert_eq!(data["warnings"][0]["file"], "beta/notes/it.md");
+
+        let errors = data["errors"].as_array().unwrap();
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        assert_eq!(errors[0]["file"], "../../evil/_company/org_chart.json");
+    }
+
+    #[test]
+    fn archives_without_company_data_are_refused() {
+        let dir = TempDir::new("archive-empty");
+        let path = dir.path().join("notes.zip");
+        write_zip(&path, &[("acme-main/README.md", "hello"), ("acme-main/docs/_company.md", "")]);
+        let err = read_zip(&path, &ViewerConfig::default()).unwrap_err();
+        assert!(matches!(err, CommandError::NotFound(_)), "{:?}", err);
+
+        std::fs::write(&path, "not a zip").unwrap();
+        let err = read_zip(&path, &ViewerConfig::default()).unwrap_err();
+        assert!(matches!(err, CommandError::Parse(_)), "{:?}", err);
+    }
+}
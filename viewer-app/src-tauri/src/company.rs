@@ -0,0 +1,2572 @@
+// ── Company data (the _company files plus engagement knowledge logs) ───────
+
+use crate::config::{self, ViewerConfig};
+use crate::error::CommandError;
+use crate::launch::LaunchConfig;
+use crate::knowledge;
+use crate::paths;
+use crate::telemetry;
+use rayon::prelude::*;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::SystemTime;
+use tauri::Emitter;
+
+/// Where a repo's company files live unless read_company_data is told
+/// otherwise.
+pub const DEFAULT_COMPANY_DIR: &str = "_company";
+
+/// The files read from `_company`, as (result key, filename).
+pub const COMPANY_FILES: [(&str, &str); 5] = [
+    ("org_chart", "org_chart.json"),
+    ("company_config", "company_config.json"),
+    ("engagement_registry", "engagement_registry.json"),
+    ("engagement_map", "engagement_map.json"),
+    ("file_index", "file_index.json"),
+];
+
+/// Enough of a file's metadata to tell whether it changed since it was parsed.
+#[derive(Clone, Copy, PartialEq)]
+struct FileStamp {
+    modified: SystemTime,
+    len: u64,
+}
+
+impl FileStamp {
+    fn of(path: &Path) -> Option<Self> {
+        let meta = std::fs::metadata(path).ok()?;
+        Some(FileStamp { modified: meta.modified().ok()?, len: meta.len() })
+    }
+
+    /// The `_meta` record for a file: its path, size, and modification time
+    /// in epoch milliseconds.
+    fn to_meta(self, path: &Path) -> serde_json::Value {
+        let modified_ms = self
+            .modified
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |since| since.as_millis() as u64);
+        serde_json::json!({
+            "path": path.to_string_lossy(),
+            "modified_ms": modified_ms,
+            "size": self.len,
+        })
+    }
+}
+
+/// What a load noticed besides the data itself.
+#[derive(Default)]
+struct LoadReport {
+    errors: Vec<serde_json::Value>,
+    warnings: Vec<serde_json::Value>,
+    /// File metadata by result key, or by file for knowledge logs.
+    meta: serde_json::Map<String, serde_json::Value>,
+}
+
+struct Cached<T> {
+    stamp: FileStamp,
+    value: T,
+}
+
+/// A company file's value, and whether it only parsed once relaxed (see
+/// config::relax_json).
+#[derive(Clone)]
+struct ParsedFile {
+    value: serde_json::Value,
+    lenient: bool,
+}
+
+/// What the previous load of one repo parsed, keyed by file path.
+#[derive(Default)]
+pub struct RepoCache {
+    files: HashMap<PathBuf, Cached<Result<ParsedFile, CommandError>>>,
+    /// Whether `files` were parsed with `lenient_json`.
+    lenient_json: bool,
+    logs: HashMap<PathBuf, Cached<Result<knowledge::ParsedLog, CommandError>>>,
+    /// Files the most recent load actually had to (re)parse.
+    reparsed: Vec<PathBuf>,
+}
+
+/// Parsed results per repo path, so reloading only re-reads changed files.
+pub type CompanyCache = Arc<Mutex<HashMap<PathBuf, RepoCache>>>;
+
+/// Parse each of `paths`, reusing the parse from `old` for files that are
+/// unchanged. Stat-ing and parsing run in parallel, which matters on network
+/// drives; results come back in the order of `paths`, each with the stamp it
+/// was checked against. Everything is recorded in `new`, so files that
+/// disappeared drop out of the cache. `finished` is called with each index as
+/// its value is ready, from whichever thread parsed it.
+fn cached_all<T: Clone + Send>(
+    old: &mut HashMap<PathBuf, Cached<T>>,
+    new: &mut HashMap<PathBuf, Cached<T>>,
+    reparsed: &mut Vec<PathBuf>,
+    paths: &[PathBuf],
+    parse: impl Fn(usize) -> T + Sync,
+    finished: impl Fn(usize) + Sync,
+) -> Vec<(Option<FileStamp>, T)> {
+    let stamps: Vec<Option<FileStamp>> = paths.par_iter().map(|path| FileStamp::of(path)).collect();
+
+    let mut values: Vec<Option<T>> = Vec::with_capacity(paths.len());
+    for (i, (path, stamp)) in paths.iter().zip(&stamps).enumerate() {
+        let hit = match (stamp, old.remove(path)) {
+            (Some(stamp), Some(hit)) if hit.stamp == *stamp => hit,
+            _ => {
+                values.push(None);
+                continue;
+            }
+        };
+        values.push(Some(hit.value.clone()));
+        new.insert(path.clone(), hit);
+        finished(i);
+    }
+
+    let misses: Vec<usize> = (0..paths.len()).filter(|&i| values[i].is_none()).collect();
+    let parsed: Vec<(usize, T)> = misses
+        .par_iter()
+        .map(|&i| {
+            let value = parse(i);
+            finished(i);
+            (i, value)
+        })
+        .collect();
+    for (i, value) in parsed {
+        reparsed.push(paths[i].clone());
+        if let Some(stamp) = stamps[i] {
+            new.insert(paths[i].clone(), Cached { stamp, value: value.clone() });
+        }
+        values[i] = Some(value);
+    }
+    stamps.into_iter().zip(values.into_iter().flatten()).collect()
+}
+
+/// Load the `_company` files and every engagement's knowledge logs. Each
+/// company file may be gzipped (`file_index.json.gz`) or YAML instead
+/// (`org_chart.yaml` or `.yml`), used only when the `.json` doesn't exist.
+/// A file that can't be read or parsed doesn't fail the load: its key is set
+/// to null and `{file, message}` is added to the `errors` array instead.
+/// Problems that don't stop a file loading go in `warnings` in the same
+/// shape; a knowledge log's (an unrecognized or empty date heading, an entry
+/// with no summary or no date) also carry `engagement`, `workstream` and
+/// `line`. `_meta` maps each company key (null when the file is missing) and
+/// each knowledge log to `{path, modified_ms, size}`.
+///
+/// `extra_files` loads further `_company` files, like `billing.json`, under
+/// their stem. Each is a file name or a pattern with `*` and `?` wildcards
+/// (`*.json`); matches that would shadow one of the standard keys are
+/// skipped. With `dedupe`, knowledge entries copied into several workstream
+/// logs of an engagement are shown once; see knowledge::dedupe.
+///
+/// `knowledge_files` names each workstream's knowledge log, like
+/// `["KNOWLEDGE_LOG.md", "knowledge.md", "LOG.md"]`, matched ignoring case
+/// with the first listed preferred; the default is just `KNOWLEDGE_LOG.md`.
+///
+/// `entry_types` lists the knowledge entry types in use, like `["FINDING",
+/// "RISK", "DECISION"]`; the default is the `entry_types` array in
+/// company_config.json, if it has one. An entry of any other type (a typo
+/// like `[DECISON]`) is kept but gets a warning, matched ignoring case.
+///
+/// `lenient_json` accepts JSON company files with `//` or `/* */` comments
+/// and trailing commas, as hand-edited files often have, instead of loading
+/// them as null; each file that needed it gets a warning. Strict otherwise.
+///
+/// `company_dir_name` reads the company files (and extra files) from another
+/// directory, like `company` or `_org`, which must be inside the repo. File
+/// names in `errors` and progress events start with it instead of
+/// `_company/`. `.slotignore` is still read from `_company`.
+///
+/// Results are cached per repo and reused for files whose modification time
+/// and size haven't changed; `refresh` discards the cache first.
+///
+/// While loading, a `company-load-progress` event with `{done, total,
+/// current}` is emitted as each company file and each engagement's logs
+/// finish, `current` naming the file or engagement. The command runs off the
+/// main thread, so the events arrive while it runs rather than after.
+// Each argument is a named parameter of the frontend's invoke call.
+#[allow(clippy::too_many_arguments)]
+#[tauri::command(async)]
+pub fn read_company_data(
+    app: tauri::AppHandle,
+    cache: tauri::State<'_, CompanyCache>,
+    config: tauri::State<'_, ViewerConfig>,
+    repo_path: String,
+    refresh: Option<bool>,
+    extra_files: Option<Vec<String>>,
+    dedupe: Option<bool>,
+    knowledge_files: Option<Vec<String>>,
+    company_dir_name: Option<String>,
+    entry_types: Option<Vec<String>>,
+    lenient_json: Option<bool>,
+) -> Result<serde_json::Value, CommandError> {
+    telemetry::timed(&app, "read_company_data", || {
+        let base = PathBuf::from(&repo_path);
+        let mut cache = cache.lock()?;
+        if refresh.unwrap_or(false) {
+            cache.remove(&base);
+        }
+        let repo = cache.entry(base.clone()).or_default();
+        let emit = |step: LoadProgress| {
+            let _ = app.emit("company-load-progress", step);
+        };
+        let options = LoadOptions {
+            extra_files: extra_files.unwrap_or_default(),
+            knowledge_files: knowledge_files.unwrap_or_default(),
+            dedupe: dedupe.unwrap_or(false),
+            company_dir_name: company_dir_name.unwrap_or_default(),
+            entry_types,
+            lenient_json: lenient_json.unwrap_or(false),
+            progress: Progress::new(&emit),
+        };
+        let result = load_company_data(&base, repo, &config, &options);
+        tracing::debug!(repo = %repo_path, reparsed = repo.reparsed.len(), "read_company_data");
+        result
+    })
+}
+
+/// How many entries each `knowledge-batch` event carries.
+const STREAM_BATCH: usize = 200;
+
+/// Emit every knowledge entry as `knowledge-batch` events (arrays of up to
+/// 200 entries), then a `knowledge-done` event with `{total, errors,
+/// warnings}`, so a repo with a huge history can be rendered as it arrives
+/// instead of after one giant response. Entries come log by log in
+/// engagement order rather than newest first, and the cache isn't used.
+#[tauri::command(async)]
+pub fn stream_knowledge(
+    app: tauri::AppHandle,
+    config: tauri::State<'_, ViewerConfig>,
+    repo_path: String,
+) -> Result<(), CommandError> {
+    telemetry::timed(&app, "stream_knowledge", || {
+        let base = PathBuf::from(&repo_path);
+        let (total, report) = stream_entries(&base, &config, STREAM_BATCH, |batch| {
+            let _ = app.emit("knowledge-batch", batch);
+        });
+        tracing::debug!(repo = %repo_path, total, "stream_knowledge");
+        app.emit(
+            "knowledge-done",
+            serde_json::json!({ "total": total, "errors": report.errors, "warnings": report.warnings }),
+        )
+        .map_err(|e| CommandError::Internal(format!("Failed to emit knowledge-done: {}", e)))
+    })
+}
+
+/// Parse each knowledge log under `base` in turn, handing its entries to
+/// `emit` in batches of at most `batch_size`. Returns the number of entries
+/// and the load errors and warnings.
+fn stream_entries(
+    base: &Path,
+    config: &ViewerConfig,
+    batch_size: usize,
+    mut emit: impl FnMut(&[knowledge::KnowledgeEntry]),
+) -> (usize, LoadReport) {
+    let mut report = LoadReport::default();
+    let mut batch = Vec::with_capacity(batch_size);
+    let mut total = 0;
+    for path in engagement_dirs(base, config) {
+        let eng_name = file_name(&path);
+        for log in knowledge::scan_knowledge_logs(&path, &[], config) {
+            let file = log.file(&eng_name);
+            let parsed = match knowledge::load_knowledge_log(&log, &eng_name, config.max_file_bytes) {
+                Ok(parsed) => parsed,
+                Err(message) => {
+                    report.errors.push(file_error(&file, message));
+                    continue;
+                }
+            };
+            for warning in parsed.warnings {
+                report.warnings.push(log_warning(&file, &eng_name, &log, warning));
+            }
+            for entry in parsed.entries {
+                batch.push(entry);
+                total += 1;
+                if batch.len() >= batch_size {
+                    emit(&batch);
+                    batch.clear();
+                }
+            }
+        }
+    }
+    if !batch.is_empty() {
+        emit(&batch);
+    }
+    (total, report)
+}
+
+/// read_company_data for each of `repo_paths`, returned as `repos`, an array
+/// of `{path, name, data}` (or `{path, name, error}` for a repo that couldn't
+/// be loaded at all), plus `knowledge`, every repo's entries merged newest
+/// first with each tagged by `repo` name. A repo's name is its folder name,
+/// with as many parent folders in front (`acme/repo`) as it takes to tell
+/// it from the others. An engagement name found in more than one repo is
+/// written `repo/engagement` in the merged timeline so the two stay apart.
+/// Runs off the main thread, taking the cache lock for one repo at a time.
+#[tauri::command(async)]
+pub fn read_company_data_multi(
+    app: tauri::AppHandle,
+    cache: tauri::State<'_, CompanyCache>,
+    config: tauri::State<'_, ViewerConfig>,
+    repo_paths: Vec<String>,
+    refresh: Option<bool>,
+) -> Result<serde_json::Value, CommandError> {
+    telemetry::timed(&app, "read_company_data_multi", || {
+        load_multi(&cache, &config, &repo_paths, refresh.unwrap_or(false))
+    })
+}
+
+fn load_multi(
+    cache: &CompanyCache,
+    config: &ViewerConfig,
+    repo_paths: &[String],
+    refresh: bool,
+) -> Result<serde_json::Value, CommandError> {
+    let bases: Vec<PathBuf> = repo_paths.iter().map(PathBuf::from).collect();
+    let names = repo_names(&bases);
+    let mut repos = Vec::new();
+    let mut loaded: Vec<(String, Vec<knowledge::KnowledgeEntry>)> = Vec::new();
+    for ((repo_path, base), name) in repo_paths.iter().zip(&bases).zip(names) {
+        let result = {
+            let mut cache = cache.lock()?;
+            if refresh {
+                cache.remove(base);
+            }
+            let repo = cache.entry(base.clone()).or_default();
+            load_company_parts(base, repo, config, &LoadOptions::default())
+        };
+        match result {
+            Ok((mut data, entries)) => {
+                let knowledge = serde_json::to_value(&entries).map_err(|e| {
+                    CommandError::Internal(format!("Failed to serialize knowledge entries: {}", e))
+                })?;
+                data.insert("knowledge".to_string(), knowledge);
+                repos.push(serde_json::json!({ "path": repo_path, "name": name, "data": data }));
+                loaded.push((name, entries));
+            }
+            Err(e) => {
+                tracing::warn!(repo = %repo_path, "{}", e);
+                repos.push(serde_json::json!({ "path": repo_path, "name": name, "error": e }));
+            }
+        }
+    }
+
+    let knowledge = merge_timelines(loaded);
+    let knowledge = serde_json::to_value(&knowledge)
+        .map_err(|e| CommandError::Internal(format!("Failed to serialize knowledge entries: {}", e)))?;
+    Ok(serde_json::json!({ "repos": repos, "knowledge": knowledge }))
+}
+
+/// A name for each of `paths`: its last component, or its last few joined
+/// with `/` where that's shared with another path, or failing that all of
+/// it. The same path given twice gets the same name.
+fn repo_names(paths: &[PathBuf]) -> Vec<String> {
+    let tail = |path: &Path, depth: usize| -> Option<String> {
+        let names: Vec<String> = path
+            .components()
+            .filter_map(|c| match c {
+                std::path::Component::Normal(name) => Some(name.to_string_lossy().into_owned()),
+                _ => None,
+            })
+            .collect();
+        (depth <= names.len()).then(|| names[names.len() - depth..].join("/"))
+    };
+    paths
+        .iter()
+        .map(|path| {
+            (1..)
+                .map_while(|depth| Some((depth, tail(path, depth)?)))
+                .find(|(depth, name)| {
+                    paths.iter().all(|other| other == path || tail(other, *depth).as_ref() != Some(name))
+                })
+                .map(|(_, name)| name)
+                .unwrap_or_else(|| path.display().to_string())
+        })
+        .collect()
+}
+
+/// Merge per-repo knowledge entries into one newest-first timeline, tagging
+/// each with its repo and prefixing engagement names that more than one repo
+/// uses.
+fn merge_timelines(
+    repos: Vec<(String, Vec<knowledge::KnowledgeEntry>)>,
+) -> Vec<knowledge::KnowledgeEntry> {
+    let mut owners: HashMap<String, HashSet<String>> = HashMap::new();
+    for (name, entries) in &repos {
+        for entry in entries {
+            owners.entry(entry.engagement.clone()).or_default().insert(name.clone());
+        }
+    }
+    let mut merged: Vec<knowledge::KnowledgeEntry> = repos
+        .into_iter()
+        .flat_map(|(name, entries)| {
+            let owners = &owners;
+            entries.into_iter().map(move |mut entry| {
+                if owners[&entry.engagement].len() > 1 {
+                    entry.engagement = format!("{}/{}", name, entry.engagement);
+                }
+                entry.repo = Some(name.clone());
+                entry
+            })
+        })
+        .collect();
+    knowledge::sort_newest_first(&mut merged);
+    merged
+}
+
+/// Knowledge entries matching `query`, newest first. Shares
+/// read_company_data's cache, so searching an already-loaded repo doesn't
+/// re-read unchanged logs.
+#[tauri::command]
+pub fn search_knowledge(
+    app: tauri::AppHandle,
+    cache: tauri::State<'_, CompanyCache>,
+    config: tauri::State<'_, ViewerConfig>,
+    repo_path: String,
+    query: knowledge::Query,
+) -> Result<Vec<knowledge::KnowledgeEntry>, CommandError> {
+    telemetry::timed(&app, "search_knowledge", || {
+        let base = PathBuf::from(&repo_path);
+        let mut cache = cache.lock()?;
+        let repo = cache.entry(base.clone()).or_default();
+        repo.reparsed.clear();
+        let entries = load_knowledge(
+            &engagement_dirs(&base, &config),
+            repo,
+            &config,
+            &[],
+            &mut LoadReport::default(),
+            &Progress::default(),
+        );
+        query.filter(entries)
+    })
+}
+
+/// Knowledge entries dated from `since` to `until` inclusive, newest first,
+/// for the timeline's date filter. Undated entries are left out unless
+/// `include_undated`, when they're returned in `undated`. See
+/// knowledge::in_date_range for the bounds.
+#[tauri::command]
+pub fn knowledge_in_range(
+    app: tauri::AppHandle,
+    cache: tauri::State<'_, CompanyCache>,
+    config: tauri::State<'_, ViewerConfig>,
+    repo_path: String,
+    since: String,
+    until: String,
+    include_undated: Option<bool>,
+) -> Result<knowledge::DateRange, CommandError> {
+    telemetry::timed(&app, "knowledge_in_range", || {
+        let base = PathBuf::from(&repo_path);
+        let mut cache = cache.lock()?;
+        let repo = cache.entry(base.clone()).or_default();
+        repo.reparsed.clear();
+        let entries = load_knowledge(
+            &engagement_dirs(&base, &config),
+            repo,
+            &config,
+            &[],
+            &mut LoadReport::default(),
+            &Progress::default(),
+        );
+        let mut range = knowledge::in_date_range(entries, &since, &until)?;
+        if !include_undated.unwrap_or(false) {
+            range.undated.clear();
+        }
+        Ok(range)
+    })
+}
+
+/// Counts for the dashboard's summary cards.
+#[derive(Debug, Default, PartialEq, serde::Serialize)]
+pub struct CompanyStats {
+    /// Engagements in engagement_registry.json.
+    pub engagements: usize,
+    /// Distinct people in the org chart, external contacts included.
+    pub people: usize,
+    pub knowledge_entries: usize,
+    pub entries_by_type: BTreeMap<String, usize>,
+    /// Keyed by `YYYY-MM`; entries without a recognized date are only
+    /// counted in `undated_entries`.
+    pub entries_by_month: BTreeMap<String, usize>,
+    pub undated_entries: usize,
+}
+
+/// Summary counts over the registry, org chart and knowledge logs, so the
+/// dashboard doesn't have to walk every entry itself. Shares
+/// read_company_data's cache; files that fail to load count as empty.
+#[tauri::command]
+pub fn get_company_stats(
+    app: tauri::AppHandle,
+    cache: tauri::State<'_, CompanyCache>,
+    config: tauri::State<'_, ViewerConfig>,
+    repo_path: String,
+) -> Result<CompanyStats, CommandError> {
+    telemetry::timed(&app, "get_company_stats", || {
+        let base = PathBuf::from(&repo_path);
+        let mut cache = cache.lock()?;
+        let repo = cache.entry(base.clone()).or_default();
+        let (data, entries) = load_company_parts(&base, repo, &config, &LoadOptions::default())?;
+        Ok(company_stats(&data, &entries))
+    })
+}
+
+fn company_stats(
+    data: &serde_json::Map<String, serde_json::Value>,
+    entries: &[knowledge::KnowledgeEntry],
+) -> CompanyStats {
+    let mut stats = CompanyStats {
+        engagements: data
+            .get("engagement_registry")
+            .and_then(|registry| registry["engagements"].as_object())
+            .map_or(0, |engagements| engagements.len()),
+        people: data.get("org_chart").map_or(0, |chart| crate::org::chart_people(chart).len()),
+        knowledge_entries: entries.len(),
+        ..Default::default()
+    };
+    for entry in entries {
+        *stats.entries_by_type.entry(entry.entry_type.clone()).or_default() += 1;
+        match entry.date_iso.as_deref().and_then(|date| date.get(..7)) {
+            Some(month) => *stats.entries_by_month.entry(month.to_string()).or_default() += 1,
+            None => stats.undated_entries += 1,
+        }
+    }
+    stats
+}
+
+/// One engagement's knowledge at a glance, for the engagement list.
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct EngagementSummary {
+    pub engagement: String,
+    pub entry_count: usize,
+    /// The newest entry's date as `YYYY-MM-DD`; None if no entry has a
+    /// recognized date.
+    pub last_entry_date: Option<String>,
+    /// Workstreams with at least one entry.
+    pub workstream_count: usize,
+}
+
+/// Entry counts per engagement, most recently active first: by latest entry
+/// date, then engagements with no dated entries, each group by name. Every
+/// engagement is listed, even one without entries. Shares read_company_data's
+/// cache.
+#[tauri::command]
+pub fn engagement_summaries(
+    app: tauri::AppHandle,
+    cache: tauri::State<'_, CompanyCache>,
+    config: tauri::State<'_, ViewerConfig>,
+    repo_path: String,
+) -> Result<Vec<EngagementSummary>, CommandError> {
+    telemetry::timed(&app, "engagement_summaries", || {
+        let base = PathBuf::from(&repo_path);
+        let engagements = engagement_dirs(&base, &config);
+        let mut cache = cache.lock()?;
+        let repo = cache.entry(base.clone()).or_default();
+        repo.reparsed.clear();
+        let entries = load_knowledge(
+            &engagements,
+            repo,
+            &config,
+            &[],
+            &mut LoadReport::default(),
+            &Progress::default(),
+        );
+        let names: Vec<String> = engagements.iter().map(|path| file_name(path)).collect();
+        Ok(summarize_engagements(&names, &entries))
+    })
+}
+
+fn summarize_engagements(
+    engagements: &[String],
+    entries: &[knowledge::KnowledgeEntry],
+) -> Vec<EngagementSummary> {
+    let mut by_engagement: BTreeMap<&str, (usize, Option<&str>, HashSet<&str>)> =
+        engagements.iter().map(|name| (name.as_str(), Default::default())).collect();
+    for entry in entries {
+        let (count, last, workstreams) = by_engagement.entry(&entry.engagement).or_default();
+        *count += 1;
+        *last = (*last).max(entry.date_iso.as_deref());
+        workstreams.insert(&entry.workstream);
+    }
+    let mut summaries: Vec<EngagementSummary> = by_engagement
+        .into_iter()
+        .map(|(engagement, (entry_count, last, workstreams))| EngagementSummary {
+            engagement: engagement.to_string(),
+            entry_count,
+            last_entry_date: last.map(str::to_string),
+            workstream_count: workstreams.len(),
+        })
+        .collect();
+    // Stable, so engagements stay in name order within a date.
+    summaries.sort_by(|a, b| b.last_entry_date.cmp(&a.last_entry_date));
+    summaries
+}
+
+/// How many entries get_knowledge_page returns when no `limit` is given.
+pub const DEFAULT_PAGE_LIMIT: usize = 100;
+
+/// A window onto the repo's knowledge entries.
+#[derive(Debug, serde::Serialize)]
+pub struct KnowledgePage {
+    pub entries: Vec<knowledge::KnowledgeEntry>,
+    /// How many entries there are in all.
+    pub total: usize,
+    pub offset: usize,
+}
+
+/// Up to `limit` (default 100) knowledge entries starting at `offset`, in
+/// `sort` order (newest first by default), for scrolling through a large
+/// history a page at a time. An offset at or past the end gives an empty
+/// page, with `total` still set, rather than an error. Shares
+/// read_company_data's cache.
+#[tauri::command]
+pub fn get_knowledge_page(
+    app: tauri::AppHandle,
+    cache: tauri::State<'_, CompanyCache>,
+    config: tauri::State<'_, ViewerConfig>,
+    repo_path: String,
+    offset: usize,
+    limit: Option<usize>,
+    sort: Option<knowledge::SortOrder>,
+) -> Result<KnowledgePage, CommandError> {
+    telemetry::timed(&app, "get_knowledge_page", || {
+        let base = PathBuf::from(&repo_path);
+        let mut cache = cache.lock()?;
+        let repo = cache.entry(base.clone()).or_default();
+        repo.reparsed.clear();
+        let mut entries = load_knowledge(
+            &engagement_dirs(&base, &config),
+            repo,
+            &config,
+            &[],
+            &mut LoadReport::default(),
+            &Progress::default(),
+        );
+        Ok(page_of(&mut entries, offset, limit.unwrap_or(DEFAULT_PAGE_LIMIT), sort.unwrap_or_default()))
+    })
+}
+
+fn page_of(
+    entries: &mut Vec<knowledge::KnowledgeEntry>,
+    offset: usize,
+    limit: usize,
+    sort: knowledge::SortOrder,
+) -> KnowledgePage {
+    // load_knowledge already returns them newest first.
+    if sort != knowledge::SortOrder::NewestFirst {
+        knowledge::sort_by_date(entries, sort);
+    }
+    let total = entries.len();
+    let start = offset.min(total);
+    let end = start.saturating_add(limit).min(total);
+    KnowledgePage { entries: entries.drain(start..end).collect(), total, offset }
+}
+
+/// Write every knowledge entry to `out_path` as CSV, newest first, and
+/// return the number of rows written (not counting the header).
+#[tauri::command]
+pub fn export_knowledge_csv(
+    app: tauri::AppHandle,
+    cache: tauri::State<'_, CompanyCache>,
+    config: tauri::State<'_, ViewerConfig>,
+    launch: tauri::State<'_, LaunchConfig>,
+    repo_path: String,
+    out_path: String,
+) -> Result<usize, CommandError> {
+    telemetry::timed(&app, "export_knowledge_csv", || {
+        export_csv(&launch, &cache, &config, &repo_path, Path::new(&out_path))
+    })
+}
+
+fn export_csv(
+    launch: &LaunchConfig,
+    cache: &CompanyCache,
+    config: &ViewerConfig,
+    repo_path: &str,
+    out_path: &Path,
+) -> Result<usize, CommandError> {
+    launch.ensure_writable("export_knowledge_csv")?;
+    write_knowledge_csv(&cached_knowledge(cache, config, repo_path)?, out_path)
+}
+
+/// Every knowledge entry of the repo at `repo_path`, through the cache.
+fn cached_knowledge(
+    cache: &CompanyCache,
+    config: &ViewerConfig,
+    repo_path: &str,
+) -> Result<Vec<knowledge::KnowledgeEntry>, CommandError> {
+    let base = PathBuf::from(repo_path);
+    let mut cache = cache.lock()?;
+    let repo = cache.entry(base.clone()).or_default();
+    repo.reparsed.clear();
+    Ok(load_knowledge(
+        &engagement_dirs(&base, config),
+        repo,
+        config,
+        &[],
+        &mut LoadReport::default(),
+        &Progress::default(),
+    ))
+}
+
+fn write_knowledge_csv(
+    entries: &[knowledge::KnowledgeEntry],
+    out_path: &Path,
+) -> Result<usize, CommandError> {
+    let fail = |e: csv::Error| CommandError::Io(format!("Failed to write {}: {}", out_path.display(), e));
+    let mut writer = csv::Writer::from_path(out_path).map_err(fail)?;
+    writer
+        .write_record(["engagement", "workstream", "date", "type", "summary", "detail", "source"])
+        .map_err(fail)?;
+    for entry in entries {
+        writer
+            .write_record([
+                &entry.engagement,
+                &entry.workstream,
+                &entry.date,
+                &entry.entry_type,
+                &entry.summary,
+                &entry.detail,
+                &entry.source,
+            ])
+            .map_err(fail)?;
+    }
+    writer
+        .flush()
+        .map_err(|e| CommandError::io(format_args!("Failed to write {}", out_path.display()), &e))?;
+    Ok(entries.len())
+}
+
+/// Write the knowledge entries matching `query` to `out_path` as one
+/// Markdown knowledge log, newest date first, for pasting into a status
+/// report, and return how many were written. Each date gets one `## `
+/// heading; see knowledge::render_log. Parsing the file gives the same
+/// entries back, less which engagement and workstream they came from.
+#[tauri::command]
+pub fn export_knowledge_markdown(
+    app: tauri::AppHandle,
+    cache: tauri::State<'_, CompanyCache>,
+    config: tauri::State<'_, ViewerConfig>,
+    launch: tauri::State<'_, LaunchConfig>,
+    repo_path: String,
+    query: knowledge::Query,
+    out_path: String,
+) -> Result<usize, CommandError> {
+    telemetry::timed(&app, "export_knowledge_markdown", || {
+        export_markdown(&launch, &cache, &config, &repo_path, query, Path::new(&out_path))
+    })
+}
+
+fn export_markdown(
+    launch: &LaunchConfig,
+    cache: &CompanyCache,
+    config: &ViewerConfig,
+    repo_path: &str,
+    query: knowledge::Query,
+    out_path: &Path,
+) -> Result<usize, CommandError> {
+    launch.ensure_writable("export_knowledge_markdown")?;
+    let entries = query.filter(cached_knowledge(cache, config, repo_path)?)?;
+    write_knowledge_markdown(&entries, out_path)
+}
+
+fn write_knowledge_markdown(
+    entries: &[knowledge::KnowledgeEntry],
+    out_path: &Path,
+) -> Result<usize, CommandError> {
+    config::write_atomic(out_path, &knowledge::render_log("", entries)?)?;
+    Ok(entries.len())
+}
+
+/// The `schema_version` export_company_bundle writes and read_company_bundle
+/// reads. Bump it when the bundle's shape changes.
+const BUNDLE_SCHEMA_VERSION: u64 = 1;
+
+/// Keys a bundle carries over from read_company_data's result, besides the
+/// COMPANY_FILES keys.
+const BUNDLE_KEYS: [&str; 4] = ["knowledge", "errors", "warnings", "_meta"];
+
+/// Write a snapshot of the repo to `out_path`: one JSON document holding
+/// `schema_version`, `exported_at`, the `repo` it was taken from, and what
+/// read_company_data returns for the five company files, `knowledge`,
+/// `errors`, `warnings` and `_meta`. read_company_bundle opens it again.
+/// Shares read_company_data's cache. Returns the number of knowledge entries.
+#[tauri::command]
+pub fn export_company_bundle(
+    app: tauri::AppHandle,
+    cache: tauri::State<'_, CompanyCache>,
+    config: tauri::State<'_, ViewerConfig>,
+    launch: tauri::State<'_, LaunchConfig>,
+    repo_path: String,
+    out_path: String,
+) -> Result<usize, CommandError> {
+    telemetry::timed(&app, "export_company_bundle", || {
+        export_bundle(&launch, &cache, &config, &repo_path, Path::new(&out_path))
+    })
+}
+
+fn export_bundle(
+    launch: &LaunchConfig,
+    cache: &CompanyCache,
+    config: &ViewerConfig,
+    repo_path: &str,
+    out_path: &Path,
+) -> Result<usize, CommandError> {
+    launch.ensure_writable("export_company_bundle")?;
+    let base = PathBuf::from(repo_path);
+    let data = {
+        let mut cache = cache.lock()?;
+        let repo = cache.entry(base.clone()).or_default();
+        load_company_data(&base, repo, config, &LoadOptions::default())?
+    };
+    write_bundle(data, repo_path, out_path)
+}
+
+fn write_bundle(data: serde_json::Value, repo: &str, out_path: &Path) -> Result<usize, CommandError> {
+    let serde_json::Value::Object(mut data) = data else {
+        return Err(CommandError::Internal("Company data is not an object".to_string()));
+    };
+    let mut bundle = serde_json::Map::new();
+    bundle.insert("schema_version".to_string(), BUNDLE_SCHEMA_VERSION.into());
+    bundle.insert("exported_at".to_string(), chrono::Local::now().to_rfc3339().into());
+    bundle.insert("repo".to_string(), repo.into());
+    for key in COMPANY_FILES.iter().map(|(key, _)| *key).chain(BUNDLE_KEYS) {
+        bundle.insert(key.to_string(), data.remove(key).unwrap_or_default());
+    }
+    let entries = bundle["knowledge"].as_array().map_or(0, Vec::len);
+    let contents = serde_json::to_string(&bundle)
+        .map_err(|e| CommandError::Internal(format!("Failed to serialize bundle: {}", e)))?;
+    config::write_atomic(out_path, &contents)?;
+    tracing::info!(repo, out = %out_path.display(), entries, "exported company bundle");
+    Ok(entries)
+}
+
+/// Open a bundle written by export_company_bundle, in the shape
+/// read_company_data returns, so a snapshot can be viewed like a live repo.
+/// Bundles from a newer viewer (a higher `schema_version`) are refused.
+#[tauri::command]
+pub fn read_company_bundle(
+    app: tauri::AppHandle,
+    config: tauri::State<'_, ViewerConfig>,
+    path: String,
+) -> Result<serde_json::Value, CommandError> {
+    telemetry::timed(&app, "read_company_bundle", || {
+        read_bundle(Path::new(&path), &config)
+    })
+}
+
+fn read_bundle(path: &Path, config: &ViewerConfig) -> Result<serde_json::Value, CommandError> {
+    let name = path.display().to_string();
+    let content = config::read_capped(path, &name, config.max_file_bytes)?;
+    let bundle: serde_json::Value = serde_json::from_str(strip_bom(&content))
+        .map_err(|e| CommandError::Parse(format!("Failed to parse {}: {}", name, e)))?;
+    let serde_json::Value::Object(mut bundle) = bundle else {
+        return Err(CommandError::Parse(format!("{} is not a company bundle", name)));
+    };
+    match bundle.get("schema_version").and_then(|v| v.as_u64()) {
+        Some(BUNDLE_SCHEMA_VERSION) => {}
+        Some(version) => {
+            return Err(CommandError::InvalidInput(format!(
+                "{} has schema version {}; this viewer reads version {}",
+                name, version, BUNDLE_SCHEMA_VERSION
+            )))
+        }
+        None => return Err(CommandError::Parse(format!("{} is not a company bundle", name))),
+    }
+    let mut data = serde_json::Map::new();
+    for (key, _) in COMPANY_FILES {
+        data.insert(key.to_string(), bundle.remove(key).unwrap_or_default());
+    }
+    for key in BUNDLE_KEYS {
+        let empty = if key == "_meta" { serde_json::json!({}) } else { serde_json::json!([]) };
+        data.insert(key.to_string(), bundle.remove(key).unwrap_or(empty));
+    }
+    Ok(serde_json::Value::Object(data))
+}
+
+/// Whether something in a SnapshotDiff was added, removed or changed going
+/// from the first snapshot to the second.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Change {
+    Added,
+    Removed,
+    Modified,
+}
+
+/// What changed between two snapshots of a repo, as diff_company_snapshots
+/// reports it. Everything is sorted, engagements and files by name and
+/// entries by engagement, date and summary.
+#[derive(Debug, Default, PartialEq, serde::Serialize)]
+pub struct SnapshotDiff {
+    pub engagements: Vec<EngagementChange>,
+    pub entries_added: Vec<EntryRef>,
+    pub entries_removed: Vec<EntryRef>,
+    pub files: Vec<FileChange>,
+}
+
+/// An engagement that appears, disappears, or has a changed registry entry
+/// or knowledge entries.
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct EngagementChange {
+    pub engagement: String,
+    pub change: Change,
+    pub entries_added: usize,
+    pub entries_removed: usize,
+    /// Whether its entry in engagement_registry.json differs.
+    pub registry_changed: bool,
+}
+
+/// A knowledge entry, by the engagement, date and summary that identify it
+/// across snapshots, with its type and workstream for display.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, serde::Serialize)]
+pub struct EntryRef {
+    pub engagement: String,
+    pub date: String,
+    pub summary: String,
+    #[serde(rename = "type")]
+    pub entry_type: String,
+    pub workstream: String,
+}
+
+/// A company file (by its COMPANY_FILES key) that differs. A file that was
+/// there both times lists its top-level keys that changed in `sections`;
+/// that's empty if the file isn't an object.
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct FileChange {
+    pub file: String,
+    pub change: Change,
+    pub sections: Vec<SectionChange>,
+}
+
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct SectionChange {
+    pub section: String,
+    pub change: Change,
+}
+
+/// What changed from snapshot `a_path` to snapshot `b_path`. Each is a
+/// bundle from export_company_bundle, a repo folder, or a zipped repo (see
+/// read_company_data_zip). Knowledge entries match up by engagement, date
+/// and summary, so an entry whose detail was edited counts as unchanged.
+/// Runs off the main thread, since it reads both snapshots in full.
+#[tauri::command(async)]
+pub fn diff_company_snapshots(
+    app: tauri::AppHandle,
+    config: tauri::State<'_, ViewerConfig>,
+    a_path: String,
+    b_path: String,
+) -> Result<SnapshotDiff, CommandError> {
+    telemetry::timed(&app, "diff_company_snapshots", || {
+        let a = load_snapshot(Path::new(&a_path), &config)?;
+        let b = load_snapshot(Path::new(&b_path), &config)?;
+        Ok(diff_snapshots(&a, &b))
+    })
+}
+
+/// A snapshot in read_company_data's shape, loaded fresh (not through the
+/// cache) when it's a repo.
+fn load_snapshot(path: &Path, config: &ViewerConfig) -> Result<serde_json::Value, CommandError> {
+    if path.is_dir() {
+        load_company_data(path, &mut RepoCache::default(), config, &LoadOptions::default())
+    } else if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("zip")) {
+        crate::archive::read_zip(path, config)
+    } else {
+        read_bundle(path, config)
+    }
+}
+
+fn diff_snapshots(a: &serde_json::Value, b: &serde_json::Value) -> SnapshotDiff {
+    let entries = |data: &serde_json::Value| -> BTreeSet<EntryRef> {
+        let text = |entry: &serde_json::Value, key: &str| entry[key].as_str().unwrap_or_default().to_string();
+        data["knowledge"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|entry| EntryRef {
+                engagement: text(entry, "engagement"),
+                date: entry["date_iso"].as_str().map_or_else(|| text(entry, "date"), str::to_string),
+                summary: text(entry, "summary"),
+                entry_type: text(entry, "type"),
+                workstream: text(entry, "workstream"),
+            })
+            .collect()
+    };
+    // Entries differing only in type or workstream are the same entry.
+    let ids = |entries: &BTreeSet<EntryRef>| -> HashSet<(String, String, String)> {
+        entries.iter().map(|e| (e.engagement.clone(), e.date.clone(), e.summary.clone())).collect()
+    };
+    let (a_entries, b_entries) = (entries(a), entries(b));
+    let (a_ids, b_ids) = (ids(&a_entries), ids(&b_entries));
+    let id = |e: &EntryRef| (e.engagement.clone(), e.date.clone(), e.summary.clone());
+    let entries_added: Vec<EntryRef> = b_entries.iter().filter(|e| !a_ids.contains(&id(e))).cloned().collect();
+    let entries_removed: Vec<EntryRef> = a_entries.iter().filter(|e| !b_ids.contains(&id(e))).cloned().collect();
+
+    let registry = |data: &serde_json::Value| data["engagement_registry"]["engagements"].as_object().cloned();
+    let (a_registry, b_registry) = (registry(a).unwrap_or_default(), registry(b).unwrap_or_default());
+    let names = |registry: &serde_json::Map<String, serde_json::Value>, entries: &BTreeSet<EntryRef>| {
+        let in_entries = entries.iter().map(|e| e.engagement.clone());
+        registry.keys().cloned().chain(in_entries).collect::<HashSet<String>>()
+    };
+    let (a_names, b_names) = (names(&a_registry, &a_entries), names(&b_registry, &b_entries));
+    let mut all: Vec<&String> = a_names.union(&b_names).collect();
+    all.sort();
+    let engagements = all
+        .into_iter()
+        .filter_map(|name| {
+            let count = |entries: &[EntryRef]| entries.iter().filter(|e| &e.engagement == name).count();
+            let (added, removed) = (count(&entries_added), count(&entries_removed));
+            let registry_changed = a_registry.get(name) != b_registry.get(name);
+            let change = match (a_names.contains(name), b_names.contains(name)) {
+                (false, _) => Change::Added,
+                (_, false) => Change::Removed,
+                _ if added > 0 || removed > 0 || registry_changed => Change::Modified,
+                _ => return None,
+            };
+            Some(EngagementChange {
+                engagement: name.clone(),
+                change,
+                entries_added: added,
+                entries_removed: removed,
+                registry_changed,
+            })
+        })
+        .collect();
+
+    let files = COMPANY_FILES
+        .iter()
+        .filter_map(|(key, _)| {
+            let (a_file, b_file) = (&a[*key], &b[*key]);
+            let change = match (a_file.is_null(), b_file.is_null()) {
+                (true, true) => return None,
+                (true, false) => Change::Added,
+                (false, true) => Change::Removed,
+                _ if a_file == b_file => return None,
+                _ => Change::Modified,
+            };
+            let sections = match (a_file, b_file) {
+                (serde_json::Value::Object(a_file), serde_json::Value::Object(b_file)) => {
+                    let mut keys: Vec<&String> = a_file.keys().chain(b_file.keys()).collect();
+                    keys.sort();
+                    keys.dedup();
+                    keys.into_iter()
+                        .filter_map(|section| {
+                            let change = match (a_file.get(section), b_file.get(section)) {
+                                (None, _) => Change::Added,
+                                (_, None) => Change::Removed,
+                                (Some(a), Some(b)) if a != b => Change::Modified,
+                                _ => return None,
+                            };
+                            Some(SectionChange { section: section.clone(), change })
+                        })
+                        .collect()
+                }
+                _ => Vec::new(),
+            };
+            Some(FileChange { file: key.to_string(), change, sections })
+        })
+        .collect();
+
+    SnapshotDiff { engagements, entries_added, entries_removed, files }
+}
+
+/// Just `_company/file_index.json` (or its `.gz`/YAML form), parsed with
+/// read_company_data's size limit, for panels that don't need the rest of the
+/// repo loaded. Null if the repo has no file index.
+#[tauri::command]
+pub fn get_file_index(
+    app: tauri::AppHandle,
+    config: tauri::State<'_, ViewerConfig>,
+    repo_path: String,
+) -> Result<serde_json::Value, CommandError> {
+    telemetry::timed(&app, "get_file_index", || {
+        read_file_index(Path::new(&repo_path), &config)
+    })
+}
+
+fn read_file_index(base: &Path, config: &ViewerConfig) -> Result<serde_json::Value, CommandError> {
+    let company_dir = company_dir(base)?;
+    match find_company_file(&company_dir, "file_index.json") {
+        Some(path) => parse_company_file(&path, &file_name(&path), config.max_file_bytes),
+        None => Ok(serde_json::Value::Null),
+    }
+}
+
+/// Replace one of COMPANY_FILES (`key` is its key in read_company_data's
+/// result) with `value`, pretty-printed, after copying the current file to
+/// `<file>.bak`. A YAML file stays YAML, a missing file is created as JSON,
+/// and a gzipped file is refused.
+#[tauri::command]
+pub fn write_company_file(
+    app: tauri::AppHandle,
+    cache: tauri::State<'_, CompanyCache>,
+    launch: tauri::State<'_, LaunchConfig>,
+    repo_path: String,
+    key: String,
+    value: serde_json::Value,
+) -> Result<(), CommandError> {
+    telemetry::timed(&app, "write_company_file", || write_company(&launch, &cache, &repo_path, &key, &value))
+}
+
+fn write_company(
+    launch: &LaunchConfig,
+    cache: &CompanyCache,
+    repo_path: &str,
+    key: &str,
+    value: &serde_json::Value,
+) -> Result<(), CommandError> {
+    launch.ensure_writable("write_company_file")?;
+    let base = PathBuf::from(repo_path);
+    save_company_file(&base, key, value)?;
+    // The stamp check would catch the change, but not one made within the
+    // same timestamp tick at the same size.
+    cache.lock()?.remove(&base);
+    Ok(())
+}
+
+pub fn save_company_file(
+    base: &Path,
+    key: &str,
+    value: &serde_json::Value,
+) -> Result<(), CommandError> {
+    let filename = COMPANY_FILES
+        .iter()
+        .find(|(k, _)| *k == key)
+        .map(|(_, filename)| *filename)
+        .ok_or_else(|| CommandError::InvalidInput(format!("Unknown company file: {}", key)))?;
+    let company_dir = company_dir(base)?;
+    let path = find_company_file(&company_dir, filename).unwrap_or(company_dir.join(filename));
+    let name = file_name(&path);
+
+    let extension = path.extension().unwrap_or_default().to_ascii_lowercase();
+    if extension == "gz" {
+        let message = format!("Refusing to write {}: compressed files are read-only", name);
+        return Err(CommandError::InvalidInput(message));
+    }
+    let is_yaml = extension != "json";
+    let (contents, reparsed) = if is_yaml {
+        let contents = serde_yaml::to_string(value)
+            .map_err(|e| CommandError::InvalidInput(format!("Failed to serialize {}: {}", name, e)))?;
+        let reparsed = serde_yaml::from_str::<serde_json::Value>(&contents).ok();
+        (contents, reparsed)
+    } else {
+        let contents = serde_json::to_string_pretty(value)
+            .map_err(|e| CommandError::InvalidInput(format!("Failed to serialize {}: {}", name, e)))? + "\n";
+        let reparsed = serde_json::from_str::<serde_json::Value>(&contents).ok();
+        (contents, reparsed)
+    };
+    if reparsed.as_ref() != Some(value) {
+        let message = format!("Refusing to write {}: contents don't read back unchanged", name);
+        return Err(CommandError::InvalidInput(message));
+    }
+
+    if path.exists() {
+        let backup = path.with_file_name(format!("{}.bak", name));
+        std::fs::copy(&path, &backup)
+            .map_err(|e| CommandError::io(format_args!("Failed to back up {}", name), &e))?;
+    }
+    config::write_atomic(&path, &contents)?;
+    tracing::info!(file = %name, "wrote company file");
+    Ok(())
+}
+
+/// read_company_data's optional extras.
+#[derive(Default)]
+struct LoadOptions<'a> {
+    extra_files: Vec<String>,
+    /// Knowledge log file names; empty for the default.
+    knowledge_files: Vec<String>,
+    dedupe: bool,
+    /// The company directory's name; empty for `_company`.
+    company_dir_name: String,
+    /// Allowed entry types; None for company_config.json's `entry_types`.
+    entry_types: Option<Vec<String>>,
+    lenient_json: bool,
+    progress: Progress<'a>,
+}
+
+/// How far a read_company_data load has got.
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub struct LoadProgress {
+    pub done: usize,
+    pub total: usize,
+    /// The company file (`_company/org_chart.json`) or engagement just done.
+    pub current: String,
+}
+
+/// Counts a load's steps, one per company file and one per engagement, and
+/// reports each as it finishes. The default reports nothing.
+#[derive(Default)]
+struct Progress<'a> {
+    report: Option<&'a (dyn Fn(LoadProgress) + Sync)>,
+    /// (done, total); locked while reporting so `done` arrives in order.
+    counts: Mutex<(usize, usize)>,
+}
+
+impl<'a> Progress<'a> {
+    fn new(report: &'a (dyn Fn(LoadProgress) + Sync)) -> Self {
+        Progress { report: Some(report), counts: Mutex::default() }
+    }
+
+    fn add_total(&self, steps: usize) {
+        self.counts.lock().unwrap_or_else(PoisonError::into_inner).1 += steps;
+    }
+
+    fn step(&self, current: &str) {
+        let Some(report) = self.report else {
+            return;
+        };
+        let mut counts = self.counts.lock().unwrap_or_else(PoisonError::into_inner);
+        counts.0 += 1;
+        report(LoadProgress { done: counts.0, total: counts.1, current: current.to_string() });
+    }
+}
+
+fn load_company_data(
+    base: &Path,
+    repo: &mut RepoCache,
+    config: &ViewerConfig,
+    options: &LoadOptions,
+) -> Result<serde_json::Value, CommandError> {
+    let (mut result, mut knowledge_entries) = load_company_parts(base, repo, config, options)?;
+    if options.dedupe {
+        knowledge_entries = knowledge::dedupe(knowledge_entries);
+    }
+    let knowledge = serde_json::to_value(&knowledge_entries)
+        .map_err(|e| CommandError::Internal(format!("Failed to serialize knowledge entries: {}", e)))?;
+    result.insert("knowledge".to_string(), knowledge);
+    Ok(serde_json::Value::Object(result))
+}
+
+/// load_company_data's result without `knowledge`, and the entries that go
+/// there.
+fn load_company_parts(
+    base: &Path,
+    repo: &mut RepoCache,
+    config: &ViewerConfig,
+    options: &LoadOptions,
+) -> Result<(serde_json::Map<String, serde_json::Value>, Vec<knowledge::KnowledgeEntry>), CommandError> {
+    let dir_name = match options.company_dir_name.as_str() {
+        "" => DEFAULT_COMPANY_DIR,
+        name => name.trim_end_matches(['/', '\\']),
+    };
+    let company_dir = company_dir_named(base, dir_name)?;
+    let extras = find_extra_files(&company_dir, &options.extra_files)?;
+    let progress = &options.progress;
+
+    let mut old_files = std::mem::take(&mut repo.files);
+    if repo.lenient_json != options.lenient_json {
+        // A file's parse depends on the mode, so none can be reused.
+        old_files.clear();
+        repo.lenient_json = options.lenient_json;
+    }
+    repo.reparsed.clear();
+
+    let mut result = serde_json::Map::new();
+    let mut report = LoadReport::default();
+
+    let found: Vec<(String, Option<PathBuf>)> = COMPANY_FILES
+        .iter()
+        .map(|(key, filename)| (key.to_string(), find_company_file(&company_dir, filename)))
+        .chain(extras)
+        .collect();
+    let paths: Vec<PathBuf> = found.iter().filter_map(|(_, path)| path.clone()).collect();
+    let engagements = engagement_dirs(base, config);
+    progress.add_total(paths.len() + engagements.len());
+    let mut parsed = cached_all(
+        &mut old_files,
+        &mut repo.files,
+        &mut repo.reparsed,
+        &paths,
+        |i| parse_file(&paths[i], &file_name(&paths[i]), config.max_file_bytes, options.lenient_json),
+        |i| progress.step(&format!("{}/{}", dir_name, file_name(&paths[i]))),
+    )
+    .into_iter();
+
+    for (key, path) in found {
+        let Some(path) = path else {
+            result.insert(key.clone(), serde_json::Value::Null);
+            report.meta.insert(key, serde_json::Value::Null);
+            continue;
+        };
+        // parsed has one result per found path, in order.
+        let missing = ParsedFile { value: serde_json::Value::Null, lenient: false };
+        let (stamp, value) = parsed.next().unwrap_or((None, Ok(missing)));
+        let file = format!("{}/{}", dir_name, file_name(&path));
+        let value = match value {
+            Ok(ParsedFile { value, lenient }) => {
+                if lenient {
+                    let message = "parsed leniently: it has comments or trailing commas, which JSON \
+                                   doesn't allow";
+                    report.warnings.push(serde_json::json!({ "file": file, "message": message }));
+                }
+                value
+            }
+            Err(message) => {
+                report.errors.push(file_error(&file, message));
+                serde_json::Value::Null
+            }
+        };
+        result.insert(key.clone(), value);
+        let meta = stamp.map_or(serde_json::Value::Null, |stamp| stamp.to_meta(&path));
+        report.meta.insert(key, meta);
+    }
+
+    let knowledge_entries =
+        load_knowledge(&engagements, repo, config, &options.knowledge_files, &mut report, progress);
+    let allowed = match &options.entry_types {
+        Some(types) => types.clone(),
+        None => result["company_config"]["entry_types"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|entry_type| entry_type.as_str().map(str::to_string))
+            .collect(),
+    };
+    report.warnings.extend(type_warnings(&knowledge_entries, &allowed, &engagements));
+    result.insert("errors".to_string(), serde_json::Value::Array(report.errors));
+    result.insert("warnings".to_string(), serde_json::Value::Array(report.warnings));
+    result.insert("_meta".to_string(), serde_json::Value::Object(report.meta));
+
+    Ok((result, knowledge_entries))
+}
+
+/// The `errors` and `warnings` read_company_data would return for `base`,
+/// from a fresh load that leaves the cache alone.
+pub fn load_problems(
+    base: &Path,
+    config: &ViewerConfig,
+) -> Result<(Vec<serde_json::Value>, Vec<serde_json::Value>), CommandError> {
+    let (mut data, _) =
+        load_company_parts(base, &mut RepoCache::default(), config, &LoadOptions::default())?;
+    let mut take = |key: &str| match data.remove(key) {
+        Some(serde_json::Value::Array(items)) => items,
+        _ => Vec::new(),
+    };
+    Ok((take("errors"), take("warnings")))
+}
+
+/// Keys load_company_data sets itself, which an extra file can't take.
+const RESERVED_KEYS: [&str; 4] = ["knowledge", "errors", "warnings", "_meta"];
+
+/// The `_company` files named by `patterns`, as (stem, path). A pattern
+/// without wildcards names one file, which gets a null entry if it doesn't
+/// exist; a wildcard pattern matches only JSON and YAML files. The first file
+/// to claim a stem wins, and stems of the standard keys are never claimed.
+/// Patterns must be plain file names, and a match that links outside
+/// `_company` is skipped.
+fn find_extra_files(
+    company_dir: &Path,
+    patterns: &[String],
+) -> Result<Vec<(String, Option<PathBuf>)>, CommandError> {
+    let mut names: Vec<String> = std::fs::read_dir(company_dir)
+        .map_err(|e| CommandError::io(format_args!("Failed to read {}", company_dir.display()), &e))?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_file())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .collect();
+    names.sort();
+
+    let mut found: Vec<(String, Option<PathBuf>)> = Vec::new();
+    let mut claim = |name: &str, path: Option<PathBuf>| {
+        let stem = Path::new(name).file_stem().unwrap_or_default().to_string_lossy().to_string();
+        let taken = COMPANY_FILES.iter().any(|(key, _)| *key == stem)
+            || RESERVED_KEYS.contains(&stem.as_str())
+            || found.iter().any(|(key, _)| *key == stem);
+        if !taken {
+            found.push((stem, path));
+        }
+    };
+    for pattern in patterns {
+        let pattern = paths::plain_name(pattern)?;
+        let wild = pattern.contains(['*', '?']);
+        let matches: Vec<&String> = names
+            .iter()
+            .filter(|name| {
+                if wild {
+                    is_data_file(name) && wildcard_match(pattern, name)
+                } else {
+                    *name == pattern
+                }
+            })
+            .collect();
+        if !wild && matches.is_empty() {
+            claim(pattern, None);
+        }
+        for name in matches {
+            match paths::resolve_within(company_dir, name) {
+                Ok(_) => claim(name, Some(company_dir.join(name))),
+                Err(e) => tracing::warn!("skipping _company/{}: {}", name, e),
+            }
+        }
+    }
+    Ok(found)
+}
+
+fn is_data_file(name: &str) -> bool {
+    let ext = Path::new(name).extension().unwrap_or_default();
+    ["json", "yaml", "yml"].iter().any(|e| ext.eq_ignore_ascii_case(e))
+}
+
+/// Whether `name` matches `pattern`, where `*` stands for any run of
+/// characters and `?` for exactly one.
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    // Greedy with backtracking to the most recent `*`.
+    let (mut p, mut n) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    p = star_p + 1;
+                    n = star_n + 1;
+                    star = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// The repo's `_company` directory, or an error saying why there isn't one.
+pub fn company_dir(base: &Path) -> Result<PathBuf, CommandError> {
+    company_dir_named(base, DEFAULT_COMPANY_DIR)
+}
+
+/// company_dir for a repo that keeps its company files in `name`, which may
+/// not lead outside the repo.
+pub fn company_dir_named(base: &Path, name: &str) -> Result<PathBuf, CommandError> {
+    let company_dir = base.join(name);
+    if company_dir.is_dir() {
+        // Checked once it exists, since resolving follows links.
+        paths::resolve_within(base, name)?;
+        Ok(company_dir)
+    } else if company_dir.exists() {
+        let message = format!("{} exists but is not a directory: {}", name, company_dir.display());
+        Err(CommandError::InvalidInput(message))
+    } else {
+        Err(CommandError::NotFound(format!("No {} directory found at {}", name, base.display())))
+    }
+}
+
+/// Every engagement's knowledge entries, newest first, going through the same
+/// cache as read_company_data. Logs are found by `log_names` (see
+/// knowledge::scan_knowledge_logs). Load errors, parse warnings and each log's
+/// metadata go in `report`, and `progress` steps as each engagement's last
+/// log is parsed.
+fn load_knowledge(
+    engagements: &[PathBuf],
+    repo: &mut RepoCache,
+    config: &ViewerConfig,
+    log_names: &[String],
+    report: &mut LoadReport,
+    progress: &Progress,
+) -> Vec<knowledge::KnowledgeEntry> {
+    let mut old_logs = std::mem::take(&mut repo.logs);
+    let names: Vec<String> = engagements.iter().map(|path| file_name(path)).collect();
+    // (engagement index, log), in engagement order.
+    let logs: Vec<(usize, knowledge::KnowledgeLog)> = engagements
+        .par_iter()
+        .enumerate()
+        .flat_map_iter(|(eng, path)| {
+            knowledge::scan_knowledge_logs(path, log_names, config)
+                .into_iter()
+                .map(move |log| (eng, log))
+        })
+        .collect();
+
+    let remaining: Vec<AtomicUsize> = engagements.iter().map(|_| AtomicUsize::new(0)).collect();
+    for (eng, _) in &logs {
+        remaining[*eng].fetch_add(1, Ordering::Relaxed);
+    }
+    for (eng, left) in remaining.iter().enumerate() {
+        if left.load(Ordering::Relaxed) == 0 {
+            progress.step(&names[eng]);
+        }
+    }
+
+    let paths: Vec<PathBuf> = logs.iter().map(|(_, log)| log.path.clone()).collect();
+    let parsed = cached_all(
+        &mut old_logs,
+        &mut repo.logs,
+        &mut repo.reparsed,
+        &paths,
+        |i| {
+            let (eng, log) = &logs[i];
+            knowledge::load_knowledge_log(log, &names[*eng], config.max_file_bytes)
+        },
+        |i| {
+            let eng = logs[i].0;
+            if remaining[eng].fetch_sub(1, Ordering::AcqRel) == 1 {
+                progress.step(&names[eng]);
+            }
+        },
+    );
+
+    let mut knowledge_entries = Vec::new();
+    for ((eng, log), (stamp, parsed)) in logs.iter().zip(parsed) {
+        let eng_name = &names[*eng];
+        let file = log.file(eng_name);
+        let meta = stamp.map_or(serde_json::Value::Null, |stamp| stamp.to_meta(&log.path));
+        report.meta.insert(file.clone(), meta);
+        match parsed {
+            Ok(parsed) => {
+                for warning in parsed.warnings {
+                    report.warnings.push(log_warning(&file, eng_name, log, warning));
+                }
+                knowledge_entries.extend(parsed.entries);
+            }
+            Err(message) => report.errors.push(file_error(&file, message)),
+        }
+    }
+    knowledge::sort_newest_first(&mut knowledge_entries);
+    knowledge_entries
+}
+
+/// A knowledge log's parse warning as it goes in `warnings`: `{file,
+/// engagement, workstream, line, message}`.
+pub fn log_warning(
+    file: &str,
+    engagement: &str,
+    log: &knowledge::KnowledgeLog,
+    warning: knowledge::LogWarning,
+) -> serde_json::Value {
+    serde_json::json!({
+        "file": file,
+        "engagement": engagement,
+        "workstream": log.workstream,
+        "line": warning.line,
+        "message": warning.message,
+    })
+}
+
+/// A warning, shaped like log_warning's, for each entry whose type isn't one
+/// of `allowed` (ignoring case). Untyped entries pass, and so does everything
+/// when `allowed` is empty.
+fn type_warnings(
+    entries: &[knowledge::KnowledgeEntry],
+    allowed: &[String],
+    engagements: &[PathBuf],
+) -> Vec<serde_json::Value> {
+    if allowed.is_empty() {
+        return Vec::new();
+    }
+    let allowed: HashSet<String> = allowed.iter().map(|t| t.trim().to_uppercase()).collect();
+    entries
+        .iter()
+        .filter(|entry| !entry.entry_type.is_empty() && !allowed.contains(&entry.entry_type))
+        .map(|entry| {
+            // `engagement/relative`, as log_warning names the log.
+            let relative = engagements
+                .iter()
+                .filter(|dir| file_name(dir) == entry.engagement)
+                .find_map(|dir| entry.source_file.strip_prefix(dir).ok());
+            let file = match relative {
+                Some(relative) => {
+                    let parts: Vec<_> = relative.iter().map(|part| part.to_string_lossy()).collect();
+                    format!("{}/{}", entry.engagement, parts.join("/"))
+                }
+                None => entry.source_file.display().to_string(),
+            };
+            serde_json::json!({
+                "file": file,
+                "engagement": entry.engagement,
+                "workstream": entry.workstream,
+                "line": entry.source_line,
+                "message": format!("entry type \"{}\" is not one of the allowed types", entry.entry_type),
+            })
+        })
+        .collect()
+}
+
+/// Engagement directories: folders under `base` that contain an
+/// `engagement_config.json`, looked for up to `max_depth` levels down and
+/// sorted by path. Ignored directories (`.git`, `node_modules`, ...) and
+/// folders matching `_company/.slotignore` aren't entered, and neither are
+/// the engagements themselves.
+pub fn engagement_dirs(base: &Path, config: &ViewerConfig) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    let ignore = config::SlotIgnore::load(base);
+    let links = paths::Symlinks::new(base, config.follow_symlinks);
+    find_engagements(base, "", config.max_scan_depth, &ignore, &links, &mut dirs);
+    dirs.sort();
+    dirs
+}
+
+fn find_engagements(
+    dir: &Path,
+    relative: &str,
+    depth: usize,
+    ignore: &config::SlotIgnore,
+    links: &paths::Symlinks,
+    dirs: &mut Vec<PathBuf>,
+) {
+    if depth == 0 {
+        return;
+    }
+    if let Ok(entries) = std::fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+            if config::is_ignored_dir(&name) || !links.is_dir(dir, &entry) {
+                continue;
+            }
+            let relative = if relative.is_empty() { name } else { format!("{}/{}", relative, name) };
+            if ignore.is_ignored(&relative) {
+                continue;
+            }
+            if path.join("engagement_config.json").is_file() {
+                dirs.push(path);
+            } else {
+                find_engagements(&path, &relative, depth - 1, ignore, links, dirs);
+            }
+        }
+    }
+}
+
+pub fn file_name(path: &Path) -> String {
+    path.file_name().unwrap_or_default().to_string_lossy().to_string()
+}
+
+/// The file to read for one of COMPANY_FILES: the `.json` if it exists,
+/// otherwise a gzipped `.json.gz`, otherwise a `.yaml` or `.yml` with the same
+/// stem. JSON wins if both exist.
+pub fn find_company_file(company_dir: &Path, filename: &str) -> Option<PathBuf> {
+    company_file_names(filename)
+        .into_iter()
+        .map(|name| company_dir.join(name))
+        .find(|path| path.is_file())
+}
+
+/// The names find_company_file tries for `filename`, in order.
+pub fn company_file_names(filename: &str) -> Vec<String> {
+    let stem = filename.trim_end_matches(".json");
+    let mut names = vec![filename.to_string()];
+    names.extend(["json.gz", "yaml", "yml"].iter().map(|ext| format!("{}.{}", stem, ext)));
+    names
+}
+
+/// Parse a company file as JSON, or as YAML if the extension says so.
+pub fn parse_company_file(
+    path: &Path,
+    filename: &str,
+    max_bytes: u64,
+) -> Result<serde_json::Value, CommandError> {
+    let content = config::read_capped(path, filename, max_bytes)?;
+    parse_company_text(&content, path, filename)
+}
+
+/// parse_company_file, but with `lenient` a JSON file that fails to parse is
+/// given a second try relaxed by config::relax_json.
+fn parse_file(
+    path: &Path,
+    filename: &str,
+    max_bytes: u64,
+    lenient: bool,
+) -> Result<ParsedFile, CommandError> {
+    let content = config::read_capped(path, filename, max_bytes)?;
+    let strict = match parse_company_text(&content, path, filename) {
+        Ok(value) => return Ok(ParsedFile { value, lenient: false }),
+        Err(e) => e,
+    };
+    if !lenient || is_yaml(path) {
+        return Err(strict);
+    }
+    let relaxed = config::relax_json(strip_bom(&content)).ok_or_else(|| strict.clone())?;
+    match parse_company_text(&relaxed, path, filename) {
+        Ok(value) => Ok(ParsedFile { value, lenient: true }),
+        Err(_) => Err(strict),
+    }
+}
+
+fn is_yaml(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("yaml") || ext.eq_ignore_ascii_case("yml"))
+}
+
+/// parse_company_file for a file already read as `content`.
+pub fn parse_company_text(content: &str, path: &Path, filename: &str) -> Result<serde_json::Value, CommandError> {
+    let content = strip_bom(content);
+    if is_yaml(path) {
+        serde_yaml::from_str(content)
+            .map_err(|e| CommandError::Parse(format!("Failed to parse {}: {}", filename, e)))
+    } else {
+        serde_json::from_str(content)
+            .map_err(|e| CommandError::Parse(format!("Failed to parse {}: {}", filename, e)))
+    }
+}
+
+/// Drop the UTF-8 byte order mark Windows editors like to prepend, which
+/// serde_json rejects.
+pub fn strip_bom(content: &str) -> &str {
+    content.strip_prefix('\u{feff}').unwrap_or(content)
+}
+
+pub fn file_error(file: &str, error: CommandError) -> serde_json::Value {
+    tracing::warn!(file, "{}", error);
+    serde_json::json!({ "file": file, "message": error.message() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    fn fixture() -> TempDir {
+        let dir = TempDir::new("company-cache");
+        dir.write("_company/org_chart.json", r#"{"leadership": []}"#);
+        for eng in ["alpha", "beta"] {
+            dir.write(&format!("{}/engagement_config.json", eng), "{}");
+            dir.write(
+                &format!("{}/ops/KNOWLEDGE_LOG.md", eng),
+                "## 2024-01-05\n### [STATUS] first\n- **Detail**: one\n",
+            );
+        }
+        dir
+    }
+
+    /// Load `dir` with an empty cache and the default config.
+    fn load_fresh(dir: &TempDir) -> serde_json::Value {
+        load_company_data(dir.path(), &mut RepoCache::default(), &ViewerConfig::default(), &LoadOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn only_changed_logs_are_reparsed() {
+        let dir = fixture();
+        let mut repo = RepoCache::default();
+
+        load_company_data(dir.path(), &mut repo, &ViewerConfig::default(), &LoadOptions::default()).unwrap();
+        assert_eq!(repo.reparsed.len(), 3);
+
+        load_company_data(dir.path(), &mut repo, &ViewerConfig::default(), &LoadOptions::default()).unwrap();
+        assert!(repo.reparsed.is_empty(), "{:?}", repo.reparsed);
+
+        let changed = dir.write(
+            "beta/ops/KNOWLEDGE_LOG.md",
+            "## 2024-01-06\n### [STATUS] second entry\n- **Detail**: two\n",
+        );
+        let data = load_company_data(dir.path(), &mut repo, &ViewerConfig::default(), &LoadOptions::default()).unwrap();
+        assert_eq!(repo.reparsed, vec![changed]);
+
+        let summaries: Vec<&str> = data["knowledge"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e["summary"].as_str().unwrap())
+            .collect();
+        assert!(summaries.contains(&"first"));
+        assert!(summaries.contains(&"second entry"));
+    }
+
+    #[test]
+    fn knowledge_is_sorted_newest_first() {
+        let dir = fixture();
+        dir.write(
+            "alpha/ops/KNOWLEDGE_LOG.md",
+            "## 2024-03-01\n### a-march\n## Someday\n### a-undated\n## 2024-01-10\n### a-jan-1\n### a-jan-2\n",
+        );
+        dir.write(
+            "beta/ops/KNOWLEDGE_LOG.md",
+            "## TBD\n### b-undated\n## 2024-01-10\n### b-jan\n## 2024-02-01\n### b-feb\n",
+        );
+        let data = load_fresh(&dir);
+
+        let summaries: Vec<&str> = data["knowledge"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e["summary"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            summaries,
+            ["a-march", "b-feb", "a-jan-1", "a-jan-2", "b-jan", "a-undated", "b-undated"]
+        );
+    }
+
+    #[test]
+    fn csv_export_quotes_commas_and_newlines() {
+        let dir = fixture();
+        dir.write(
+            "alpha/ops/KNOWLEDGE_LOG.md",
+            "## 2024-02-01\n### [RISK] Flat network\n- **Detail**: Ops, IT and vendors\n  share one VLAN\n",
+        );
+        let entries = load_knowledge(
+            &engagement_dirs(dir.path(), &ViewerConfig::default()),
+            &mut RepoCache::default(),
+            &ViewerConfig::default(),
+            &[],
+            &mut LoadReport::default(),
+            &Progress::default(),
+        );
+        let out = dir.path().join("knowledge.csv");
+
+        assert_eq!(write_knowledge_csv(&entries, &out).unwrap(), 2);
+        let csv = std::fs::read_to_string(&out).unwrap();
+        assert_eq!(
+            csv,
+            "engagement,workstream,date,type,summary,detail,source\n\
+             alpha,ops,2024-02-01,RISK,Flat network,\"Ops, IT and vendors\nshare one VLAN\",\n\
+             beta,ops,2024-01-05,STATUS,first,one,\n"
+        );
+    }
+
+    #[test]
+    fn markdown_export_parses_back_to_the_filtered_entries() {
+        let dir = fixture();
+        dir.write(
+            "alpha/ops/KNOWLEDGE_LOG.md",
+            "## 2024-02-01\n### [RISK: high] Flat network\n- **Detail**: Ops, IT and vendors\n  share one VLAN\n\
+             - **Source**: Walkdown\n### [STATUS] Not a risk\n\n## Mar 3, 2024\n### [RISK] Shared logins\n",
+        );
+        let entries = load_knowledge(
+            &engagement_dirs(dir.path(), &ViewerConfig::default()),
+            &mut RepoCache::default(),
+            &ViewerConfig::default(),
+            &[],
+            &mut LoadReport::default(),
+            &Progress::default(),
+        );
+        let query = knowledge::Query { entry_type: Some("risk".into()), ..Default::default() };
+        let risks = query.filter(entries).unwrap();
+        let out = dir.path().join("risks.md");
+
+        assert_eq!(write_knowledge_markdown(&risks, &out).unwrap(), 2);
+        let markdown = std::fs::read_to_string(&out).unwrap();
+        let newest_first = "## 2024-03-03\n### [RISK] Shared logins\n\n## 2024-02-01\n";
+        assert!(markdown.starts_with(newest_first), "{}", markdown);
+        let mut parsed = knowledge::ParsedLog::default();
+        knowledge::parse_knowledge_log(&markdown, "", "", &out, &mut parsed);
+        assert!(parsed.warnings.is_empty(), "{:?}", parsed.warnings);
+        let fields = |entry: &knowledge::KnowledgeEntry| {
+            (
+                entry.date_iso.clone(),
+                entry.entry_type.clone(),
+                entry.qualifier.clone(),
+                entry.summary.clone(),
+                entry.detail.clone(),
+                entry.source.clone(),
+            )
+        };
+        assert_eq!(
+            parsed.entries.iter().map(fields).collect::<Vec<_>>(),
+            risks.iter().map(fields).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn lenient_json_accepts_trailing_commas_and_comments() {
+        let dir = fixture();
+        dir.write("_company/company_config.json", "{\n  // the client\n  \"name\": \"Acme\",\n}\n");
+        dir.write("_company/org_chart.json", r#"{"leadership": [{"name": "Dana"},]}"#);
+
+        let data = load_fresh(&dir);
+        assert!(data["company_config"].is_null());
+        assert_eq!(data["errors"].as_array().unwrap().len(), 2);
+
+        let options = LoadOptions { lenient_json: true, ..Default::default() };
+        let data = load_company_data(dir.path(), &mut RepoCache::default(), &ViewerConfig::default(), &options)
+            .unwrap();
+        assert_eq!(data["company_config"]["name"], "Acme");
+        assert_eq!(data["org_chart"]["leadership"][0]["name"], "Dana");
+        assert!(data["errors"].as_array().unwrap().is_empty());
+        let warned: Vec<&str> =
+            data["warnings"].as_array().unwrap().iter().map(|w| w["file"].as_str().unwrap()).collect();
+        assert_eq!(warned, ["_company/org_chart.json", "_company/company_config.json"]);
+    }
+
+    #[test]
+    fn json_with_a_bom_loads() {
+        let dir = fixture();
+        dir.write("_company/company_config.json", "\u{feff}{\r\n  \"name\": \"Acme\"\r\n}\r\n");
+        let data = load_fresh(&dir);
+
+        assert_eq!(data["company_config"]["name"], "Acme");
+        assert!(data["errors"].as_array().unwrap().is_empty());
+    }
+
+    #[test]
+    fn yaml_files_stand_in_for_missing_json() {
+        let dir = fixture();
+        dir.write("_company/company_config.yaml", "name: Acme\nsites:\n  - Plant 1\n  - Plant 2\n");
+        dir.write("_company/org_chart.yml", "leadership: [ignored]\n");
+        dir.write("_company/file_index.yml", "files: [\n");
+        let data = load_fresh(&dir);
+
+        assert_eq!(data["company_config"]["sites"][1], "Plant 2");
+        assert_eq!(data["org_chart"]["leadership"], serde_json::json!([]));
+        assert!(data["file_index"].is_null());
+        let errors = data["errors"].as_array().unwrap();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0]["file"], "_company/file_index.yml");
+    }
+
+    #[test]
+    fn writing_a_company_file_keeps_a_backup() {
+        let dir = fixture();
+        let path = dir.write("_company/org_chart.json", r#"{"leadership":[]}"#);
+        let value = serde_json::json!({ "leadership": [{ "name": "Ana" }] });
+        save_company_file(dir.path(), "org_chart", &value).unwrap();
+
+        let written = std::fs::read_to_string(&path).unwrap();
+        assert!(written.contains("\n  \"leadership\""), "{}", written);
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&written).unwrap(), value);
+        let backup = std::fs::read_to_string(dir.path().join("_company/org_chart.json.bak"));
+        assert_eq!(backup.unwrap(), r#"{"leadership":[]}"#);
+    }
+
+    #[test]
+    fn bundles_read_back_as_company_data() {
+        let dir = fixture();
+        dir.write("_company/company_config.json", "{ broken");
+        let data = load_fresh(&dir);
+        let out = dir.path().join("snapshot.json");
+        assert_eq!(write_bundle(data.clone(), "/repos/acme", &out).unwrap(), 2);
+
+        let bundle: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&out).unwrap()).unwrap();
+        assert_eq!(bundle["schema_version"], BUNDLE_SCHEMA_VERSION);
+        assert_eq!(bundle["repo"], "/repos/acme");
+        assert_eq!(read_bundle(&out, &ViewerConfig::default()).unwrap(), data);
+
+        dir.write("newer.json", r#"{"schema_version": 2, "knowledge": []}"#);
+        let err = read_bundle(&dir.path().join("newer.json"), &ViewerConfig::default()).unwrap_err();
+        assert!(matches!(err, CommandError::InvalidInput(_)), "{:?}", err);
+        let err = read_bundle(&dir.path().join("_company/org_chart.json"), &ViewerConfig::default())
+            .unwrap_err();
+        assert!(err.message().contains("not a company bundle"), "{}", err);
+    }
+
+    #[test]
+    fn snapshot_diffs_list_what_changed() {
+        let dir = fixture();
+        dir.write(
+            "_company/engagement_registry.json",
+            r#"{"engagements": {"alpha": {"label": "Alpha"}, "beta": {"label": "Beta"}}}"#,
+        );
+        let before = dir.path().join("before.json");
+        write_bundle(load_fresh(&dir), "/repos/acme", &before).unwrap();
+
+        dir.write("_company/org_chart.json", r#"{"leadership": ["Dana"], "sites": []}"#);
+        dir.write(
+            "_company/engagement_registry.json",
+            r#"{"engagements": {"alpha": {"label": "Alpha (phase 2)"}, "gamma": {"label": "Gamma"}}}"#,
+        );
+        dir.write(
+            "alpha/ops/KNOWLEDGE_LOG.md",
+            "## 2024-01-05\n### [STATUS] first\n- **Detail**: edited\n### [RISK] second\n",
+        );
+        std::fs::remove_dir_all(dir.path().join("beta")).unwrap();
+
+        let after = load_snapshot(dir.path(), &ViewerConfig::default()).unwrap();
+        let diff = diff_snapshots(&read_bundle(&before, &ViewerConfig::default()).unwrap(), &after);
+
+        let engagements: Vec<(&str, Change, usize, usize, bool)> = diff
+            .engagements
+            .iter()
+            .map(|e| (e.engagement.as_str(), e.change, e.entries_added, e.entries_removed, e.registry_changed))
+            .collect();
+        assert_eq!(
+            engagements,
+            [
+                ("alpha", Change::Modified, 1, 0, true),
+                ("beta", Change::Removed, 0, 1, true),
+                ("gamma", Change::Added, 0, 0, true),
+            ]
+        );
+        let summaries = |entries: &[EntryRef]| -> Vec<(String, String)> {
+            entries.iter().map(|e| (e.engagement.clone(), e.summary.clone())).collect()
+        };
+        assert_eq!(summaries(&diff.entries_added), [("alpha".to_string(), "second".to_string())]);
+        assert_eq!(summaries(&diff.entries_removed), [("beta".to_string(), "first".to_string())]);
+
+        let files: Vec<(&str, Change)> = diff.files.iter().map(|f| (f.file.as_str(), f.change)).collect();
+        assert_eq!(files, [("org_chart", Change::Modified), ("engagement_registry", Change::Modified)]);
+        assert_eq!(
+            diff.files[0].sections,
+            [
+                SectionChange { section: "leadership".to_string(), change: Change::Modified },
+                SectionChange { section: "sites".to_string(), change: Change::Added },
+            ]
+        );
+        assert_eq!(diff_snapshots(&after, &after), SnapshotDiff::default());
+    }
+
+    #[test]
+    fn file_index_loads_on_its_own() {
+        let dir = fixture();
+        assert_eq!(read_file_index(dir.path(), &ViewerConfig::default()), Ok(serde_json::Value::Null));
+
+        dir.write("_company/file_index.yaml", "files:\n  - path: specs/pump.pdf\n");
+        let index = read_file_index(dir.path(), &ViewerConfig::default()).unwrap();
+        assert_eq!(index, load_fresh(&dir)["file_index"]);
+        assert_eq!(index["files"][0]["path"], "specs/pump.pdf");
+
+        let config = ViewerConfig { max_file_bytes: 8, ..ViewerConfig::default() };
+        let err = read_file_index(dir.path(), &config).unwrap_err();
+        assert!(matches!(err, CommandError::TooLarge(_)), "{:?}", err);
+    }
+
+    #[test]
+    fn writing_keeps_yaml_files_yaml_and_rejects_unknown_keys() {
+        let dir = fixture();
+        let path = dir.write("_company/company_config.yaml", "name: Acme\n");
+        let value = serde_json::json!({ "name": "Acme Corp" });
+        save_company_file(dir.path(), "company_config", &value).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "name: Acme Corp\n");
+        assert!(!dir.path().join("_company/company_config.json").exists());
+
+        let err = save_company_file(dir.path(), "secrets", &value).unwrap_err();
+        assert_eq!(err, CommandError::InvalidInput("Unknown company file: secrets".to_string()));
+    }
+
+    #[test]
+    fn entry_types_outside_the_allowed_list_are_warned_about() {
+        let dir = fixture();
+        dir.write("_company/company_config.json", r#"{"entry_types": ["status", "DECISION"]}"#);
+        dir.write(
+            "beta/ops/KNOWLEDGE_LOG.md",
+            "## 2024-01-05\n### [STATUS] Known\n### [DECISON] Typo'd\n### Untyped\n",
+        );
+        let data = load_fresh(&dir);
+        assert_eq!(data["knowledge"].as_array().unwrap().len(), 4);
+        let warnings = data["warnings"].as_array().unwrap();
+        assert_eq!(warnings.len(), 1, "{:?}", warnings);
+        assert_eq!(warnings[0]["file"], "beta/ops/KNOWLEDGE_LOG.md");
+        assert_eq!(warnings[0]["line"], 3);
+        assert_eq!(warnings[0]["message"], "entry type \"DECISON\" is not one of the allowed types");
+
+        // A list passed in replaces company_config's.
+        let options = LoadOptions { entry_types: Some(vec!["DECISON".into()]), ..Default::default() };
+        let data = load_company_data(dir.path(), &mut RepoCache::default(), &ViewerConfig::default(), &options)
+            .unwrap();
+        let messages: Vec<&str> = data["warnings"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|warning| warning["message"].as_str().unwrap())
+            .collect();
+        assert_eq!(messages.len(), 2, "{:?}", messages);
+        assert!(messages.iter().all(|message| message.contains("\"STATUS\"")), "{:?}", messages);
+    }
+
+    #[test]
+    fn streaming_sends_entries_in_bounded_batches() {
+        let dir = fixture();
+        dir.write(
+            "beta/ops/KNOWLEDGE_LOG.md",
+            "## 2024-01-05\n### [STATUS] one\n### [STATUS] two\n### [RISK] three\n## someday\n",
+        );
+        let mut batches = Vec::new();
+        let (total, report) = stream_entries(dir.path(), &ViewerConfig::default(), 2, |batch| {
+            batches.push(batch.iter().map(|e| e.summary.clone()).collect::<Vec<_>>())
+        });
+
+        assert_eq!(total, 4);
+        assert_eq!(batches, [vec!["first", "one"], vec!["two", "three"]]);
+        // `someday` is both not a date and empty.
+        assert_eq!(report.warnings.len(), 2);
+        assert_eq!(report.warnings[1]["engagement"], "beta");
+        assert_eq!(report.warnings[1]["workstream"], "ops");
+        assert_eq!(report.warnings[1]["line"], 5);
+        assert_eq!(report.warnings[1]["message"], "date heading \"someday\" has no entries");
+        assert!(report.errors.is_empty());
+    }
+
+    #[test]
+    fn merged_timelines_namespace_shared_engagements() {
+        let entry = |engagement: &str, date: &str| knowledge::KnowledgeEntry {
+            engagement: engagement.to_string(),
+            date_iso: Some(date.to_string()),
+            ..Default::default()
+        };
+        let merged = merge_timelines(vec![
+            ("acme".to_string(), vec![entry("alpha", "2024-01-05"), entry("solo", "2024-01-01")]),
+            ("globex".to_string(), vec![entry("alpha", "2024-02-01")]),
+        ]);
+
+        let names: Vec<(&str, Option<&str>)> =
+            merged.iter().map(|e| (e.engagement.as_str(), e.repo.as_deref())).collect();
+        assert_eq!(
+            names,
+            [
+                ("globex/alpha", Some("globex")),
+                ("acme/alpha", Some("acme")),
+                ("solo", Some("acme")),
+            ]
+        );
+    }
+
+    #[test]
+    fn read_only_refuses_company_writes_and_exports() {
+        let dir = fixture();
+        let repo = dir.path().display().to_string();
+        let read_only = LaunchConfig { read_only: true, ..LaunchConfig::default() };
+        let (cache, config) = (CompanyCache::default(), ViewerConfig::default());
+        let out = |name: &str| dir.path().join(name);
+
+        let results = [
+            write_company(&read_only, &cache, &repo, "org_chart", &serde_json::json!({ "leadership": [1] })),
+            export_csv(&read_only, &cache, &config, &repo, &out("out.csv")).map(drop),
+            export_markdown(&read_only, &cache, &config, &repo, knowledge::Query::default(), &out("out.md")).map(drop),
+            export_bundle(&read_only, &cache, &config, &repo, &out("out.json")).map(drop),
+        ];
+        for result in results {
+            assert!(matches!(result, Err(CommandError::ReadOnly(_))), "{:?}", result);
+        }
+        assert_eq!(std::fs::read_to_string(out("_company/org_chart.json")).unwrap(), r#"{"leadership": []}"#);
+        for name in ["out.csv", "out.md", "out.json", "_company/org_chart.json.bak"] {
+            assert!(!out(name).exists(), "{}", name);
+        }
+
+        let writable = LaunchConfig::default();
+        assert_eq!(export_csv(&writable, &cache, &config, &repo, &out("out.csv")), Ok(2));
+    }
+
+    #[test]
+    fn same_named_repo_folders_stay_apart() {
+        let paths = ["/a/acme/repo", "/b/globex/repo", "/c/other", "/c/other"].map(PathBuf::from);
+        assert_eq!(repo_names(&paths), ["acme/repo", "globex/repo", "other", "other"]);
+        assert_eq!(repo_names(&[PathBuf::from("/x/repo"), PathBuf::from("/repo")]), ["x/repo", "/repo"]);
+
+        let parent = TempDir::new("company-multi");
+        for client in ["acme", "globex"] {
+            parent.write(&format!("{}/repo/_company/org_chart.json", client), "{}");
+            parent.write(&format!("{}/repo/alpha/engagement_config.json", client), "{}");
+            parent.write(
+                &format!("{}/repo/alpha/ops/KNOWLEDGE_LOG.md", client),
+                &format!("## 2024-01-05\n### [STATUS] {} kickoff\n", client),
+            );
+        }
+        let repo_paths: Vec<String> =
+            ["acme", "globex"].iter().map(|c| parent.path().join(c).join("repo").display().to_string()).collect();
+
+        let data = load_multi(&CompanyCache::default(), &ViewerConfig::default(), &repo_paths, false).unwrap();
+        assert_eq!(data["repos"][0]["name"], "acme/repo");
+        assert_eq!(data["repos"][1]["name"], "globex/repo");
+        let mut tagged: Vec<(String, String)> = data["knowledge"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| (e["engagement"].as_str().unwrap().to_string(), e["repo"].as_str().unwrap().to_string()))
+            .collect();
+        tagged.sort();
+        assert_eq!(
+            tagged,
+            [
+                ("acme/repo/alpha".to_string(), "acme/repo".to_string()),
+                ("globex/repo/alpha".to_string(), "globex/repo".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn gzipped_company_files_parse_like_plain_ones() {
+        use std::io::Write;
+        let index = r#"{"files": [{"path": "specs/pump.pdf", "tags": ["p&id"]}]}"#;
+        let plain = fixture();
+        plain.write("_company/file_index.json", index);
+        let gzipped = fixture();
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(index.as_bytes()).unwrap();
+        let path = gzipped.write("_company/file_index.json.gz", "");
+        std::fs::write(&path, encoder.finish().unwrap()).unwrap();
+
+        let (plain, gzipped) = (load_fresh(&plain), load_fresh(&gzipped));
+        assert_eq!(gzipped["file_index"], plain["file_index"]);
+        assert_eq!(gzipped["file_index"]["files"][0]["path"], "specs/pump.pdf");
+        assert!(gzipped["errors"].as_array().unwrap().is_empty());
+    }
+
+    #[test]
+    fn odd_layouts_get_clear_errors() {
+        let dir = TempDir::new("company-odd");
+        dir.write("_company", "not a folder");
+        let err = load_company_data(dir.path(), &mut RepoCache::default(), &ViewerConfig::default(), &LoadOptions::default())
+            .unwrap_err();
+        assert!(err.message().starts_with("_company exists but is not a directory"), "{}", err);
+
+        let dir = fixture();
+        std::fs::create_dir_all(dir.path().join("gamma/engagement_config.json")).unwrap();
+        dir.write("gamma/ops/KNOWLEDGE_LOG.md", "## 2024-01-05\n### [STATUS] hidden\n");
+        std::fs::remove_file(dir.path().join("_company/org_chart.json")).unwrap();
+        std::fs::create_dir_all(dir.path().join("_company/org_chart.json")).unwrap();
+        let data = load_fresh(&dir);
+        assert!(data["org_chart"].is_null() && data["errors"].as_array().unwrap().is_empty());
+        assert!(data["knowledge"].as_array().unwrap().iter().all(|e| e["engagement"] != "gamma"));
+    }
+
+    #[test]
+    fn pages_are_windows_onto_the_sorted_entries() {
+        let entry = |date: Option<&str>| knowledge::KnowledgeEntry {
+            date_iso: date.map(str::to_string),
+            ..Default::default()
+        };
+        let dates = |page: &KnowledgePage| -> Vec<Option<String>> {
+            page.entries.iter().map(|e| e.date_iso.clone()).collect()
+        };
+        let all = || {
+            let mut entries = vec![entry(Some("2024-03-01")), entry(Some("2024-01-01")), entry(None)];
+            knowledge::sort_newest_first(&mut entries);
+            entries
+        };
+
+        let page = page_of(&mut all(), 0, 2, knowledge::SortOrder::NewestFirst);
+        assert_eq!(dates(&page), [Some("2024-03-01".into()), Some("2024-01-01".into())]);
+        let page = page_of(&mut all(), 1, 5, knowledge::SortOrder::OldestFirst);
+        assert_eq!(dates(&page), [Some("2024-03-01".into()), None]);
+        let page = page_of(&mut all(), 7, 2, knowledge::SortOrder::NewestFirst);
+        assert!(page.entries.is_empty());
+        assert_eq!((page.total, page.offset), (3, 7));
+    }
+
+    #[test]
+    fn dedupe_collapses_findings_copied_between_workstreams() {
+        let dir = fixture();
+        let finding = "## 2024-02-01\n### [RISK] Pump seal wear\n- **Detail**: seen on P-101\n";
+        dir.write("alpha/maint/KNOWLEDGE_LOG.md", finding);
+        dir.write("alpha/ops/KNOWLEDGE_LOG.md", finding);
+        let options = LoadOptions { dedupe: true, ..Default::default() };
+        let data =
+            load_company_data(dir.path(), &mut RepoCache::default(), &ViewerConfig::default(), &options)
+                .unwrap();
+
+        let risks: Vec<&serde_json::Value> =
+            data["knowledge"].as_array().unwrap().iter().filter(|e| e["type"] == "RISK").collect();
+        assert_eq!(risks.len(), 1);
+        assert_eq!(risks[0]["workstream"], "maint");
+        assert_eq!(risks[0]["workstreams"], serde_json::json!(["maint", "ops"]));
+        assert!(data["knowledge"][1].get("workstreams").is_none());
+        assert_eq!(load_fresh(&dir)["knowledge"].as_array().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn slotignore_hides_archived_engagements() {
+        let dir = fixture();
+        dir.write("archive-2022/engagement_config.json", "{}");
+        dir.write("archive-2022/ops/KNOWLEDGE_LOG.md", "## 2022-06-01\n### [STATUS] old\n");
+        dir.write("_company/.slotignore", "# old work\narchive-*\n");
+        let engagements: Vec<String> =
+            engagement_dirs(dir.path(), &ViewerConfig::default()).iter().map(|path| file_name(path)).collect();
+        assert_eq!(engagements, ["alpha", "beta"]);
+        assert_eq!(load_fresh(&dir)["knowledge"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn engagements_are_summarized_most_recent_first() {
+        let dir = fixture();
+        dir.write("alpha/it/KNOWLEDGE_LOG.md", "## 2024-03-01\n### [RISK] later\n## someday\n### [NOTE] undated\n");
+        dir.write("gamma/engagement_config.json", "{}");
+        dir.write("delta/engagement_config.json", "{}");
+        dir.write("delta/ops/KNOWLEDGE_LOG.md", "## TBD\n### [NOTE] undated\n");
+        let engagements = engagement_dirs(dir.path(), &ViewerConfig::default());
+        let mut repo = RepoCache::default();
+        let entries = load_knowledge(
+            &engagements,
+            &mut repo,
+            &ViewerConfig::default(),
+            &[],
+            &mut LoadReport::default(),
+            &Progress::default(),
+        );
+        let names: Vec<String> = engagements.iter().map(|path| file_name(path)).collect();
+
+        let summary = |engagement: &str, entry_count, last: Option<&str>, workstream_count| EngagementSummary {
+            engagement: engagement.to_string(),
+            entry_count,
+            last_entry_date: last.map(str::to_string),
+            workstream_count,
+        };
+        assert_eq!(
+            summarize_engagements(&names, &entries),
+            [
+                summary("alpha", 3, Some("2024-03-01"), 2),
+                summary("beta", 1, Some("2024-01-05"), 1),
+                summary("delta", 1, None, 1),
+                summary("gamma", 0, None, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn stats_count_engagements_people_and_entries() {
+        let dir = fixture();
+        dir.write(
+            "_company/org_chart.json",
+            r#"{"leadership": [{"name": "Ana"}], "people": [{"name": "Ben"}, {"name": "ana"}],
+                "external_ecosystem": [{"name": "Vendor", "key_contacts": [{"name": "Cy"}]}]}"#,
+        );
+        dir.write(
+            "_company/engagement_registry.json",
+            r#"{"engagements": {"alpha": {}, "beta": {}, "gamma": {}}}"#,
+        );
+        dir.write(
+            "beta/ops/KNOWLEDGE_LOG.md",
+            "## 2024-02-03\n### [RISK] one\n### [STATUS] two\n## someday\n### [RISK] three\n",
+        );
+        let mut repo = RepoCache::default();
+        let (data, entries) =
+            load_company_parts(dir.path(), &mut repo, &ViewerConfig::default(), &LoadOptions::default()).unwrap();
+        let stats = company_stats(&data, &entries);
+
+        let counts = |pairs: &[(&str, usize)]| -> BTreeMap<String, usize> {
+            pairs.iter().map(|(k, n)| (k.to_string(), *n)).collect()
+        };
+        assert_eq!(
+            stats,
+            CompanyStats {
+                engagements: 3,
+                people: 3,
+                knowledge_entries: 4,
+                entries_by_type: counts(&[("RISK", 2), ("STATUS", 2)]),
+                entries_by_month: counts(&[("2024-01", 1), ("2024-02", 2)]),
+                undated_entries: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn extra_files_load_under_their_stem() {
+        let dir = fixture();
+        dir.write("_company/billing.json", r#"{"rate": 150}"#);
+        dir.write("_company/timeline.yaml", "phases: [design, build]\n");
+        dir.write("_company/errors.json", "{}");
+        dir.write("_company/notes.txt", "not data");
+        let extra_files = ["*.json", "timeline.yaml", "missing.json"].map(String::from).to_vec();
+        let options = LoadOptions { extra_files, ..Default::default() };
+        let data =
+            load_company_data(dir.path(), &mut RepoCache::default(), &ViewerConfig::default(), &options)
+                .unwrap();
+
+        assert_eq!(data["billing"]["rate"], 150);
+        assert_eq!(data["timeline"]["phases"][1], "build");
+        assert_eq!(data["org_chart"]["leadership"], serde_json::json!([]));
+        assert!(data["missing"].is_null() && data["_meta"]["missing"].is_null());
+        assert!(data["errors"].as_array().unwrap().is_empty());
+        assert!(data.get("notes").is_none());
+    }
+
+    #[test]
+    fn progress_steps_through_files_and_engagements() {
+        let dir = fixture();
+        dir.write("alpha/maint/KNOWLEDGE_LOG.md", "## 2024-02-01\n### [RISK] second log\n");
+        dir.write("gamma/engagement_config.json", "{}");
+        let steps = Mutex::new(Vec::new());
+        let record = |step: LoadProgress| steps.lock().unwrap().push(step);
+        let options = LoadOptions { progress: Progress::new(&record), ..Default::default() };
+        load_company_data(dir.path(), &mut RepoCache::default(), &ViewerConfig::default(), &options).unwrap();
+
+        let steps = steps.into_inner().unwrap();
+        let done: Vec<usize> = steps.iter().map(|s| s.done).collect();
+        assert_eq!(done, [1, 2, 3, 4]);
+        assert!(steps.iter().all(|s| s.total == 4));
+        let mut current: Vec<&str> = steps.iter().map(|s| s.current.as_str()).collect();
+        current.sort();
+        assert_eq!(current, ["_company/org_chart.json", "alpha", "beta", "gamma"]);
+    }
+
+    #[test]
+    fn custom_knowledge_log_names_are_matched_ignoring_case() {
+        let dir = fixture();
+        dir.write("alpha/maint/knowledge.md", "## 2024-02-01\n### [RISK] custom name\n");
+        dir.write("beta/ops/LOG.md", "## 2024-03-01\n### [STATUS] shadowed\n");
+        let knowledge_files = ["Knowledge_Log.md", "KNOWLEDGE.md", "log.md"].map(String::from).to_vec();
+        let options = LoadOptions { knowledge_files, ..Default::default() };
+        let data =
+            load_company_data(dir.path(), &mut RepoCache::default(), &ViewerConfig::default(), &options)
+                .unwrap();
+
+        let summaries: Vec<&str> =
+            data["knowledge"].as_array().unwrap().iter().map(|e| e["summary"].as_str().unwrap()).collect();
+        assert_eq!(summaries, ["custom name", "first", "first"]);
+        assert!(data["_meta"]["alpha/maint/knowledge.md"].is_object());
+
+        // Without the option only KNOWLEDGE_LOG.md counts.
+        assert_eq!(load_fresh(&dir)["knowledge"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn extra_file_patterns_must_stay_in_company_dir() {
+        let dir = fixture();
+        dir.write("secret.json", "{}");
+        for pattern in ["../secret.json", "../*.json", "sub/*.json"] {
+            let options = LoadOptions { extra_files: vec![pattern.to_string()], ..Default::default() };
+            let result =
+                load_company_data(dir.path(), &mut RepoCache::default(), &ViewerConfig::default(), &options);
+            assert!(result.is_err(), "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn company_files_can_live_in_another_directory() {
+        let dir = fixture();
+        std::fs::rename(dir.path().join("_company"), dir.path().join("_org")).unwrap();
+        dir.write("_org/company_config.json", "{ broken");
+        let load = |name: &str| {
+            let options = LoadOptions { company_dir_name: name.to_string(), ..Default::default() };
+            load_company_data(dir.path(), &mut RepoCache::default(), &ViewerConfig::default(), &options)
+        };
+
+        let data = load("_org/").unwrap();
+        assert!(data["org_chart"].is_object());
+        assert_eq!(data["errors"][0]["file"], "_org/company_config.json");
+        assert_eq!(data["knowledge"].as_array().unwrap().len(), 2);
+
+        let err = load("").unwrap_err();
+        assert!(matches!(err, CommandError::NotFound(_)), "{:?}", err);
+        assert!(err.message().starts_with("No _company directory found"), "{}", err);
+        assert!(load("missing").unwrap_err().message().starts_with("No missing directory found"));
+        let outside = TempDir::new("company-dir-outside");
+        outside.write("org_chart.json", "{}");
+        let escape = format!("../{}", file_name(outside.path()));
+        let err = load(&escape).unwrap_err();
+        assert!(matches!(err, CommandError::PathTraversal(_)), "{:?}", err);
+    }
+
+    #[test]
+    fn wildcards_match_runs_and_single_characters() {
+        assert!(wildcard_match("*.json", "billing.json"));
+        assert!(wildcard_match("t?me*.y*ml", "timeline.yml"));
+        assert!(!wildcard_match("*.json", "billing.json.bak"));
+        assert!(!wildcard_match("b?", "b"));
+    }
+
+    /// A synthetic 50-engagement repo loaded on one thread and then on the
+    /// default pool. Run with `cargo test bench_parallel -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn bench_parallel_load() {
+        let dir = TempDir::new("company-bench");
+        dir.write("_company/org_chart.json", r#"{"leadership": []}"#);
+        let mut log = String::new();
+        for day in 1..=28 {
+            log.push_str(&format!("## 2024-02-{:02}\n", day));
+            for n in 0..10 {
+                log.push_str(&format!(
+                    "### [STATUS] Entry {}\n- **Detail**: Some detail, with a comma\n  and a second line\n- **Source**: Call\n",
+                    n
+                ));
+            }
+        }
+        for eng in 0..50 {
+            dir.write(&format!("eng-{:02}/engagement_config.json", eng), "{}");
+            for ws in ["ops", "network", "historian", "safety"] {
+                dir.write(&format!("eng-{:02}/{}/KNOWLEDGE_LOG.md", eng, ws), &log);
+            }
+        }
+
+        let load = || {
+            let started = std::time::Instant::now();
+            let data = load_fresh(&dir);
+            (started.elapsed(), data)
+        };
+        let single = rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+        let (sequential, sequential_data) = single.install(load);
+        let (parallel, parallel_data) = load();
+
+        println!(
+            "sequential: {:?}, parallel: {:?} on {} threads",
+            sequential,
+            parallel,
+            rayon::current_num_threads()
+        );
+        assert_eq!(parallel_data["knowledge"].as_array().unwrap().len(), 50 * 4 * 28 * 10);
+        assert_eq!(sequential_data, parallel_data);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn scan_does_not_loop_through_or_escape_by_symlinks() {
+        let dir = fixture();
+        let outside = TempDir::new("company-outside");
+        outside.write("stray/engagement_config.json", "{}");
+        outside.write("ops/KNOWLEDGE_LOG.md", "## 2024-01-01\n### stray\n");
+        let link = |target: &Path, name: &str| std::os::unix::fs::symlink(target, dir.path().join(name)).unwrap();
+        std::fs::create_dir_all(dir.path().join("clients")).unwrap();
+        link(dir.path(), "clients/loop");
+        link(&dir.path().join("alpha"), "alpha/self");
+        link(outside.path(), "clients/elsewhere");
+        link(&outside.path().join("ops"), "alpha/stray");
+
+        for follow_symlinks in [false, true] {
+            let config = ViewerConfig { max_scan_depth: 8, follow_symlinks, ..ViewerConfig::default() };
+            let names: Vec<String> = engagement_dirs(dir.path(), &config).iter().map(|path| file_name(path)).collect();
+            assert_eq!(names, ["alpha", "beta"], "follow_symlinks: {}", follow_symlinks);
+            let data = load_company_data(dir.path(), &mut RepoCache::default(), &config, &LoadOptions::default()).unwrap();
+            let knowledge = data["knowledge"].as_array().unwrap();
+            assert!(knowledge.iter().all(|e| e["summary"] == "first"), "{:?}", knowledge);
+        }
+    }
+
+    #[test]
+    fn oversized_files_are_reported() {
+        let dir = fixture();
+        dir.write("_company/file_index.json", &format!("[{}]", "0,".repeat(600) + "0"));
+        let config = ViewerConfig { max_file_bytes: 1024, ..Default::default() };
+        let data = load_company_data(dir.path(), &mut RepoCache::default(), &config, &LoadOptions::default()).unwrap();
+
+        assert!(data["file_index"].is_null());
+        assert!(data["org_chart"].is_object());
+        let errors = data["errors"].as_array().unwrap();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0]["message"].as_str().unwrap().contains("exceeds size limit"));
+    }
+
+    #[test]
+    fn scan_skips_ignored_dirs_and_respects_depth() {
+        let dir = fixture();
+        for decoy in [".git", "node_modules", ".cache/nested"] {
+            dir.write(&format!("{}/engagement_config.json", decoy), "{}");
+            dir.write(&format!("{}/ops/KNOWLEDGE_LOG.md", decoy), "## 2024-01-01\n### decoy\n");
+        }
+        dir.write("clients/acme/gamma/engagement_config.json", "{}");
+        dir.write("alpha/.hidden/KNOWLEDGE_LOG.md", "## 2024-01-01\n### hidden\n");
+
+        let names = |depth: usize| -> Vec<String> {
+            let config = ViewerConfig { max_scan_depth: depth, ..ViewerConfig::default() };
+            engagement_dirs(dir.path(), &config).iter().map(|path| file_name(path)).collect()
+        };
+        assert_eq!(names(1), ["alpha", "beta"]);
+        assert_eq!(names(3), ["alpha", "beta", "gamma"]);
+
+        let data = load_fresh(&dir);
+        let knowledge = data["knowledge"].as_array().unwrap();
+        assert_eq!(knowledge.len(), 2);
+        assert!(knowledge.iter().all(|e| e["summary"] == "first"));
+    }
+
+    #[test]
+    fn meta_covers_core_files_and_logs() {
+        let dir = fixture();
+        let data = load_fresh(&dir);
+        let meta = &data["_meta"];
+
+        let org_chart = &meta["org_chart"];
+        assert_eq!(org_chart["size"], r#"{"leadership": []}"#.len());
+        assert!(org_chart["modified_ms"].as_u64().unwrap() > 0);
+        assert!(org_chart["path"].as_str().unwrap().ends_with("org_chart.json"));
+        assert!(meta["company_config"].is_null());
+        assert!(meta["alpha/ops/KNOWLEDGE_LOG.md"]["size"].as_u64().unwrap() > 0);
+    }
+
+    #[test]
+    fn broken_files_are_reported_and_nulled() {
+        let dir = fixture();
+        dir.write("_company/company_config.json", "{ not json");
+        let data = load_fresh(&dir);
+
+        assert!(data["company_config"].is_null());
+        assert!(data["org_chart"].is_object());
+        let errors = data["errors"].as_array().unwrap();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0]["file"], "_company/company_config.json");
+    }
+}
@@ -0,0 +1,439 @@
+// ── Viewer configuration (limits on repo file I/O, and the I/O helpers) ────
+
+use crate::error::CommandError;
+use std::io::{BufRead, Read};
+use std::path::Path;
+
+/// Settings for reading repo files, managed as Tauri state so commands share
+/// one copy and tests can override it.
+#[derive(Clone, Debug)]
+pub struct ViewerConfig {
+    /// Files bigger than this are refused instead of read into memory.
+    pub max_file_bytes: u64,
+    /// How many directory levels below the repo root to look for
+    /// engagements; 1 means only its immediate subfolders.
+    pub max_scan_depth: usize,
+    /// Whether scans enter symlinked folders and read symlinked logs. Even
+    /// then only links that stay inside the repo (or, in a workstream, the
+    /// engagement) and don't lead back up the tree are followed; see
+    /// paths::Symlinks.
+    pub follow_symlinks: bool,
+}
+
+/// Directories never scanned for engagements or knowledge logs, besides
+/// anything starting with a dot.
+pub const IGNORED_DIRS: [&str; 2] = ["node_modules", "target"];
+
+/// Whether a directory named `name` is skipped when scanning a repo.
+pub fn is_ignored_dir(name: &str) -> bool {
+    name.starts_with('.') || IGNORED_DIRS.contains(&name)
+}
+
+/// Patterns from a repo's `_company/.slotignore`, one per line, for
+/// engagement folders to leave out of scans. As in `.gitignore`, blank lines
+/// and `#` comments are skipped, a pattern without a `/` matches a folder
+/// name at any depth, and one with a `/` matches the path from the repo root
+/// (a leading `/` is optional). `*` doesn't cross `/`, `**` does. Negation
+/// with `!` isn't supported.
+#[derive(Default)]
+pub struct SlotIgnore {
+    names: globset::GlobSet,
+    paths: globset::GlobSet,
+}
+
+impl SlotIgnore {
+    pub const FILE: &'static str = ".slotignore";
+
+    /// The repo's ignore list, empty if it has none. Patterns that don't
+    /// parse are logged and skipped.
+    pub fn load(base: &Path) -> Self {
+        let path = base.join("_company").join(Self::FILE);
+        match std::fs::read_to_string(&path) {
+            Ok(content) => Self::parse(&content),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn parse(content: &str) -> Self {
+        let mut names = globset::GlobSetBuilder::new();
+        let mut paths = globset::GlobSetBuilder::new();
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let pattern = line.trim_end_matches('/');
+            let anchored = pattern.contains('/');
+            let glob = globset::GlobBuilder::new(pattern.trim_start_matches('/'))
+                .literal_separator(true)
+                .build();
+            match glob {
+                Ok(glob) if anchored => {
+                    paths.add(glob);
+                }
+                Ok(glob) => {
+                    names.add(glob);
+                }
+                Err(e) => tracing::warn!("{}: skipping pattern {:?}: {}", Self::FILE, line, e),
+            }
+        }
+        SlotIgnore {
+            names: names.build().unwrap_or_default(),
+            paths: paths.build().unwrap_or_default(),
+        }
+    }
+
+    /// Whether the folder at `relative` (from the repo root, `/`-separated)
+    /// is excluded.
+    pub fn is_ignored(&self, relative: &str) -> bool {
+        let name = relative.rsplit('/').next().unwrap_or(relative);
+        self.names.is_match(name) || self.paths.is_match(relative)
+    }
+}
+
+impl Default for ViewerConfig {
+    fn default() -> Self {
+        ViewerConfig { max_file_bytes: 50 * 1024 * 1024, max_scan_depth: 1, follow_symlinks: false }
+    }
+}
+
+/// The error for file `name` being `size` bytes when at most `max_bytes` are
+/// allowed.
+pub fn too_big(name: &str, size: u64, max_bytes: u64) -> CommandError {
+    let message = format!("{} exceeds size limit ({} bytes, limit {} bytes)", name, size, max_bytes);
+    CommandError::TooLarge(message)
+}
+
+/// Read a text file, checking its size first so a huge file is refused
+/// rather than read. `name` is how the file is referred to in errors. A
+/// gzip-compressed file (a `.gz` name, or failing that the gzip magic bytes)
+/// is decompressed, and the limit applies to the decompressed size too.
+pub fn read_capped(path: &Path, name: &str, max_bytes: u64) -> Result<String, CommandError> {
+    let size = std::fs::metadata(path)
+        .map_err(|e| CommandError::io(format_args!("Failed to read {}", name), &e))?
+        .len();
+    if size > max_bytes {
+        return Err(too_big(name, size, max_bytes));
+    }
+    let bytes =
+        std::fs::read(path).map_err(|e| CommandError::io(format_args!("Failed to read {}", name), &e))?;
+    decode_capped(bytes, path, name, max_bytes)
+}
+
+/// The text of a file read as `bytes` from `path`, checked like read_capped
+/// checks it once read.
+pub fn decode_capped(bytes: Vec<u8>, path: &Path, name: &str, max_bytes: u64) -> Result<String, CommandError> {
+    let is_gzip = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("gz"))
+        || bytes.starts_with(&GZIP_MAGIC);
+    let bytes = if is_gzip {
+        let mut decoded = Vec::new();
+        // One byte past the limit is enough to know it's over.
+        flate2::read::GzDecoder::new(bytes.as_slice())
+            .take(max_bytes + 1)
+            .read_to_end(&mut decoded)
+            .map_err(|e| CommandError::Parse(format!("Failed to decompress {}: {}", name, e)))?;
+        if decoded.len() as u64 > max_bytes {
+            return Err(too_big(name, decoded.len() as u64, max_bytes));
+        }
+        decoded
+    } else {
+        bytes
+    };
+    String::from_utf8(bytes)
+        .map_err(|e| CommandError::Parse(format!("{} is not valid UTF-8 text: {}", name, e)))
+}
+
+/// The part of `value` (read from the file `name`) that the RFC 6901 JSON
+/// Pointer `pointer` selects, like `/settings/theme` or `/people/0`. The empty
+/// pointer selects the whole document.
+pub fn select_pointer(
+    mut value: serde_json::Value,
+    pointer: &str,
+    name: &str,
+) -> Result<serde_json::Value, CommandError> {
+    if !pointer.is_empty() && !pointer.starts_with('/') {
+        return Err(CommandError::InvalidInput(format!(
+            "JSON Pointer must be empty or start with '/': {:?}",
+            pointer
+        )));
+    }
+    value
+        .pointer_mut(pointer)
+        .map(serde_json::Value::take)
+        .ok_or_else(|| CommandError::NotFound(format!("{} has nothing at {}", name, pointer)))
+}
+
+/// The first two bytes of every gzip stream.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// A window onto a JSON array of records, from read_records.
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct RecordWindow {
+    pub records: Vec<serde_json::Value>,
+    pub offset: usize,
+    /// How many records the whole array holds.
+    pub total: usize,
+}
+
+/// Records `offset..offset + limit` of the JSON array in the file at `path`
+/// (gzipped or not), parsed as the file streams past so only the window is
+/// ever held in memory; the rest are skipped without being built. For that
+/// reason the size limit read_capped applies isn't needed here.
+pub fn read_records(
+    path: &Path,
+    name: &str,
+    offset: usize,
+    limit: usize,
+) -> Result<RecordWindow, CommandError> {
+    let failed = |e: std::io::Error| CommandError::io(format_args!("Failed to read {}", name), &e);
+    let mut reader = std::io::BufReader::new(std::fs::File::open(path).map_err(failed)?);
+    let head = reader.fill_buf().map_err(failed)?;
+    let is_gzip = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("gz"))
+        || head.starts_with(&GZIP_MAGIC);
+    let reader: Box<dyn BufRead> = if is_gzip {
+        Box::new(std::io::BufReader::new(flate2::bufread::GzDecoder::new(reader)))
+    } else {
+        Box::new(reader)
+    };
+    stream_window(reader, offset, limit)
+        .map_err(|e| CommandError::Parse(format!("Failed to parse {}: {}", name, e)))
+}
+
+fn stream_window(
+    mut reader: impl BufRead,
+    offset: usize,
+    limit: usize,
+) -> Result<RecordWindow, serde_json::Error> {
+    use serde::de::{Deserializer as _, IgnoredAny, SeqAccess, Visitor};
+
+    struct Window {
+        offset: usize,
+        limit: usize,
+    }
+
+    impl<'de> Visitor<'de> for Window {
+        type Value = (Vec<serde_json::Value>, usize);
+
+        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            f.write_str("an array of records")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut records = Vec::with_capacity(self.limit.min(1024));
+            let mut total = 0;
+            loop {
+                let in_window = total >= self.offset && records.len() < self.limit;
+                let more = if in_window {
+                    seq.next_element::<serde_json::Value>()?.map(|record| records.push(record)).is_some()
+                } else {
+                    seq.next_element::<IgnoredAny>()?.is_some()
+                };
+                if !more {
+                    return Ok((records, total));
+                }
+                total += 1;
+            }
+        }
+    }
+
+    // serde_json rejects the byte order mark Windows editors write.
+    if reader.fill_buf().map_err(serde_json::Error::io)?.starts_with(b"\xEF\xBB\xBF") {
+        reader.consume(3);
+    }
+    let mut de = serde_json::Deserializer::from_reader(reader);
+    let (records, total) = (&mut de).deserialize_seq(Window { offset, limit })?;
+    de.end()?;
+    Ok(RecordWindow { records, offset, total })
+}
+
+/// JSON as a person might hand-edit it, made strict: `//` and `/* */`
+/// comments blanked out and commas before a closing `}` or `]` dropped,
+/// leaving strings alone. Comments become spaces (newlines kept), so parse
+/// errors still point at the right line. None if there was nothing to fix.
+pub fn relax_json(text: &str) -> Option<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut uncommented = String::with_capacity(text.len());
+    let mut changed = false;
+    let mut in_string = false;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if in_string {
+            uncommented.push(c);
+            if c == '\\' {
+                if let Some(&escaped) = chars.get(i + 1) {
+                    uncommented.push(escaped);
+                    i += 1;
+                }
+            } else if c == '"' {
+                in_string = false;
+            }
+        } else if c == '/' && chars.get(i + 1) == Some(&'/') {
+            changed = true;
+            while i < chars.len() && chars[i] != '\n' {
+                uncommented.push(' ');
+                i += 1;
+            }
+            continue;
+        } else if c == '/' && chars.get(i + 1) == Some(&'*') {
+            changed = true;
+            let end = (i + 2..chars.len().saturating_sub(1))
+                .find(|&j| chars[j] == '*' && chars[j + 1] == '/')
+                .map_or(chars.len(), |j| j + 2);
+            uncommented.extend(chars[i..end].iter().map(|&c| if c == '\n' { '\n' } else { ' ' }));
+            i = end;
+            continue;
+        } else {
+            in_string = c == '"';
+            uncommented.push(c);
+        }
+        i += 1;
+    }
+
+    let chars: Vec<char> = uncommented.chars().collect();
+    let mut relaxed = String::with_capacity(uncommented.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, &c) in chars.iter().enumerate() {
+        if in_string {
+            in_string = escaped || c != '"';
+            escaped = !escaped && c == '\\';
+        } else if c == '"' {
+            in_string = true;
+        } else if c == ',' {
+            let next = chars[i + 1..].iter().find(|c| !c.is_whitespace());
+            if matches!(next, Some('}') | Some(']')) {
+                changed = true;
+                relaxed.push(' ');
+                continue;
+            }
+        }
+        relaxed.push(c);
+    }
+    changed.then_some(relaxed)
+}
+
+/// Replace `path` with `contents` by writing a sibling temp file and
+/// renaming it over the original.
+pub fn write_atomic(path: &Path, contents: &str) -> Result<(), CommandError> {
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let tmp = path.with_file_name(format!(".{}.{}.tmp", file_name, std::process::id()));
+    std::fs::write(&tmp, contents)
+        .map_err(|e| CommandError::io(format_args!("Failed to write {}", tmp.display()), &e))?;
+    std::fs::rename(&tmp, path).map_err(|e| {
+        let _ = std::fs::remove_file(&tmp);
+        CommandError::io(format_args!("Failed to replace {}", path.display()), &e)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn record_windows_stream_from_large_arrays() {
+        let dir = TempDir::new("records");
+        let records: Vec<String> = (0..20_000)
+            .map(|i| format!(r#"{{"id": {}, "path": "docs/file-{}.pdf", "tags": ["a", "b"]}}"#, i, i))
+            .collect();
+        let path = dir.write("file_index.json", &format!("\u{feff}[{}]", records.join(",\n")));
+
+        let window = read_records(&path, "file_index.json", 19_990, 25).unwrap();
+        assert_eq!(window.total, 20_000);
+        let ids: Vec<u64> = window.records.iter().map(|r| r["id"].as_u64().unwrap()).collect();
+        assert_eq!(ids, (19_990..20_000).collect::<Vec<u64>>());
+        assert_eq!(read_records(&path, "file_index.json", 3, 2).unwrap().records[1]["path"], "docs/file-4.pdf");
+        assert!(read_records(&path, "file_index.json", 30_000, 5).unwrap().records.is_empty());
+
+        let path = dir.write("not_an_array.json", r#"{"id": 1}"#);
+        let err = read_records(&path, "not_an_array.json", 0, 5).unwrap_err();
+        assert!(matches!(err, CommandError::Parse(_)), "{:?}", err);
+        let path = dir.write("truncated.json", r#"[{"id": 1}, {"id""#);
+        assert!(read_records(&path, "truncated.json", 0, 1).is_err());
+    }
+
+    #[test]
+    fn pointers_select_part_of_a_document() {
+        let doc = serde_json::json!({
+            "settings": { "theme": "dark", "a/b": 1, "m~n": [10, 20] },
+        });
+        let select = |pointer: &str| select_pointer(doc.clone(), pointer, "viewer.json");
+        assert_eq!(select("/settings/theme"), Ok(serde_json::json!("dark")));
+        assert_eq!(select("/settings/a~1b"), Ok(serde_json::json!(1)));
+        assert_eq!(select("/settings/m~0n/1"), Ok(serde_json::json!(20)));
+        assert_eq!(select(""), Ok(doc.clone()));
+
+        for dangling in ["/settings/font", "/settings/m~0n/2", "/settings/theme/x"] {
+            let err = select(dangling).unwrap_err();
+            assert!(matches!(err, CommandError::NotFound(_)), "{:?}", err);
+            assert!(err.message().contains(dangling), "{}", err);
+        }
+        assert!(matches!(select("settings"), Err(CommandError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn files_over_the_limit_are_refused() {
+        let dir = TempDir::new("config-cap");
+        let path = dir.write("big.json", &"x".repeat(2048));
+        assert_eq!(read_capped(&path, "big.json", 4096).unwrap().len(), 2048);
+        let err = read_capped(&path, "big.json", 1024).unwrap_err();
+        assert!(matches!(err, CommandError::TooLarge(_)), "{:?}", err);
+        assert!(err.message().starts_with("big.json exceeds size limit"), "{}", err);
+
+        std::fs::write(&path, b"caf\xe9").unwrap();
+        let err = read_capped(&path, "big.json", 4096).unwrap_err();
+        assert!(err.message().starts_with("big.json is not valid UTF-8 text"), "{}", err);
+    }
+
+    #[test]
+    fn slotignore_matches_names_anywhere_and_paths_from_the_root() {
+        let ignore = SlotIgnore::parse("# archived work\narchive-*\n\n/clients/old/\ntemplates/**\n[\n");
+        assert!(ignore.is_ignored("archive-2022"));
+        assert!(ignore.is_ignored("clients/archive-pump"));
+        assert!(ignore.is_ignored("clients/old"));
+        assert!(ignore.is_ignored("templates/base/starter"));
+        assert!(!ignore.is_ignored("pump-upgrade"));
+        assert!(!ignore.is_ignored("other/clients/old"));
+        assert!(!SlotIgnore::default().is_ignored("archive-2022"));
+    }
+
+    #[test]
+    fn gzip_files_read_like_plain_ones() {
+        use std::io::Write;
+        let dir = TempDir::new("config-gzip");
+        let json = r#"{"files": [{"path": "a.pdf"}]}"#;
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(json.as_bytes()).unwrap();
+        let compressed = encoder.finish().unwrap();
+        let gz = dir.path().join("file_index.json.gz");
+        std::fs::write(&gz, &compressed).unwrap();
+        // No .gz extension: recognized by its magic bytes instead.
+        let sniffed = dir.path().join("file_index.json");
+        std::fs::write(&sniffed, &compressed).unwrap();
+
+        assert_eq!(read_capped(&gz, "file_index.json.gz", 4096).unwrap(), json);
+        assert_eq!(read_capped(&sniffed, "file_index.json", 4096).unwrap(), json);
+
+        // Small on disk but over the limit once inflated.
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(" ".repeat(100_000).as_bytes()).unwrap();
+        let bomb = dir.path().join("bomb.json.gz");
+        std::fs::write(&bomb, encoder.finish().unwrap()).unwrap();
+        let err = read_capped(&bomb, "bomb.json.gz", 4096).unwrap_err();
+        assert!(err.message().starts_with("bomb.json.gz exceeds size limit (4097 bytes"), "{}", err);
+    }
+
+    #[test]
+    fn relaxing_drops_comments_and_trailing_commas_outside_strings() {
+        let text = "{\n  // the client\n  \"name\": \"Acme // Plant 1,]\", /* note */\n  \"sites\": [1, 2,],\n}\n";
+        let relaxed = relax_json(text).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&relaxed).unwrap();
+        assert_eq!(value, serde_json::json!({ "name": "Acme // Plant 1,]", "sites": [1, 2] }));
+        assert_eq!(relaxed.lines().count(), text.lines().count());
+
+        assert_eq!(relax_json(r#"{"a": "b\",}"}"#), None);
+        // A comment that never closes runs to the end.
+        assert_eq!(relax_json("[1] /* open").as_deref(), Some("[1]        "));
+    }
+}
@@ -0,0 +1,155 @@
+// ── Self-test for the About/Diagnostics panel ──────────────────────────────
+
+use crate::launch::LaunchConfig;
+use crate::logging;
+use crate::telemetry;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// What self_test found, for pasting into a support ticket.
+#[derive(Debug, serde::Serialize)]
+pub struct SelfTestReport {
+    pub app_version: &'static str,
+    pub tauri_version: &'static str,
+    pub os: &'static str,
+    pub arch: &'static str,
+    pub exe_dir: Option<String>,
+    pub exe_dir_writable: bool,
+    pub log_dir: String,
+    pub log_dir_writable: bool,
+    /// Each shell the terminal may start, and whether it could be run.
+    pub shells: Vec<ShellCheck>,
+    /// The repo given on the command line, if any.
+    pub repo: Option<String>,
+    pub repo_has_company_dir: bool,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct ShellCheck {
+    pub name: &'static str,
+    pub available: bool,
+}
+
+/// Which build of the viewer is running, for the UI to show and support to
+/// ask about.
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct AppVersion {
+    pub version: &'static str,
+    /// The commit it was built from, abbreviated, or `unknown`.
+    pub git_sha: &'static str,
+    /// `YYYY-MM-DD`, or `unknown`.
+    pub build_date: &'static str,
+    pub tauri_version: &'static str,
+}
+
+/// The viewer's version and build commit and date, as build.rs recorded them,
+/// and the version of Tauri it was built with.
+#[tauri::command]
+pub fn get_app_version() -> AppVersion {
+    AppVersion {
+        version: env!("CARGO_PKG_VERSION"),
+        git_sha: env!("SL_OT_GIT_SHA"),
+        build_date: env!("SL_OT_BUILD_DATE"),
+        tauri_version: tauri::VERSION,
+    }
+}
+
+/// Check the environment the viewer depends on: where it can write, which
+/// shells the terminal can start, and the repo it was launched with.
+#[tauri::command(async)]
+pub fn self_test(app: tauri::AppHandle, launch: tauri::State<'_, LaunchConfig>) -> SelfTestReport {
+    telemetry::timed_value(&app, "self_test", || run_self_test(&launch))
+}
+
+fn run_self_test(launch: &LaunchConfig) -> SelfTestReport {
+    let exe_dir = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf));
+    let log_dir = logging::log_dir();
+    let shell_names: &[&'static str] = if cfg!(target_os = "windows") {
+        &["wsl.exe", "cmd.exe", "bash"]
+    } else {
+        &["bash"]
+    };
+    let shells = shell_names
+        .iter()
+        .map(|&name| ShellCheck { name, available: runs(name) })
+        .collect();
+
+    let report = SelfTestReport {
+        app_version: env!("CARGO_PKG_VERSION"),
+        tauri_version: tauri::VERSION,
+        os: std::env::consts::OS,
+        arch: std::env::consts::ARCH,
+        exe_dir_writable: exe_dir.as_deref().is_some_and(is_writable),
+        exe_dir: exe_dir.map(|dir| dir.display().to_string()),
+        log_dir_writable: is_writable(&log_dir),
+        log_dir: log_dir.display().to_string(),
+        shells,
+        repo_has_company_dir: launch
+            .repo
+            .as_deref()
+            .is_some_and(|repo| Path::new(repo).join("_company").is_dir()),
+        repo: launch.repo.clone(),
+    };
+    tracing::info!(?report, "self_test");
+    report
+}
+
+/// Whether a file can be created in `dir`, found by creating (and removing)
+/// one.
+fn is_writable(dir: &Path) -> bool {
+    let probe = dir.join(format!(".sl-ot-viewer-probe-{}", std::process::id()));
+    match std::fs::write(&probe, b"") {
+        Ok(()) => {
+            let _ = std::fs::remove_file(&probe);
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+/// Whether `program` can be started at all. It's run with a no-op argument
+/// and no console, and how it exits doesn't matter.
+fn runs(program: &str) -> bool {
+    let probe_args: &[&str] = match program {
+        "cmd.exe" => &["/C", "exit"],
+        "wsl.exe" => &["--status"],
+        _ => &["-c", "exit"],
+    };
+    Command::new(program)
+        .args(probe_args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn probes_writability_and_programs() {
+        let dir = TempDir::new("self-test");
+        assert!(is_writable(dir.path()));
+        assert!(!is_writable(&dir.path().join("missing")));
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+        assert!(!runs("no-such-shell-here"));
+    }
+
+    #[test]
+    fn build_info_is_recorded() {
+        let version = get_app_version();
+        assert_eq!(version.version, env!("CARGO_PKG_VERSION"));
+        assert!(!version.git_sha.is_empty());
+        let date = version.build_date;
+        assert!(
+            date == "unknown" || chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").is_ok(),
+            "{}",
+            date
+        );
+    }
+}
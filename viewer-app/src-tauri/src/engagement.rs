@@ -0,0 +1,490 @@
+// ── New engagement scaffolding ─────────────────────────────────────────────
+
+use crate::company::{self, CompanyCache};
+use crate::config;
+use crate::error::CommandError;
+use crate::launch::LaunchConfig;
+use crate::knowledge;
+use crate::paths;
+use crate::telemetry;
+use std::path::{Path, PathBuf};
+
+/// Create engagement `name` in the repo: the `<name>/` folder with
+/// `config` written as its engagement_config.json, and an entry for it in
+/// `_company/engagement_registry.json`. With `create_workstreams`, each
+/// workstream in the config also gets its folder (`output_dir`, else its
+/// key) holding an empty KNOWLEDGE_LOG.md.
+///
+/// `config` must be an object. `engagement` (the registry key) defaults to
+/// `name` and `engagement_label` to `name` in title case. An existing folder
+/// or registry entry is never overwritten, and if any step fails the new
+/// folder is removed again. Returns the config as written.
+// Each argument is a named parameter of the frontend's invoke call.
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+pub fn create_engagement(
+    app: tauri::AppHandle,
+    cache: tauri::State<'_, CompanyCache>,
+    launch: tauri::State<'_, LaunchConfig>,
+    viewer_config: tauri::State<'_, config::ViewerConfig>,
+    repo_path: String,
+    name: String,
+    config: serde_json::Value,
+    create_workstreams: Option<bool>,
+) -> Result<serde_json::Value, CommandError> {
+    telemetry::timed(&app, "create_engagement", || {
+        let base = PathBuf::from(&repo_path);
+        let create_workstreams = create_workstreams.unwrap_or(false);
+        let written = scaffold(&launch, &base, &name, config, create_workstreams, viewer_config.max_file_bytes)?;
+        cache.lock()?.remove(&base);
+        Ok(written)
+    })
+}
+
+fn scaffold(
+    launch: &LaunchConfig,
+    base: &Path,
+    name: &str,
+    config: serde_json::Value,
+    create_workstreams: bool,
+    max_bytes: u64,
+) -> Result<serde_json::Value, CommandError> {
+    launch.ensure_writable("create_engagement")?;
+    let name = paths::plain_name(name)?;
+    let config = complete_config(name, config)?;
+    let key = config["engagement"].as_str().unwrap_or(name).to_string();
+    let workstream_dirs = workstream_dirs(&config)?;
+
+    let company_dir = company::company_dir(base)?;
+    let mut registry = match company::find_company_file(&company_dir, "engagement_registry.json") {
+        Some(path) => company::parse_company_file(&path, &company::file_name(&path), max_bytes)?,
+        None => serde_json::json!({ "engagements": {} }),
+    };
+    let engagements = registry
+        .as_object_mut()
+        .ok_or_else(|| CommandError::Parse("engagement_registry.json is not an object".to_string()))?
+        .entry("engagements")
+        .or_insert_with(|| serde_json::json!({}))
+        .as_object_mut()
+        .ok_or_else(|| {
+            CommandError::Parse("engagement_registry.json's engagements is not an object".to_string())
+        })?;
+    if engagements.contains_key(&key) {
+        return Err(CommandError::AlreadyExists(format!("Engagement {} is already registered", key)));
+    }
+    engagements.insert(key.clone(), registry_entry(&config));
+
+    let dir = base.join(name);
+    // create_dir (not _all) fails if the folder exists, even if it appeared
+    // since the checks above.
+    std::fs::create_dir(&dir).map_err(|e| match e.kind() {
+        std::io::ErrorKind::AlreadyExists => {
+            CommandError::AlreadyExists(format!("{} already exists", dir.display()))
+        }
+        _ => CommandError::io(format_args!("Failed to create {}", dir.display()), &e),
+    })?;
+
+    let result = (|| {
+        let contents = serde_json::to_string_pretty(&config)
+            .map_err(|e| {
+                CommandError::Internal(format!("Failed to serialize engagement_config.json: {}", e))
+            })?
+            + "\n";
+        config::write_atomic(&dir.join("engagement_config.json"), &contents)?;
+        if create_workstreams {
+            for ws in &workstream_dirs {
+                let ws_dir = dir.join(ws);
+                std::fs::create_dir_all(&ws_dir)
+                    .map_err(|e| CommandError::io(format_args!("Failed to create {}", ws_dir.display()), &e))?;
+                config::write_atomic(&ws_dir.join(knowledge::DEFAULT_LOG_NAME), "")?;
+            }
+        }
+        company::save_company_file(base, "engagement_registry", &registry)
+    })();
+    if let Err(e) = result {
+        if let Err(cleanup) = std::fs::remove_dir_all(&dir) {
+            tracing::warn!("Failed to remove {}: {}", dir.display(), cleanup);
+        }
+        return Err(e);
+    }
+    tracing::info!(engagement = %key, dir = %dir.display(), "created engagement");
+    Ok(config)
+}
+
+/// Rename engagement folder `old_name` to `new_name`, changing its key in
+/// `_company/engagement_registry.json` and `_company/engagement_map.json` to
+/// match, along with the `engagement` in its engagement_config.json. That's
+/// when the key is the folder name, as create_engagement makes it; an
+/// engagement keyed otherwise only has its folder renamed.
+///
+/// Refuses if `new_name` exists or is already a key in either file. The
+/// company files are written as write_company_file writes them, each with a
+/// `.bak` of the old one, and if any step fails everything is put back.
+#[tauri::command]
+pub fn rename_engagement(
+    app: tauri::AppHandle,
+    cache: tauri::State<'_, CompanyCache>,
+    launch: tauri::State<'_, LaunchConfig>,
+    config: tauri::State<'_, config::ViewerConfig>,
+    repo_path: String,
+    old_name: String,
+    new_name: String,
+) -> Result<(), CommandError> {
+    telemetry::timed(&app, "rename_engagement", || {
+        let base = PathBuf::from(&repo_path);
+        rename(&launch, &base, &old_name, &new_name, config.max_file_bytes)?;
+        cache.lock()?.remove(&base);
+        Ok(())
+    })
+}
+
+fn rename(
+    launch: &LaunchConfig,
+    base: &Path,
+    old_name: &str,
+    new_name: &str,
+    max_bytes: u64,
+) -> Result<(), CommandError> {
+    launch.ensure_writable("rename_engagement")?;
+    let old_name = paths::plain_name(old_name)?;
+    let new_name = paths::plain_name(new_name)?;
+    let old_dir = base.join(old_name);
+    let new_dir = base.join(new_name);
+    let config_file = "engagement_config.json";
+    if !old_dir.join(config_file).is_file() {
+        return Err(CommandError::NotFound(format!("No engagement at {}", old_dir.display())));
+    }
+    if new_dir.symlink_metadata().is_ok() {
+        return Err(CommandError::AlreadyExists(format!("{} already exists", new_dir.display())));
+    }
+
+    let config_text = config::read_capped(&old_dir.join(config_file), config_file, max_bytes)?;
+    let mut engagement_config: serde_json::Value = serde_json::from_str(company::strip_bom(&config_text))
+        .map_err(|e| CommandError::Parse(format!("Failed to parse {}: {}", config_file, e)))?;
+    let old_key = engagement_config["engagement"].as_str().unwrap_or(old_name).to_string();
+    let new_key = if old_key == old_name { new_name.to_string() } else { old_key.clone() };
+    let taken = || CommandError::AlreadyExists(format!("Engagement {} is already registered", new_key));
+
+    // (COMPANY_FILES key, path, contents to write)
+    let mut updates: Vec<(&str, PathBuf, serde_json::Value)> = Vec::new();
+    let mut config_changed = false;
+    if new_key != old_key {
+        let company_dir = company::company_dir(base)?;
+        if let Some(path) = company::find_company_file(&company_dir, "engagement_registry.json") {
+            let mut registry = company::parse_company_file(&path, "engagement_registry.json", max_bytes)?;
+            if let Some(engagements) = registry.get_mut("engagements") {
+                let engagements = engagements.as_object_mut().ok_or_else(|| {
+                    CommandError::Parse("engagement_registry.json's engagements is not an object".to_string())
+                })?;
+                if engagements.contains_key(&new_key) {
+                    return Err(taken());
+                }
+                if let Some(entry) = engagements.remove(&old_key) {
+                    engagements.insert(new_key.clone(), entry);
+                    updates.push(("engagement_registry", path, registry));
+                }
+            }
+        }
+        if let Some(path) = company::find_company_file(&company_dir, "engagement_map.json") {
+            let mut map = company::parse_company_file(&path, "engagement_map.json", max_bytes)?;
+            let keys = map["engagements"].as_array_mut().into_iter().flatten().map(|eng| &mut eng["key"]);
+            let mut renamed = false;
+            for key in keys {
+                if *key == new_key.as_str() {
+                    return Err(taken());
+                }
+                if *key == old_key.as_str() {
+                    *key = new_key.clone().into();
+                    renamed = true;
+                }
+            }
+            if renamed {
+                updates.push(("engagement_map", path, map));
+            }
+        }
+        if engagement_config.get("engagement").is_some() {
+            engagement_config["engagement"] = new_key.clone().into();
+            config_changed = true;
+        }
+    }
+
+    std::fs::rename(&old_dir, &new_dir).map_err(|e| {
+        CommandError::io(format_args!("Failed to rename {} to {}", old_dir.display(), new_name), &e)
+    })?;
+    // Files written so far, with what they held before.
+    let mut written: Vec<(PathBuf, String)> = Vec::new();
+    let result = (|| {
+        if config_changed {
+            let path = new_dir.join(config_file);
+            let contents = serde_json::to_string_pretty(&engagement_config)
+                .map_err(|e| CommandError::Internal(format!("Failed to serialize {}: {}", config_file, e)))?
+                + "\n";
+            config::write_atomic(&path, &contents)?;
+            written.push((path, config_text.clone()));
+        }
+        for (key, path, value) in &updates {
+            let original = config::read_capped(path, &company::file_name(path), max_bytes)?;
+            company::save_company_file(base, key, value)?;
+            written.push((path.clone(), original));
+        }
+        Ok(())
+    })();
+    if let Err(e) = result {
+        for (path, original) in written.iter().rev() {
+            if let Err(restore) = config::write_atomic(path, original) {
+                tracing::warn!("Failed to restore {}: {}", path.display(), restore);
+            }
+        }
+        if let Err(restore) = std::fs::rename(&new_dir, &old_dir) {
+            tracing::warn!("Failed to rename {} back: {}", new_dir.display(), restore);
+        }
+        return Err(e);
+    }
+    tracing::info!(from = %old_name, to = %new_name, key = %new_key, "renamed engagement");
+    Ok(())
+}
+
+/// Check `config` is shaped like an engagement_config.json and fill in
+/// `engagement` and `engagement_label` from `name` where missing.
+fn complete_config(name: &str, config: serde_json::Value) -> Result<serde_json::Value, CommandError> {
+    let serde_json::Value::Object(mut config) = config else {
+        return Err(CommandError::InvalidInput("Engagement config must be a JSON object".to_string()));
+    };
+    for field in ["engagement", "engagement_label"] {
+        if config.get(field).is_some_and(|v| !v.is_string()) {
+            return Err(CommandError::InvalidInput(format!("Engagement config's {} must be a string", field)));
+        }
+    }
+    if config.get("engagement").is_none() {
+        config.insert("engagement".to_string(), name.into());
+    }
+    if config.get("engagement_label").is_none() {
+        config.insert("engagement_label".to_string(), title_case(name).into());
+    }
+    match config.get("workstreams") {
+        None => {}
+        Some(serde_json::Value::Object(workstreams)) => {
+            if let Some((ws, _)) = workstreams.iter().find(|(_, ws)| !ws.is_object()) {
+                return Err(CommandError::InvalidInput(format!("Workstream {} must be a JSON object", ws)));
+            }
+        }
+        Some(_) => {
+            let message = "Engagement config's workstreams must be an object".to_string();
+            return Err(CommandError::InvalidInput(message));
+        }
+    }
+    Ok(serde_json::Value::Object(config))
+}
+
+/// Each workstream's folder name: its `output_dir`, else its key.
+fn workstream_dirs(config: &serde_json::Value) -> Result<Vec<String>, CommandError> {
+    let Some(workstreams) = config["workstreams"].as_object() else {
+        return Ok(Vec::new());
+    };
+    workstreams
+        .iter()
+        .map(|(key, ws)| {
+            let dir = ws["output_dir"].as_str().unwrap_or(key);
+            paths::plain_name(dir).map(str::to_string)
+        })
+        .collect()
+}
+
+/// A registry entry like the ones `sl-ot-tools migrate-registry` writes.
+fn registry_entry(config: &serde_json::Value) -> serde_json::Value {
+    serde_json::json!({
+        "label": config["engagement_label"],
+        "status": "active",
+        "governance": "steering_committee",
+        "raci": { "responsible": [], "accountable": [], "consulted": [], "informed": [] },
+    })
+}
+
+/// `deal-evaluation` as `Deal Evaluation`, as `sl-ot-tools init engagement`
+/// labels it.
+fn title_case(name: &str) -> String {
+    name.split('-')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first
+                    .to_uppercase()
+                    .chain(chars.flat_map(char::to_lowercase))
+                    .collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    const MAX_BYTES: u64 = 1024 * 1024;
+
+    fn repo() -> TempDir {
+        let dir = TempDir::new("create-engagement");
+        dir.write(
+            "_company/engagement_registry.json",
+            r#"{"governance_types": {}, "engagements": {"existing": {"label": "Existing"}}}"#,
+        );
+        dir.write("existing/engagement_config.json", "{}");
+        dir
+    }
+
+    #[test]
+    fn created_engagements_read_back() {
+        let (dir, launch) = (repo(), LaunchConfig::default());
+        let config = serde_json::json!({
+            "workstreams": {
+                "ops": { "label": "01-Ops", "output_dir": "01-Ops" },
+                "it": { "label": "IT" },
+            },
+        });
+        let written = scaffold(&launch, dir.path(), "pump-upgrade", config, true, MAX_BYTES).unwrap();
+        assert_eq!(written["engagement"], "pump-upgrade");
+        assert_eq!(written["engagement_label"], "Pump Upgrade");
+
+        let engagements = company::engagement_dirs(dir.path(), &config::ViewerConfig::default());
+        assert_eq!(
+            engagements,
+            [dir.path().join("existing"), dir.path().join("pump-upgrade")]
+        );
+        let on_disk: serde_json::Value = serde_json::from_str(
+            &std::fs::read_to_string(dir.path().join("pump-upgrade/engagement_config.json"))
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(on_disk, written);
+        let logs: Vec<String> =
+            knowledge::scan_knowledge_logs(&engagements[1], &[], &config::ViewerConfig::default())
+                .into_iter()
+                .map(|log| log.workstream)
+                .collect();
+        assert_eq!(logs, ["01-Ops", "it"]);
+
+        let registry = company::parse_company_file(
+            &dir.path().join("_company/engagement_registry.json"),
+            "engagement_registry.json",
+            MAX_BYTES,
+        )
+        .unwrap();
+        assert_eq!(registry["engagements"]["existing"]["label"], "Existing");
+        assert_eq!(
+            registry["engagements"]["pump-upgrade"]["label"],
+            "Pump Upgrade"
+        );
+        assert_eq!(registry["engagements"]["pump-upgrade"]["status"], "active");
+    }
+
+    #[test]
+    fn renamed_engagements_keep_their_references() {
+        let (dir, launch) = (repo(), LaunchConfig::default());
+        let config = serde_json::json!({ "workstreams": { "ops": {} } });
+        scaffold(&launch, dir.path(), "pump-upgrade", config, true, MAX_BYTES).unwrap();
+        let map = r#"{"engagements": [{"key": "existing", "workstreams": []}, {"key": "pump-upgrade", "workstreams": []}]}"#;
+        dir.write("_company/engagement_map.json", map);
+        let company_file = |name: &str| {
+            company::parse_company_file(&dir.path().join("_company").join(name), name, MAX_BYTES).unwrap()
+        };
+        let registry_before = company_file("engagement_registry.json");
+
+        rename(&launch, dir.path(), "pump-upgrade", "pump-retrofit", MAX_BYTES).unwrap();
+        assert!(!dir.path().join("pump-upgrade").exists());
+        assert!(dir.path().join("pump-retrofit/ops/KNOWLEDGE_LOG.md").is_file());
+        let registry = company_file("engagement_registry.json");
+        assert!(registry["engagements"].get("pump-upgrade").is_none());
+        assert_eq!(registry["engagements"]["pump-retrofit"], registry_before["engagements"]["pump-upgrade"]);
+        assert_eq!(company_file("engagement_map.json")["engagements"][1]["key"], "pump-retrofit");
+        assert!(dir.path().join("_company/engagement_map.json.bak").is_file());
+        let on_disk = std::fs::read_to_string(dir.path().join("pump-retrofit/engagement_config.json")).unwrap();
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&on_disk).unwrap()["engagement"], "pump-retrofit");
+
+        rename(&launch, dir.path(), "pump-retrofit", "pump-upgrade", MAX_BYTES).unwrap();
+        assert_eq!(company_file("engagement_registry.json"), registry_before);
+        assert_eq!(company_file("engagement_map.json"), serde_json::from_str::<serde_json::Value>(map).unwrap());
+
+        let err = rename(&launch, dir.path(), "pump-upgrade", "existing", MAX_BYTES).unwrap_err();
+        assert!(matches!(err, CommandError::AlreadyExists(_)), "{:?}", err);
+        let err = rename(&launch, dir.path(), "missing", "other", MAX_BYTES).unwrap_err();
+        assert!(matches!(err, CommandError::NotFound(_)), "{:?}", err);
+        assert!(rename(&launch, dir.path(), "pump-upgrade", "../escape", MAX_BYTES).is_err());
+
+        // The map can't be written once gzipped, which is found out after the
+        // folder, config and registry have changed; all of them go back.
+        std::fs::remove_file(dir.path().join("_company/engagement_map.json")).unwrap();
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        std::io::Write::write_all(&mut gz, map.as_bytes()).unwrap();
+        std::fs::write(dir.path().join("_company/engagement_map.json.gz"), gz.finish().unwrap()).unwrap();
+        let err = rename(&launch, dir.path(), "pump-upgrade", "pump-retrofit", MAX_BYTES).unwrap_err();
+        assert!(err.message().contains("read-only"), "{}", err);
+        assert!(dir.path().join("pump-upgrade").is_dir() && !dir.path().join("pump-retrofit").exists());
+        assert_eq!(company_file("engagement_registry.json"), registry_before);
+        let on_disk = std::fs::read_to_string(dir.path().join("pump-upgrade/engagement_config.json")).unwrap();
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&on_disk).unwrap()["engagement"], "pump-upgrade");
+    }
+
+    #[test]
+    fn refuses_to_overwrite_and_rolls_back() {
+        let (dir, launch) = (repo(), LaunchConfig::default());
+        let err = scaffold(&launch, dir.path(), "existing", serde_json::json!({}), false, MAX_BYTES).unwrap_err();
+        assert!(matches!(err, CommandError::AlreadyExists(_)), "{:?}", err);
+        assert!(err.message().contains("already registered"), "{}", err);
+
+        dir.write("unregistered/notes.md", "keep me");
+        let err = scaffold(&launch, dir.path(), "unregistered", serde_json::json!({}), false, MAX_BYTES).unwrap_err();
+        assert!(err.message().contains("already exists"), "{}", err);
+        assert!(dir.path().join("unregistered/notes.md").exists());
+
+        let config = serde_json::json!({ "workstreams": { "ops": { "output_dir": "../escape" } } });
+        assert!(scaffold(&launch, dir.path(), "bad", config, true, MAX_BYTES).is_err());
+        assert!(scaffold(&launch, dir.path(), "../bad", serde_json::json!({}), false, MAX_BYTES).is_err());
+        assert!(scaffold(&launch, dir.path(), "bad", serde_json::json!([]), false, MAX_BYTES).is_err());
+
+        // A gzipped registry reads fine but can't be written, which is found
+        // out only after the folder and its files are made.
+        std::fs::remove_file(dir.path().join("_company/engagement_registry.json")).unwrap();
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        std::io::Write::write_all(&mut gz, br#"{"engagements": {}}"#).unwrap();
+        std::fs::write(
+            dir.path().join("_company/engagement_registry.json.gz"),
+            gz.finish().unwrap(),
+        )
+        .unwrap();
+        let err = scaffold(
+            &launch,
+            dir.path(),
+            "fresh",
+            serde_json::json!({ "workstreams": { "ops": {} } }),
+            true,
+            MAX_BYTES,
+        )
+        .unwrap_err();
+        assert!(err.message().contains("read-only"), "{}", err);
+        assert!(!dir.path().join("fresh").exists());
+        assert!(!dir.path().join("bad").exists());
+    }
+
+    #[test]
+    fn read_only_refuses_to_create_or_rename() {
+        let dir = repo();
+        let launch = LaunchConfig { read_only: true, ..LaunchConfig::default() };
+        let registry_path = dir.path().join("_company/engagement_registry.json");
+        let registry_before = std::fs::read_to_string(&registry_path).unwrap();
+
+        let config = serde_json::json!({ "workstreams": { "ops": {} } });
+        let err = scaffold(&launch, dir.path(), "fresh", config, true, MAX_BYTES).unwrap_err();
+        assert!(matches!(err, CommandError::ReadOnly(_)), "{:?}", err);
+        let err = rename(&launch, dir.path(), "existing", "renamed", MAX_BYTES).unwrap_err();
+        assert!(matches!(err, CommandError::ReadOnly(_)), "{:?}", err);
+
+        assert!(!dir.path().join("fresh").exists());
+        assert!(!dir.path().join("renamed").exists());
+        assert!(dir.path().join("existing/engagement_config.json").is_file());
+        assert_eq!(std::fs::read_to_string(&registry_path).unwrap(), registry_before);
+        assert!(!dir.path().join("_company/engagement_registry.json.bak").exists());
+    }
+}
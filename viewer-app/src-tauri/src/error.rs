@@ -0,0 +1,94 @@
+// ── Command errors (what the frontend gets when a command fails) ───────────
+
+use std::fmt;
+use std::sync::PoisonError;
+
+/// Why a command failed. It reaches the frontend as `{kind, message}`, with
+/// `kind` the variant in snake_case (`not_found`, `path_traversal`, ...), so
+/// the UI can choose what to offer by kind and show the message as it is.
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+#[serde(tag = "kind", content = "message", rename_all = "snake_case")]
+pub enum CommandError {
+    /// A file, folder, engagement, terminal or tail that isn't there.
+    NotFound(String),
+    /// A terminal whose shell has exited; its session is gone, and the UI
+    /// should spawn a new one.
+    TerminalNotRunning(String),
+    /// A file that was read but couldn't be parsed.
+    Parse(String),
+    /// A path that would lead outside the folder it has to stay in.
+    PathTraversal(String),
+    /// A file over the size limit.
+    TooLarge(String),
+    /// Arguments the command can't act on.
+    InvalidInput(String),
+    /// Something already there that won't be overwritten.
+    AlreadyExists(String),
+    /// A write or a process refused because the viewer was launched with
+    /// `--read-only`.
+    ReadOnly(String),
+    /// Reading, writing or starting a process failed.
+    Io(String),
+    /// Anything else, like a lock poisoned by a panic.
+    Internal(String),
+}
+
+impl CommandError {
+    /// `context: e`, classed by the kind of I/O error.
+    pub fn io(context: impl fmt::Display, e: &std::io::Error) -> Self {
+        let message = format!("{}: {}", context, e);
+        match e.kind() {
+            std::io::ErrorKind::NotFound => CommandError::NotFound(message),
+            std::io::ErrorKind::AlreadyExists => CommandError::AlreadyExists(message),
+            _ => CommandError::Io(message),
+        }
+    }
+
+    /// The text shown to the user.
+    pub fn message(&self) -> &str {
+        match self {
+            CommandError::NotFound(message)
+            | CommandError::TerminalNotRunning(message)
+            | CommandError::Parse(message)
+            | CommandError::PathTraversal(message)
+            | CommandError::TooLarge(message)
+            | CommandError::InvalidInput(message)
+            | CommandError::AlreadyExists(message)
+            | CommandError::ReadOnly(message)
+            | CommandError::Io(message)
+            | CommandError::Internal(message) => message,
+        }
+    }
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+impl<T> From<PoisonError<T>> for CommandError {
+    fn from(e: PoisonError<T>) -> Self {
+        CommandError::Internal(e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_as_kind_and_message() {
+        let err = CommandError::PathTraversal("Path escapes /repo: ../x".to_string());
+        assert_eq!(
+            serde_json::to_value(&err).unwrap(),
+            serde_json::json!({ "kind": "path_traversal", "message": "Path escapes /repo: ../x" })
+        );
+        let missing = std::io::Error::from(std::io::ErrorKind::NotFound);
+        let err = CommandError::io("Failed to read a.json", &missing);
+        assert!(matches!(err, CommandError::NotFound(_)), "{:?}", err);
+        assert!(err.to_string().starts_with("Failed to read a.json: "), "{}", err);
+    }
+}
@@ -0,0 +1,193 @@
+// ── Git status of the engagement repo ──────────────────────────────────────
+
+use crate::error::CommandError;
+use crate::telemetry;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Where the repo stands against its upstream and what's uncommitted.
+#[derive(Debug, Default, PartialEq, serde::Serialize)]
+pub struct GitStatus {
+    /// False if `repo_path` isn't inside a git work tree; everything else is
+    /// then empty.
+    pub is_repo: bool,
+    /// The checked-out branch, or None when HEAD is detached.
+    pub branch: Option<String>,
+    pub upstream: Option<String>,
+    /// Commits not on the upstream, and upstream commits not here.
+    pub ahead: u32,
+    pub behind: u32,
+    pub changes: Vec<GitChange>,
+}
+
+/// One changed or untracked path.
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct GitChange {
+    pub path: String,
+    /// Git's two-letter status: staged then unstaged, `.` for unchanged
+    /// (`.M` is modified but not staged), or `??` for untracked.
+    pub status: String,
+    /// The old path of a rename or copy.
+    pub orig_path: Option<String>,
+}
+
+/// Branch, upstream divergence and changed paths of the repo at
+/// `repo_path`, from `git status`. Needs git on the PATH.
+#[tauri::command(async)]
+pub fn git_status(app: tauri::AppHandle, repo_path: String) -> Result<GitStatus, CommandError> {
+    telemetry::timed(&app, "git_status", || status_of(&repo_path))
+}
+
+fn status_of(repo_path: &str) -> Result<GitStatus, CommandError> {
+    if !Path::new(repo_path).is_dir() {
+        return Err(CommandError::NotFound(format!("Repo path is not a directory: {}", repo_path)));
+    }
+    run_status(status_command(repo_path))
+}
+
+fn status_command(repo_path: &str) -> Command {
+    let mut command = Command::new("git");
+    command
+        .arg("-C")
+        .arg(repo_path)
+        .args(["status", "--porcelain=v2", "--branch", "-z"])
+        // Don't take the index lock to refresh it, which could get in the
+        // way of a commit running at the same time.
+        .env("GIT_OPTIONAL_LOCKS", "0")
+        // Untranslated messages, so a folder outside any repo is recognized
+        // below whatever the user's language.
+        .env("LC_ALL", "C")
+        .stdin(Stdio::null());
+    command
+}
+
+fn run_status(mut command: Command) -> Result<GitStatus, CommandError> {
+    let output = command.output().map_err(|e| CommandError::io("Failed to run git", &e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if stderr.contains("not a git repository") {
+            return Ok(GitStatus::default());
+        }
+        return Err(CommandError::Io(format!("git status failed: {}", stderr.trim())));
+    }
+    Ok(parse_status(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Parse `git status --porcelain=v2 --branch -z` output.
+fn parse_status(output: &str) -> GitStatus {
+    let mut status = GitStatus {
+        is_repo: true,
+        ..Default::default()
+    };
+    let mut records = output.split('\0').filter(|r| !r.is_empty());
+    while let Some(record) = records.next() {
+        if let Some(header) = record.strip_prefix("# ") {
+            let (key, value) = header.split_once(' ').unwrap_or((header, ""));
+            match key {
+                "branch.head" if value != "(detached)" => status.branch = Some(value.to_string()),
+                "branch.upstream" => status.upstream = Some(value.to_string()),
+                "branch.ab" => {
+                    for count in value.split(' ') {
+                        if let Some(n) = count.strip_prefix('+') {
+                            status.ahead = n.parse().unwrap_or(0);
+                        } else if let Some(n) = count.strip_prefix('-') {
+                            status.behind = n.parse().unwrap_or(0);
+                        }
+                    }
+                }
+                _ => {}
+            }
+            continue;
+        }
+        // Fields before the path: ordinary entries have 8, renames and
+        // copies 9 (and the old path as the next record), unmerged 10.
+        let (fields, renamed) = match record.as_bytes().first() {
+            Some(b'1') => (8, false),
+            Some(b'2') => (9, true),
+            Some(b'u') => (10, false),
+            Some(b'?') => {
+                let path = record.get(2..).unwrap_or_default().to_string();
+                status.changes.push(GitChange {
+                    path,
+                    status: "??".to_string(),
+                    orig_path: None,
+                });
+                continue;
+            }
+            _ => continue,
+        };
+        let parts: Vec<&str> = record.splitn(fields + 1, ' ').collect();
+        let (Some(code), Some(path)) = (parts.get(1), parts.get(fields)) else {
+            continue;
+        };
+        let orig_path = if renamed {
+            records.next().map(str::to_string)
+        } else {
+            None
+        };
+        status.changes.push(GitChange {
+            path: path.to_string(),
+            status: code.to_string(),
+            orig_path,
+        });
+    }
+    status
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn parses_branch_divergence_and_changes() {
+        let output = [
+            "# branch.oid 1234",
+            "# branch.head main",
+            "# branch.upstream origin/main",
+            "# branch.ab +2 -1",
+            "1 .M N... 100644 100644 100644 abc abc ops/KNOWLEDGE LOG.md",
+            "2 R. N... 100644 100644 100644 abc abc R100 _company/new.json",
+            "_company/old.json",
+            "u UU N... 100644 100644 100644 100644 a b c notes.md",
+            "? scratch/todo.txt",
+            "",
+        ]
+        .join("\0");
+        let status = parse_status(&output);
+
+        assert_eq!(status.branch.as_deref(), Some("main"));
+        assert_eq!(status.upstream.as_deref(), Some("origin/main"));
+        assert_eq!((status.ahead, status.behind), (2, 1));
+        let changes: Vec<(&str, &str, Option<&str>)> = status
+            .changes
+            .iter()
+            .map(|c| (c.path.as_str(), c.status.as_str(), c.orig_path.as_deref()))
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                ("ops/KNOWLEDGE LOG.md", ".M", None),
+                ("_company/new.json", "R.", Some("_company/old.json")),
+                ("notes.md", "UU", None),
+                ("scratch/todo.txt", "??", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn detached_head_has_no_branch() {
+        let status = parse_status("# branch.oid 1234\0# branch.head (detached)\0");
+        assert!(status.is_repo);
+        assert_eq!(status.branch, None);
+    }
+
+    #[test]
+    fn a_plain_folder_is_not_a_repo() {
+        let dir = TempDir::new("git-none");
+        let mut command = status_command(&dir.path().display().to_string());
+        // Keep git from finding a work tree the temp dir happens to be in.
+        command.env("GIT_CEILING_DIRECTORIES", dir.path().parent().unwrap());
+        assert_eq!(run_status(command), Ok(GitStatus::default()));
+    }
+}
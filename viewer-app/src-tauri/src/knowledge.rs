@@ -0,0 +1,1697 @@
+// ── Knowledge logs (KNOWLEDGE_LOG.md per workstream) ───────────────────────
+
+use crate::config;
+use crate::error::CommandError;
+use crate::launch::LaunchConfig;
+use crate::telemetry;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+
+/// A workstream's knowledge log, found by scan_knowledge_logs.
+pub struct KnowledgeLog {
+    pub workstream: String,
+    pub path: PathBuf,
+    /// Where the log is inside its engagement folder, `/`-separated.
+    pub relative: String,
+}
+
+impl KnowledgeLog {
+    /// `engagement/relative`, how errors and `_meta` name the log.
+    pub fn file(&self, engagement: &str) -> String {
+        format!("{}/{}", engagement, self.relative)
+    }
+}
+
+/// The knowledge log's file name when a team hasn't configured others.
+pub const DEFAULT_LOG_NAME: &str = "KNOWLEDGE_LOG.md";
+
+/// Find the knowledge log in each workstream directory directly under
+/// `engagement_dir`, sorted by workstream. A log is a file called one of
+/// `names`, compared case-insensitively, or `KNOWLEDGE_LOG.md` if `names` is
+/// empty; a workstream with several takes the one listed first.
+///
+/// A workstream in the engagement_config.json that sets `knowledge_log` (a
+/// path inside the engagement, like `notes/ops.md`) has its log there
+/// instead, under the workstream's key, and its folder (`output_dir`, else
+/// the key) isn't searched.
+///
+/// Symlinked workstream folders and logs are skipped, unless `config` says
+/// to follow symlinks and they stay inside the engagement.
+pub fn scan_knowledge_logs(
+    engagement_dir: &Path,
+    names: &[String],
+    config: &config::ViewerConfig,
+) -> Vec<KnowledgeLog> {
+    scan_workstreams(engagement_dir, names, config).0
+}
+
+/// scan_knowledge_logs's logs, and the workstream folders it searched that
+/// had none, as (workstream, folder).
+fn scan_workstreams(
+    engagement_dir: &Path,
+    names: &[String],
+    config: &config::ViewerConfig,
+) -> (Vec<KnowledgeLog>, Vec<(String, PathBuf)>) {
+    let default_names = [DEFAULT_LOG_NAME.to_string()];
+    let names = if names.is_empty() { &default_names[..] } else { names };
+    let links = crate::paths::Symlinks::new(engagement_dir, config.follow_symlinks);
+    let (mut logs, declared_dirs) = declared_logs(engagement_dir, config.max_file_bytes);
+    let mut without_logs = Vec::new();
+    if let Ok(dir_entries) = std::fs::read_dir(engagement_dir) {
+        for entry in dir_entries.flatten() {
+            let path = entry.path();
+            let dir_name = entry.file_name().to_string_lossy().to_string();
+            if config::is_ignored_dir(&dir_name) || !links.is_dir(engagement_dir, &entry) {
+                continue;
+            }
+            if declared_dirs.contains(&dir_name) {
+                continue;
+            }
+            if let Some(log_path) = find_log(&path, names, &links) {
+                let file = log_path.file_name().unwrap_or_default().to_string_lossy();
+                let relative = format!("{}/{}", dir_name, file);
+                logs.push(KnowledgeLog { workstream: dir_name, path: log_path, relative });
+            } else {
+                without_logs.push((dir_name, path));
+            }
+        }
+    }
+    logs.sort_by(|a, b| a.workstream.cmp(&b.workstream));
+    (logs, without_logs)
+}
+
+/// A workstream of an engagement, as list_engagement_workstreams finds it.
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct WorkstreamInfo {
+    pub name: String,
+    pub has_knowledge_log: bool,
+    /// When the log, or the folder if there's no log, last changed, in
+    /// milliseconds since the Unix epoch.
+    pub last_modified_ms: Option<u64>,
+}
+
+/// The workstreams of `engagement`, sorted by name, for the sidebar: every
+/// folder scan_knowledge_logs would search and every log it finds where
+/// engagement_config.json places one, with whether it has a log. Only
+/// directories are listed; no log is read. `knowledge_files` names the logs
+/// as it does for read_company_data.
+#[tauri::command]
+pub fn list_engagement_workstreams(
+    app: tauri::AppHandle,
+    config: tauri::State<'_, config::ViewerConfig>,
+    repo_path: String,
+    engagement: String,
+    knowledge_files: Option<Vec<String>>,
+) -> Result<Vec<WorkstreamInfo>, CommandError> {
+    telemetry::timed(&app, "list_engagement_workstreams", || {
+        let engagement_dir = engagement_dir(&repo_path, &engagement)?;
+        Ok(list_workstreams(&engagement_dir, &knowledge_files.unwrap_or_default(), &config))
+    })
+}
+
+fn list_workstreams(
+    engagement_dir: &Path,
+    names: &[String],
+    config: &config::ViewerConfig,
+) -> Vec<WorkstreamInfo> {
+    let modified_ms = |path: &Path| {
+        let modified = std::fs::metadata(path).and_then(|meta| meta.modified()).ok()?;
+        let since = modified.duration_since(std::time::UNIX_EPOCH).ok()?;
+        Some(since.as_millis() as u64)
+    };
+    let (logs, without_logs) = scan_workstreams(engagement_dir, names, config);
+    let mut workstreams: Vec<WorkstreamInfo> = logs
+        .iter()
+        .map(|log| (log.workstream.clone(), &log.path, true))
+        .chain(without_logs.iter().map(|(name, dir)| (name.clone(), dir, false)))
+        .map(|(name, path, has_knowledge_log)| WorkstreamInfo {
+            name,
+            has_knowledge_log,
+            last_modified_ms: modified_ms(path),
+        })
+        .collect();
+    workstreams.sort_by(|a, b| a.name.cmp(&b.name));
+    workstreams
+}
+
+/// The logs engagement_config.json places with `knowledge_log`, and the
+/// folders of the workstreams that do so. A declared log that doesn't exist
+/// (yet), or would be outside the engagement, is skipped.
+fn declared_logs(engagement_dir: &Path, max_bytes: u64) -> (Vec<KnowledgeLog>, HashSet<String>) {
+    let mut logs = Vec::new();
+    let mut dirs = HashSet::new();
+    let Some(engagement_config) = read_engagement_config(engagement_dir, max_bytes) else {
+        return (logs, dirs);
+    };
+    for (key, ws) in engagement_config["workstreams"].as_object().into_iter().flatten() {
+        let Some(relative) = ws["knowledge_log"].as_str() else {
+            continue;
+        };
+        dirs.insert(ws["output_dir"].as_str().unwrap_or(key).to_string());
+        match declared_path(engagement_dir, relative) {
+            Ok(path) if path.is_file() => logs.push(KnowledgeLog {
+                workstream: key.clone(),
+                path,
+                relative: relative.replace('\\', "/"),
+            }),
+            Ok(_) => tracing::debug!(workstream = %key, "knowledge_log not found: {}", relative),
+            Err(e) => tracing::warn!(workstream = %key, "knowledge_log skipped: {}", e),
+        }
+    }
+    (logs, dirs)
+}
+
+/// The engagement's engagement_config.json, if it has a readable one no
+/// larger than `max_bytes`.
+fn read_engagement_config(engagement_dir: &Path, max_bytes: u64) -> Option<serde_json::Value> {
+    let path = engagement_dir.join("engagement_config.json");
+    let content = config::read_capped(&path, "engagement_config.json", max_bytes).ok()?;
+    serde_json::from_str(crate::company::strip_bom(&content)).ok()
+}
+
+/// Where a `knowledge_log` path points. It must be relative and made only of
+/// plain names, so it can't lead out of the engagement even if the log
+/// doesn't exist yet.
+fn declared_path(engagement_dir: &Path, relative: &str) -> Result<PathBuf, CommandError> {
+    check_log_path(relative)?;
+    Ok(engagement_dir.join(relative))
+}
+
+/// declared_path's check on a `knowledge_log` path, for callers that don't
+/// read the engagement from disk.
+pub fn check_log_path(relative: &str) -> Result<(), CommandError> {
+    let plain = !relative.is_empty()
+        && Path::new(relative)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+    if !plain {
+        let message = format!("knowledge_log must be a path inside the engagement: {:?}", relative);
+        return Err(CommandError::PathTraversal(message));
+    }
+    Ok(())
+}
+
+/// The file in `dir` matching the earliest of `names`, ignoring case.
+fn find_log(dir: &Path, names: &[String], links: &crate::paths::Symlinks) -> Option<PathBuf> {
+    let files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .filter(|entry| links.is_file(entry))
+        .map(|entry| entry.path())
+        .collect();
+    names.iter().find_map(|name| {
+        files
+            .iter()
+            .find(|path| {
+                path.file_name()
+                    .is_some_and(|file| file.to_string_lossy().eq_ignore_ascii_case(name))
+            })
+            .cloned()
+    })
+}
+
+/// One `### [TYPE] summary` entry from a knowledge log.
+#[derive(Clone, Debug, Default, serde::Serialize)]
+pub struct KnowledgeEntry {
+    pub engagement: String,
+    pub workstream: String,
+    pub date: String,
+    /// `date` as `YYYY-MM-DD`, or None if the heading isn't a recognized date.
+    pub date_iso: Option<String>,
+    #[serde(rename = "type")]
+    pub entry_type: String,
+    /// Whatever followed a colon in the type bracket, e.g. "high" in
+    /// `[RISK: high]`.
+    pub qualifier: Option<String>,
+    pub summary: String,
+    pub detail: String,
+    pub source: String,
+    /// Every `**Key**: value` field on the entry, keyed by lowercased name.
+    /// `detail` and `source` above are copies of those two, kept for
+    /// existing readers.
+    pub fields: BTreeMap<String, String>,
+    /// The text under each `#### Title` sub-heading, keyed by title.
+    pub sections: BTreeMap<String, String>,
+    /// `@handle` mentions and `#tag`s in the summary and detail, in order of
+    /// first appearance and without the sigil. Tags are lowercased; see
+    /// find_tokens for what doesn't count.
+    pub mentions: Vec<String>,
+    pub tags: Vec<String>,
+    /// The `### ` header as written, e.g. `[RISK: high] Flat network`.
+    pub heading: String,
+    /// The log the entry was read from, and the 1-based line of its `### `
+    /// header there, for opening the log at the entry.
+    pub source_file: PathBuf,
+    pub source_line: usize,
+    /// Which repo the entry came from, set only in read_company_data_multi's
+    /// merged timeline.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repo: Option<String>,
+    /// Every workstream the entry appears in, set only when dedupe merged
+    /// copies of it.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub workstreams: Vec<String>,
+}
+
+/// The entries of one knowledge log, plus anything odd noticed on the way.
+#[derive(Clone, Default)]
+pub struct ParsedLog {
+    pub entries: Vec<KnowledgeEntry>,
+    pub warnings: Vec<LogWarning>,
+}
+
+/// Something in a knowledge log its author will want to fix: a date that
+/// doesn't parse, a date heading with nothing under it, or an entry with no
+/// summary or no date.
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub struct LogWarning {
+    /// 1-based line of the heading concerned.
+    pub line: usize,
+    pub message: String,
+}
+
+/// Where the parser is sending continuation lines.
+enum Target {
+    None,
+    Field(String),
+    Section(String),
+}
+
+/// Read and parse one knowledge log into its entries.
+pub fn load_knowledge_log(
+    log: &KnowledgeLog,
+    engagement: &str,
+    max_bytes: u64,
+) -> Result<ParsedLog, CommandError> {
+    let content = config::read_capped(&log.path, &log.path.display().to_string(), max_bytes)?;
+    let mut parsed = ParsedLog::default();
+    parse_knowledge_log(&content, engagement, &log.workstream, &log.path, &mut parsed);
+    Ok(parsed)
+}
+
+/// Date heading formats, tried in order. Numeric dates are day-first. `%Y`
+/// happily reads "5" or "24" as a year, so the two-digit-year and day-first
+/// forms are tried before `%Y/%m/%d`.
+const DATE_FORMATS: [&str; 9] = [
+    "%Y-%m-%d",
+    "%d/%m/%y",
+    "%d/%m/%Y",
+    "%Y/%m/%d",
+    "%b %d, %Y",
+    "%B %d, %Y",
+    "%d %b %Y",
+    "%d %B %Y",
+    "%d.%m.%Y",
+];
+
+/// Interpret a `## ` heading as a date, as `YYYY-MM-DD`.
+pub fn normalize_date(heading: &str) -> Option<String> {
+    DATE_FORMATS.iter().find_map(|format| {
+        chrono::NaiveDate::parse_from_str(heading, format)
+            .ok()
+            .map(|date| date.format("%Y-%m-%d").to_string())
+    })
+}
+
+/// Parse a knowledge log: `## ` headings set the date for the `### [TYPE]
+/// summary` entries below them. Each `- **Key**: value` field runs from its
+/// marker line until the next field or heading, so wrapped paragraphs and
+/// sub-lists are kept. A `#### Title` sub-heading starts a section that takes
+/// every line, fields included, up to the next heading. Entries without a
+/// summary or a date, and date headings without entries, are kept as they are
+/// but noted in `parsed.warnings`. Each entry records `file` and its line.
+pub fn parse_knowledge_log(
+    content: &str,
+    engagement: &str,
+    workstream: &str,
+    file: &Path,
+    parsed: &mut ParsedLog,
+) {
+    let mut current_date = String::new();
+    let mut current_date_iso = None;
+    // The current date heading's line, while no entry has been seen under it.
+    let mut empty_date: Option<usize> = None;
+    let mut current: Option<KnowledgeEntry> = None;
+    let mut target = Target::None;
+    // How far the current field's line was indented.
+    let mut field_indent = 0;
+
+    let content = crate::company::strip_bom(content);
+    for (index, line) in content.lines().enumerate() {
+        // lines() only drops a \r that precedes \n; mixed endings leave others.
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        if line.starts_with("## ") && !line.starts_with("### ") {
+            finish_entry(parsed, current.take());
+            warn_if_empty(parsed, empty_date, &current_date);
+            current_date = line.trim_start_matches("## ").trim().to_string();
+            current_date_iso = normalize_date(&current_date);
+            empty_date = Some(index + 1);
+            if current_date_iso.is_none() {
+                parsed.warnings.push(LogWarning {
+                    line: index + 1,
+                    message: format!("heading \"{}\" is not a recognized date", current_date),
+                });
+            }
+            target = Target::None;
+        } else if line.starts_with("### ") {
+            finish_entry(parsed, current.take());
+            empty_date = None;
+            let header = line.trim_start_matches("### ").trim();
+            let (entry_type, qualifier, summary) = parse_header(header);
+            current = Some(KnowledgeEntry {
+                engagement: engagement.to_string(),
+                workstream: workstream.to_string(),
+                date: current_date.clone(),
+                date_iso: current_date_iso.clone(),
+                entry_type,
+                qualifier,
+                summary,
+                heading: header.to_string(),
+                source_file: file.to_path_buf(),
+                source_line: index + 1,
+                ..Default::default()
+            });
+            target = Target::None;
+        } else if let Some(entry) = current.as_mut() {
+            // A line indented deeper than the field above continues it, even
+            // if it looks like a field itself (see format_entry).
+            let indent = line.len() - line.trim_start().len();
+            let continues_field = matches!(target, Target::Field(_)) && indent > field_indent;
+            if let Some(title) = line.strip_prefix("#### ") {
+                let title = title.trim().to_string();
+                entry.sections.entry(title.clone()).or_default();
+                target = Target::Section(title);
+            } else if let Target::Section(title) = &target {
+                if let Some(text) = entry.sections.get_mut(title) {
+                    append_line(text, line.trim());
+                }
+            } else if let Some((key, value)) = parse_field(strip_bullet(line)).filter(|_| !continues_field) {
+                entry.fields.insert(key.clone(), value.to_string());
+                target = Target::Field(key);
+                field_indent = indent;
+            } else if let Target::Field(key) = &target {
+                if let Some(text) = entry.fields.get_mut(key) {
+                    append_line(text, line.trim());
+                }
+            }
+        }
+    }
+    finish_entry(parsed, current);
+    warn_if_empty(parsed, empty_date, &current_date);
+}
+
+/// Note a date heading (at `line`, if any) that no entry followed.
+fn warn_if_empty(parsed: &mut ParsedLog, line: Option<usize>, date: &str) {
+    if let Some(line) = line {
+        parsed.warnings.push(LogWarning {
+            line,
+            message: format!("date heading \"{}\" has no entries", date),
+        });
+    }
+}
+
+/// A new entry to add to a knowledge log from the UI.
+#[derive(serde::Deserialize)]
+pub struct NewEntry {
+    /// The date heading to file it under; today if omitted.
+    #[serde(default)]
+    pub date: Option<String>,
+    pub entry_type: String,
+    pub summary: String,
+    #[serde(default)]
+    pub detail: String,
+    #[serde(default)]
+    pub source: String,
+}
+
+/// Append `entry` to `<engagement>/<workstream>/KNOWLEDGE_LOG.md`, or the
+/// workstream's `knowledge_log` if its engagement_config.json sets one, at the
+/// end of its date's section, adding the heading if the date isn't there yet.
+/// The log and the folders above it are created if missing. The file is
+/// replaced atomically, so a concurrent read sees the old or new log, never
+/// half of one.
+#[tauri::command]
+pub fn append_knowledge_entry(
+    app: tauri::AppHandle,
+    launch: tauri::State<'_, LaunchConfig>,
+    config: tauri::State<'_, config::ViewerConfig>,
+    repo_path: String,
+    engagement: String,
+    workstream: String,
+    entry: NewEntry,
+) -> Result<(), CommandError> {
+    telemetry::timed(&app, "append_knowledge_entry", || {
+        append_entry(&launch, repo_path, engagement, workstream, entry, config.max_file_bytes)
+    })
+}
+
+fn append_entry(
+    launch: &LaunchConfig,
+    repo_path: String,
+    engagement: String,
+    workstream: String,
+    entry: NewEntry,
+    max_bytes: u64,
+) -> Result<(), CommandError> {
+    launch.ensure_writable("append_knowledge_entry")?;
+    let engagement_dir = engagement_dir(&repo_path, &engagement)?;
+    let log_path = log_path(&engagement_dir, &workstream, max_bytes)?;
+    if let Some(dir) = log_path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| CommandError::io(format_args!("Failed to create {}", dir.display()), &e))?;
+    }
+
+    let content = match std::fs::read_to_string(&log_path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(CommandError::io(format_args!("Failed to read {}", log_path.display()), &e)),
+    };
+    let date = entry
+        .date
+        .clone()
+        .unwrap_or_else(|| chrono::Local::now().format("%Y-%m-%d").to_string());
+    let updated = insert_entry(&content, date.trim(), &format_entry(&entry)?);
+    config::write_atomic(&log_path, &updated)
+}
+
+/// The folder of `engagement` in the repo at `repo_path`, which has to hold an
+/// engagement_config.json.
+fn engagement_dir(repo_path: &str, engagement: &str) -> Result<PathBuf, CommandError> {
+    let engagement_dir = Path::new(repo_path).join(crate::paths::plain_name(engagement)?);
+    if !engagement_dir.join("engagement_config.json").is_file() {
+        return Err(CommandError::NotFound(format!("Not an engagement: {}", engagement)));
+    }
+    Ok(engagement_dir)
+}
+
+/// Where `workstream` keeps its log: its `knowledge_log` in the
+/// engagement_config.json, else `<workstream>/KNOWLEDGE_LOG.md`.
+fn log_path(engagement_dir: &Path, workstream: &str, max_bytes: u64) -> Result<PathBuf, CommandError> {
+    let declared = read_engagement_config(engagement_dir, max_bytes).and_then(|engagement_config| {
+        engagement_config["workstreams"][workstream]["knowledge_log"]
+            .as_str()
+            .map(str::to_string)
+    });
+    match declared {
+        Some(relative) => declared_path(engagement_dir, &relative),
+        None => Ok(engagement_dir
+            .join(crate::paths::plain_name(workstream)?)
+            .join(DEFAULT_LOG_NAME)),
+    }
+}
+
+/// What merge_knowledge_logs did.
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct MergeReport {
+    /// Entries in the merged log.
+    pub entries: usize,
+    /// Entries dropped as copies of one already merged (see dedupe).
+    pub duplicates: usize,
+    /// Source logs removed, as `<file>.bak` in their place.
+    pub deleted: Vec<PathBuf>,
+}
+
+/// Combine the logs of `source_workstreams` into `target_workstream`'s log
+/// (which may already have entries), one `## YYYY-MM-DD` section per date,
+/// oldest first, with entries copied between the logs collapsed as dedupe
+/// does. Entries are rewritten as render_log writes them, under the target
+/// log's own title text if it had any. The old target log is kept as
+/// `<file>.bak`, and with `delete_sources` each source log is renamed to one
+/// rather than removed.
+// Each argument is a named parameter of the frontend's invoke call.
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+pub fn merge_knowledge_logs(
+    app: tauri::AppHandle,
+    launch: tauri::State<'_, LaunchConfig>,
+    config: tauri::State<'_, config::ViewerConfig>,
+    repo_path: String,
+    engagement: String,
+    source_workstreams: Vec<String>,
+    target_workstream: String,
+    delete_sources: Option<bool>,
+) -> Result<MergeReport, CommandError> {
+    telemetry::timed(&app, "merge_knowledge_logs", || {
+        let sources: Vec<&str> = source_workstreams.iter().map(String::as_str).collect();
+        let delete = delete_sources.unwrap_or(false);
+        let max_bytes = config.max_file_bytes;
+        merge_logs(&launch, &repo_path, &engagement, &sources, &target_workstream, delete, max_bytes)
+    })
+}
+
+fn merge_logs(
+    launch: &LaunchConfig,
+    repo_path: &str,
+    engagement: &str,
+    sources: &[&str],
+    target: &str,
+    delete_sources: bool,
+    max_bytes: u64,
+) -> Result<MergeReport, CommandError> {
+    launch.ensure_writable("merge_knowledge_logs")?;
+    let engagement_dir = engagement_dir(repo_path, engagement)?;
+    let sources: Vec<&str> = sources.iter().copied().filter(|source| *source != target).collect();
+    if sources.is_empty() {
+        return Err(CommandError::InvalidInput("No workstreams to merge into the target".to_string()));
+    }
+    let target_path = log_path(&engagement_dir, target, max_bytes)?;
+    let mut source_paths = Vec::new();
+    for source in &sources {
+        let path = log_path(&engagement_dir, source, max_bytes)?;
+        if !path.is_file() {
+            return Err(CommandError::NotFound(format!("No knowledge log for workstream {}", source)));
+        }
+        source_paths.push(path);
+    }
+
+    let mut parsed = ParsedLog::default();
+    let mut preamble = String::new();
+    if target_path.is_file() {
+        let content = config::read_capped(&target_path, &target_path.display().to_string(), max_bytes)?;
+        preamble = crate::company::strip_bom(&content)
+            .lines()
+            .take_while(|line| !line.starts_with("## ") && !line.starts_with("### "))
+            .collect::<Vec<_>>()
+            .join("\n")
+            .trim()
+            .to_string();
+        parse_knowledge_log(&content, engagement, target, &target_path, &mut parsed);
+    }
+    for (source, path) in sources.iter().zip(&source_paths) {
+        let content = config::read_capped(path, &path.display().to_string(), max_bytes)?;
+        parse_knowledge_log(&content, engagement, source, path, &mut parsed);
+    }
+    let read = parsed.entries.len();
+    let mut entries = dedupe(parsed.entries);
+    let duplicates = read - entries.len();
+    // Stable, so entries of one date keep their order within each log. A
+    // heading that isn't a date (`## Week 3`) sorts by its text, so its
+    // entries from every log still end up under one heading.
+    entries.sort_by_key(|entry| entry.date_iso.clone().unwrap_or_else(|| entry.date.clone()));
+
+    let merged = render_log(&preamble, &entries)?;
+
+    if let Some(dir) = target_path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| CommandError::io(format_args!("Failed to create {}", dir.display()), &e))?;
+    }
+    if target_path.is_file() {
+        std::fs::copy(&target_path, with_bak(&target_path))
+            .map_err(|e| CommandError::io(format_args!("Failed to back up {}", target_path.display()), &e))?;
+    }
+    config::write_atomic(&target_path, &merged)?;
+    tracing::info!(%engagement, %target, ?sources, entries = entries.len(), "merged knowledge logs");
+
+    let mut deleted = Vec::new();
+    if delete_sources {
+        for path in source_paths {
+            std::fs::rename(&path, with_bak(&path))
+                .map_err(|e| CommandError::io(format_args!("Failed to remove {}", path.display()), &e))?;
+            deleted.push(path);
+        }
+    }
+    Ok(MergeReport { entries: entries.len(), duplicates, deleted })
+}
+
+/// `entries` as one knowledge log: `preamble` (a title, say) if it isn't
+/// empty, then a `## ` heading (the ISO date where there is one) above each
+/// run of entries with the same date, each entry as format_entry_markdown
+/// writes it. Undated entries go first, ahead of any heading, so they parse
+/// back undated; the rest should already be sorted by date, or a date will
+/// get a heading for each run.
+pub fn render_log(preamble: &str, entries: &[KnowledgeEntry]) -> Result<String, CommandError> {
+    let mut lines: Vec<String> = Vec::new();
+    if !preamble.is_empty() {
+        lines.extend([preamble.to_string(), String::new()]);
+    }
+    let (undated, dated): (Vec<&KnowledgeEntry>, Vec<&KnowledgeEntry>) =
+        entries.iter().partition(|entry| entry.date.is_empty());
+    let mut heading: Option<String> = None;
+    for entry in undated.into_iter().chain(dated) {
+        let date = entry.date_iso.clone().unwrap_or_else(|| entry.date.clone());
+        if !entry.date.is_empty() && heading.as_ref() != Some(&date) {
+            lines.push(format!("## {}", date));
+            heading = Some(date);
+        }
+        let value = serde_json::to_value(entry)
+            .map_err(|e| CommandError::Internal(format!("Failed to serialize knowledge entry: {}", e)))?;
+        lines.push(format_entry_markdown(value));
+    }
+    Ok(lines.join("\n"))
+}
+
+fn with_bak(path: &Path) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{}.bak", name))
+}
+
+/// Render an entry as the markdown parse_knowledge_log reads. Continuation
+/// lines are indented so they can't be mistaken for headings or fields.
+fn format_entry(entry: &NewEntry) -> Result<Vec<String>, CommandError> {
+    let entry_type = entry.entry_type.trim().to_uppercase();
+    let summary = entry.summary.trim();
+    if summary.is_empty() {
+        return Err(CommandError::InvalidInput("Summary is required".to_string()));
+    }
+    if entry_type.contains(['[', ']', '\n']) || summary.contains('\n') {
+        return Err(CommandError::InvalidInput(
+            "Type and summary must be single lines, and the type can't contain brackets".to_string(),
+        ));
+    }
+
+    let mut lines = vec![format!("### [{}] {}", entry_type, summary)];
+    for (label, text) in [("Detail", &entry.detail), ("Source", &entry.source)] {
+        push_field(&mut lines, label, text);
+    }
+    Ok(lines)
+}
+
+/// Add `- **label**: text`, with any further lines of `text` indented under
+/// it. Nothing is added for empty text.
+fn push_field(lines: &mut Vec<String>, label: &str, text: &str) {
+    let mut text_lines = text.trim().lines();
+    if let Some(first) = text_lines.next() {
+        lines.push(format!("- **{}**: {}", label, first.trim_end()));
+        lines.extend(text_lines.map(|line| match line.trim_end() {
+            "" => String::new(),
+            line => format!("  {}", line),
+        }));
+    }
+}
+
+/// A parsed entry (as read_company_data returns it) back as the markdown it
+/// came from, for pasting elsewhere: the `### [TYPE] summary` header, Detail,
+/// Source and any other fields, then its `####` sections. Parsing the result
+/// gives the same entry, though field names come back capitalized rather
+/// than as first written.
+#[tauri::command]
+pub fn format_entry_markdown(entry: serde_json::Value) -> String {
+    let text = |value: &serde_json::Value| value.as_str().unwrap_or_default().to_string();
+    let summary = text(&entry["summary"]);
+    let entry_type = text(&entry["type"]);
+    let mut lines = vec![match (entry_type.as_str(), entry["qualifier"].as_str()) {
+        ("", _) => format!("### {}", summary),
+        (entry_type, Some(qualifier)) => format!("### [{}: {}] {}", entry_type, qualifier, summary),
+        (entry_type, None) => format!("### [{}] {}", entry_type, summary),
+    }];
+
+    let mut fields: Vec<(String, String)> = entry["fields"]
+        .as_object()
+        .into_iter()
+        .flatten()
+        .map(|(key, value)| (key.to_lowercase(), text(value)))
+        .collect();
+    for key in ["source", "detail"] {
+        if !fields.iter().any(|(name, _)| name == key) {
+            fields.push((key.to_string(), text(&entry[key])));
+        }
+    }
+    let rank = |key: &str| match key {
+        "detail" => 0,
+        "source" => 1,
+        _ => 2,
+    };
+    fields.sort_by(|(a, _), (b, _)| rank(a).cmp(&rank(b)).then(a.cmp(b)));
+    for (key, value) in &fields {
+        push_field(&mut lines, &capitalize(key), value);
+    }
+
+    for (title, body) in entry["sections"].as_object().into_iter().flatten() {
+        lines.push(format!("#### {}", title));
+        // Indented, a line that looks like a heading can't end the section;
+        // the parser trims it back.
+        lines.extend(text(body).lines().map(|line| match line {
+            line if line.starts_with('#') => format!("  {}", line),
+            line => line.to_string(),
+        }));
+    }
+    lines.join("\n") + "\n"
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// What an entry's `source` points at, for the UI to open.
+#[derive(Debug, PartialEq, serde::Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ResolvedSource {
+    /// A file in the repo, with the line from a `file:42` hint.
+    File { path: PathBuf, line: Option<u32> },
+    /// Something for the browser (or mail client) to open.
+    Url { url: String },
+    /// Anything else: prose like "site walk", or a path that isn't in the
+    /// repo. `reason` says which.
+    Unresolved { reference: String, reason: String },
+}
+
+/// Classify an entry's `source` as a file in the repo at `repo_path`, a URL
+/// or neither. A trailing `:<line>` on a file is taken as a line to open it
+/// at. Files are resolved as paths::resolve_within does, so a source can't
+/// point at anything outside the repo.
+#[tauri::command]
+pub fn resolve_source(app: tauri::AppHandle, repo_path: String, source: String) -> ResolvedSource {
+    telemetry::timed_value(&app, "resolve_source", || resolve(&repo_path, &source))
+}
+
+fn resolve(repo_path: &str, source: &str) -> ResolvedSource {
+    let reference = source.trim().trim_matches('`');
+    let reference = reference
+        .strip_prefix('<')
+        .and_then(|r| r.strip_suffix('>'))
+        .unwrap_or(reference);
+    let unresolved = |reason: String| ResolvedSource::Unresolved {
+        reference: reference.to_string(),
+        reason,
+    };
+    if reference.is_empty() {
+        return unresolved("Source is empty".to_string());
+    }
+    if is_url(reference) {
+        return ResolvedSource::Url { url: reference.to_string() };
+    }
+
+    let (file, line) = match reference.rsplit_once(':') {
+        Some((file, line)) if !file.is_empty() && line.bytes().all(|b| b.is_ascii_digit()) => {
+            match line.parse() {
+                Ok(line) => (file, Some(line)),
+                Err(_) => (reference, None),
+            }
+        }
+        _ => (reference, None),
+    };
+    match crate::paths::resolve_within(Path::new(&repo_path), file) {
+        Ok(path) if path.is_file() => ResolvedSource::File { path, line },
+        Ok(_) => unresolved(format!("Not a file: {}", file)),
+        Err(e) => unresolved(e.message().to_string()),
+    }
+}
+
+/// `scheme://...` or `mailto:...`. A one-letter scheme is a Windows drive.
+fn is_url(reference: &str) -> bool {
+    if reference.starts_with("mailto:") {
+        return true;
+    }
+    let Some((scheme, rest)) = reference.split_once("://") else {
+        return false;
+    };
+    scheme.len() > 1
+        && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme.chars().all(|c| c.is_ascii_alphanumeric() || "+.-".contains(c))
+        && !rest.is_empty()
+}
+
+/// Insert `block` at the end of the `## date` section of `content`, or in a
+/// new section at the end of the file. Headings are matched by normalized
+/// date when both parse, so `Jan 5, 2024` finds `## 2024-01-05`.
+fn insert_entry(content: &str, date: &str, block: &[String]) -> String {
+    let newline = if content.contains("\r\n") { "\r\n" } else { "\n" };
+    let mut lines: Vec<String> = crate::company::strip_bom(content)
+        .lines()
+        .map(|line| line.trim_end_matches('\r').to_string())
+        .collect();
+
+    let wanted = normalize_date(date);
+    let is_date_heading = |line: &str| line.starts_with("## ");
+    let matches_date = |line: &str| {
+        let heading = line.trim_start_matches("## ").trim();
+        match (&wanted, normalize_date(heading)) {
+            (Some(wanted), Some(heading)) => *wanted == heading,
+            _ => heading == date,
+        }
+    };
+
+    let section_end = lines
+        .iter()
+        .position(|line| is_date_heading(line) && matches_date(line))
+        .map(|start| {
+            lines[start + 1..]
+                .iter()
+                .position(|line| is_date_heading(line))
+                .map_or(lines.len(), |offset| start + 1 + offset)
+        });
+
+    let mut new_lines = Vec::new();
+    let mut insert_at = match section_end {
+        Some(end) => {
+            new_lines.push(String::new());
+            end
+        }
+        None => {
+            while lines.last().is_some_and(|line| line.trim().is_empty()) {
+                lines.pop();
+            }
+            if !lines.is_empty() {
+                lines.push(String::new());
+            }
+            lines.push(format!("## {}", date));
+            lines.len()
+        }
+    };
+    while insert_at > 0 && lines[insert_at - 1].trim().is_empty() {
+        insert_at -= 1;
+        lines.remove(insert_at);
+    }
+
+    new_lines.extend(block.iter().cloned());
+    if insert_at < lines.len() {
+        new_lines.push(String::new());
+    }
+    lines.splice(insert_at..insert_at, new_lines);
+
+    let mut updated = lines.join(newline);
+    updated.push_str(newline);
+    updated
+}
+
+/// Split an entry heading into (type, qualifier, summary).
+///
+/// The grammar is `[TYPE: qualifier] summary`. The type bracket is the first
+/// balanced `[...]` at the very start of the heading; later brackets belong to
+/// the summary. Inside it, the type is the text before the first `:`,
+/// uppercased, and anything after the colon is the qualifier. A heading that
+/// doesn't start with `[`, or whose bracket never closes, is all summary.
+fn parse_header(header: &str) -> (String, Option<String>, String) {
+    let untyped = || (String::new(), None, header.to_string());
+    let Some(rest) = header.strip_prefix('[') else {
+        return untyped();
+    };
+    let mut depth = 0;
+    let Some(close) = rest.char_indices().find_map(|(i, c)| match c {
+        '[' => {
+            depth += 1;
+            None
+        }
+        ']' if depth == 0 => Some(i),
+        ']' => {
+            depth -= 1;
+            None
+        }
+        _ => None,
+    }) else {
+        return untyped();
+    };
+
+    let summary = rest[close + 1..].trim().to_string();
+    let (entry_type, qualifier) = match rest[..close].split_once(':') {
+        Some((entry_type, qualifier)) => {
+            let qualifier = qualifier.trim();
+            (entry_type, (!qualifier.is_empty()).then(|| qualifier.to_string()))
+        }
+        None => (&rest[..close], None),
+    };
+    (entry_type.trim().to_uppercase(), qualifier, summary)
+}
+
+/// Sort entries newest first. Ties, and the undated entries that all go
+/// last, are ordered by engagement, then workstream, then position in the
+/// log (the sort is stable).
+pub fn sort_newest_first(entries: &mut [KnowledgeEntry]) {
+    sort_by_date(entries, SortOrder::NewestFirst);
+}
+
+/// Which way knowledge entries are ordered by date.
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    #[default]
+    NewestFirst,
+    OldestFirst,
+}
+
+/// Sort entries by date in `order`. Undated entries go last either way, and
+/// ties are broken as in sort_newest_first.
+pub fn sort_by_date(entries: &mut [KnowledgeEntry], order: SortOrder) {
+    entries.sort_by(|a, b| {
+        let by_date = match (&a.date_iso, &b.date_iso) {
+            (Some(a), Some(b)) if order == SortOrder::NewestFirst => b.cmp(a),
+            (Some(a), Some(b)) => a.cmp(b),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        };
+        by_date
+            .then_with(|| a.engagement.cmp(&b.engagement))
+            .then_with(|| a.workstream.cmp(&b.workstream))
+    });
+}
+
+/// Collapse entries of one engagement that have the same date, type, summary
+/// and detail, as when a finding is pasted into several workstream logs. The
+/// first copy is kept, in place, and lists every workstream it came from in
+/// `workstreams`; entries without copies are left as they are.
+pub fn dedupe(entries: Vec<KnowledgeEntry>) -> Vec<KnowledgeEntry> {
+    let mut kept: Vec<KnowledgeEntry> = Vec::with_capacity(entries.len());
+    let mut first: HashMap<(String, String, String, String, String), usize> = HashMap::new();
+    for entry in entries {
+        let key = (
+            entry.engagement.clone(),
+            entry.date.clone(),
+            entry.entry_type.clone(),
+            entry.summary.clone(),
+            entry.detail.clone(),
+        );
+        match first.get(&key) {
+            Some(&i) => {
+                let original = &mut kept[i];
+                if original.workstreams.is_empty() {
+                    original.workstreams.push(original.workstream.clone());
+                }
+                if !original.workstreams.contains(&entry.workstream) {
+                    original.workstreams.push(entry.workstream);
+                }
+            }
+            None => {
+                first.insert(key, kept.len());
+                kept.push(entry);
+            }
+        }
+    }
+    kept
+}
+
+/// Filters for search_knowledge. Every field that's set must match.
+#[derive(Default, serde::Deserialize)]
+#[serde(default)]
+pub struct Query {
+    /// Case-insensitive substring of the summary, detail or source.
+    pub text: Option<String>,
+    pub engagement: Option<String>,
+    pub workstream: Option<String>,
+    pub entry_type: Option<String>,
+    /// Inclusive date bounds, in any format a `## ` heading may use.
+    pub since: Option<String>,
+    pub until: Option<String>,
+}
+
+impl Query {
+    /// Keep the entries matching the query. Entries without a recognized
+    /// date never match a `since`/`until` bound.
+    pub fn filter(&self, entries: Vec<KnowledgeEntry>) -> Result<Vec<KnowledgeEntry>, CommandError> {
+        let bound = |date: &Option<String>| -> Result<Option<String>, CommandError> {
+            date.as_deref()
+                .map(|d| {
+                    normalize_date(d.trim())
+                        .ok_or_else(|| CommandError::InvalidInput(format!("Invalid date: {}", d)))
+                })
+                .transpose()
+        };
+        let since = bound(&self.since)?;
+        let until = bound(&self.until)?;
+        let text = self.text.as_ref().map(|t| t.to_lowercase());
+
+        Ok(entries
+            .into_iter()
+            .filter(|entry| {
+                let same = |want: &Option<String>, have: &str| {
+                    want.as_ref().is_none_or(|w| w.eq_ignore_ascii_case(have))
+                };
+                if !same(&self.engagement, &entry.engagement)
+                    || !same(&self.workstream, &entry.workstream)
+                    || !same(&self.entry_type, &entry.entry_type)
+                {
+                    return false;
+                }
+                if since.is_some() || until.is_some() {
+                    let Some(date) = &entry.date_iso else { return false };
+                    let too_early = since.as_ref().is_some_and(|since| date < since);
+                    let too_late = until.as_ref().is_some_and(|until| date > until);
+                    if too_early || too_late {
+                        return false;
+                    }
+                }
+                text.as_ref().is_none_or(|text| {
+                    [&entry.summary, &entry.detail, &entry.source]
+                        .iter()
+                        .any(|field| field.to_lowercase().contains(text))
+                })
+            })
+            .collect())
+    }
+}
+
+/// Entries from knowledge_in_range.
+#[derive(Debug, Default, serde::Serialize)]
+pub struct DateRange {
+    /// Entries dated within the range, in the order given.
+    pub entries: Vec<KnowledgeEntry>,
+    /// Entries without a recognized date, which can't be placed in any range.
+    pub undated: Vec<KnowledgeEntry>,
+}
+
+/// Split `entries` into those dated from `since` to `until` inclusive and
+/// the undated ones; those outside the range are dropped. The bounds may be
+/// in any format a `## ` heading may use, and `since` can't be after `until`.
+pub fn in_date_range(
+    entries: Vec<KnowledgeEntry>,
+    since: &str,
+    until: &str,
+) -> Result<DateRange, CommandError> {
+    let bound = |date: &str| {
+        normalize_date(date.trim())
+            .ok_or_else(|| CommandError::InvalidInput(format!("Invalid date: {}", date)))
+    };
+    let (from, to) = (bound(since)?, bound(until)?);
+    if from > to {
+        let message = format!("Start of range ({}) is after its end ({})", since, until);
+        return Err(CommandError::InvalidInput(message));
+    }
+    let mut range = DateRange::default();
+    for entry in entries {
+        match &entry.date_iso {
+            Some(date) if *date >= from && *date <= to => range.entries.push(entry),
+            Some(_) => {}
+            None => range.undated.push(entry),
+        }
+    }
+    Ok(range)
+}
+
+/// Append a continuation line, keeping blank lines as paragraph breaks but
+/// not letting them lead.
+fn append_line(text: &mut String, line: &str) {
+    if text.is_empty() && line.is_empty() {
+        return;
+    }
+    if !text.is_empty() {
+        text.push('\n');
+    }
+    text.push_str(line);
+}
+
+/// `line` without leading whitespace and its list marker, a `-`, `*` or `+`
+/// bullet with or without a space after it (`-**Detail**:`). A `*` needs the
+/// space, since `**Detail**` starts with one too.
+fn strip_bullet(line: &str) -> &str {
+    let line = line.trim_start();
+    let rest = if let Some(rest) = line.strip_prefix(['-', '+']) {
+        rest
+    } else if let Some(rest) = line.strip_prefix("* ").or_else(|| line.strip_prefix("*\t")) {
+        rest
+    } else {
+        return line;
+    };
+    rest.trim_start()
+}
+
+/// Split a `**Key**: value` line into its normalized key and value.
+fn parse_field(line: &str) -> Option<(String, &str)> {
+    let (key, value) = line.strip_prefix("**")?.split_once("**:")?;
+    let key = key.trim().to_lowercase();
+    if key.is_empty() {
+        return None;
+    }
+    Some((key, value.trim()))
+}
+
+fn finish_entry(parsed: &mut ParsedLog, entry: Option<KnowledgeEntry>) {
+    if let Some(mut entry) = entry {
+        for value in entry.fields.values_mut().chain(entry.sections.values_mut()) {
+            value.truncate(value.trim_end().len());
+        }
+        entry.detail = entry.fields.get("detail").cloned().unwrap_or_default();
+        entry.source = entry.fields.get("source").cloned().unwrap_or_default();
+        for text in [&entry.summary, &entry.detail] {
+            find_tokens(text, '@', &mut entry.mentions);
+            find_tokens(text, '#', &mut entry.tags);
+        }
+        for tag in &mut entry.tags {
+            *tag = tag.to_lowercase();
+        }
+        let mut warn = |message: &str| {
+            parsed.warnings.push(LogWarning { line: entry.source_line, message: message.to_string() });
+        };
+        if entry.summary.is_empty() {
+            warn("entry has no summary");
+        }
+        if entry.date.is_empty() {
+            warn("entry has no date heading above it");
+        }
+        parsed.entries.push(entry);
+    }
+}
+
+/// Add the names marked with `sigil` (`@alice`, `#pumps`) in `text` to
+/// `found`, skipping ones already there (ignoring case). A sigil only counts
+/// at the start of a word, so `ops@acme.com`, `page.md#setup` and URLs don't;
+/// nor do `](#anchor)` link targets, names inside `code spans`, or tags with
+/// no letter in them (`#12`). A trailing `.` or `-` is punctuation, not name.
+fn find_tokens(text: &str, sigil: char, found: &mut Vec<String>) {
+    let is_name = |c: char| c.is_alphanumeric() || matches!(c, '_' | '-' | '.');
+    let mut in_code = false;
+    let mut prev: Option<char> = None;
+    let mut before_prev: Option<char> = None;
+    for (i, c) in text.char_indices() {
+        let at_word_start = !prev.is_some_and(|p| is_name(p) || matches!(p, '/' | '&' | '@' | '#'));
+        let link_target = prev == Some('(') && before_prev == Some(']');
+        if c == '`' {
+            in_code = !in_code;
+        } else if c == sigil && !in_code && at_word_start && !link_target {
+            let rest = &text[i + c.len_utf8()..];
+            let end = rest.find(|c: char| !is_name(c)).unwrap_or(rest.len());
+            let name = rest[..end].trim_end_matches(['.', '-']);
+            let wanted = if sigil == '#' { name.chars().any(char::is_alphabetic) } else { !name.is_empty() };
+            if wanted && !found.iter().any(|f| f.eq_ignore_ascii_case(name)) {
+                found.push(name.to_string());
+            }
+        }
+        before_prev = prev;
+        prev = Some(c);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(content: &str) -> Vec<KnowledgeEntry> {
+        parse_log(content).entries
+    }
+
+    fn parse_log(content: &str) -> ParsedLog {
+        let mut parsed = ParsedLog::default();
+        parse_knowledge_log(content, "eng", "ws", Path::new("/repo/eng/ws/KNOWLEDGE_LOG.md"), &mut parsed);
+        parsed
+    }
+
+    #[test]
+    fn mentions_and_tags_skip_emails_anchors_and_code() {
+        let entries = parse(
+            "## 2024-01-05\n\
+             ### [ACTION] @Dana to confirm #Historian sizing with @vendor-ops.\n\
+             - **Detail**: Mail ops@acme.com or (@dana); see [notes](#sizing), \
+             page.md#setup and https://wiki.example/x#y. Ticket #42, `#not-a-tag`, #historian and #pumps.\n",
+        );
+        assert_eq!(entries[0].mentions, ["Dana", "vendor-ops"]);
+        assert_eq!(entries[0].tags, ["historian", "pumps"]);
+
+        let plain = parse("## 2024-01-05\n### [NOTE] Reach me at a@b.com\n");
+        assert!(plain[0].mentions.is_empty() && plain[0].tags.is_empty());
+    }
+
+    #[test]
+    fn multi_paragraph_detail_is_preserved() {
+        let entries = parse(
+            "## 2024-01-05\n\
+             ### [DECISION] Pick a historian\n\
+             - **Detail**: First paragraph\n  wraps here.\n\n\
+             Second paragraph:\n  - sub item one\n  - sub item two\n\n\
+             Third paragraph.\n\n\
+             - **Source**: Kickoff call\n  with ops\n\
+             - **Owner**: Dana\n\
+             ### [STATUS] Next\n",
+        );
+        assert_eq!(entries.len(), 2);
+        let json = serde_json::to_value(&entries[0]).unwrap();
+        assert_eq!(
+            json["detail"],
+            "First paragraph\nwraps here.\n\nSecond paragraph:\n- sub item one\n- sub item two\n\nThird paragraph."
+        );
+        assert_eq!(json["source"], "Kickoff call\nwith ops");
+        assert_eq!(json["type"], "DECISION");
+        assert_eq!(entries[1].detail, "");
+    }
+
+    #[test]
+    fn arbitrary_fields_are_collected() {
+        let entries = parse(
+            "## 2024-01-05\n\
+             ### [ACTION] Patch the HMI\n\
+             - **Detail**: Apply vendor patch\n\
+             - **Owner**: Dana\n\
+             - ** Priority **: High\n\
+             - **Not a field** just bold text\n",
+        );
+        let json = serde_json::to_value(&entries[0]).unwrap();
+        assert_eq!(json["detail"], "Apply vendor patch");
+        assert_eq!(json["fields"]["owner"], "Dana");
+        assert_eq!(json["fields"]["priority"], "High\n- **Not a field** just bold text");
+        assert_eq!(json["fields"]["detail"], "Apply vendor patch");
+    }
+
+    #[test]
+    fn fields_accept_every_bullet_style() {
+        for bullet in ["- ", "* ", "+ ", "-", "+", "  - ", "\t* ", ""] {
+            let log = format!(
+                "## 2024-01-05\n### [RISK] Old firmware\n{b}**Detail**: PLC runs v1\n{b}**Source**: Walkdown\n",
+                b = bullet
+            );
+            let entries = parse(&log);
+            assert_eq!(entries[0].detail, "PLC runs v1", "{:?}", bullet);
+            assert_eq!(entries[0].source, "Walkdown", "{:?}", bullet);
+        }
+    }
+
+    #[test]
+    fn date_headings_are_normalized() {
+        for heading in ["2024-01-05", "Jan 5, 2024", "January 5, 2024", "5/1/24", "05/01/2024", "5 Jan 2024"] {
+            assert_eq!(normalize_date(heading).as_deref(), Some("2024-01-05"), "{}", heading);
+        }
+        assert_eq!(normalize_date("Week 3"), None);
+    }
+
+    #[test]
+    fn unrecognized_dates_are_warned_about() {
+        let parsed = parse_log("## Week 3\n### [STATUS] Kept anyway\n");
+        assert_eq!(parsed.entries[0].date, "Week 3");
+        assert_eq!(parsed.entries[0].date_iso, None);
+        assert_eq!(
+            parsed.warnings,
+            [LogWarning { line: 1, message: "heading \"Week 3\" is not a recognized date".to_string() }]
+        );
+    }
+
+    #[test]
+    fn dangling_headers_are_warned_about() {
+        let parsed = parse_log(
+            "## 2024-01-05\n### [RISK] Flat network\n- **Detail**: one VLAN\n### [STATUS]\n\n## 2024-01-06\n",
+        );
+        assert_eq!(parsed.entries.len(), 2);
+        assert_eq!(parsed.entries[1].entry_type, "STATUS");
+        let warnings: Vec<(usize, &str)> =
+            parsed.warnings.iter().map(|w| (w.line, w.message.as_str())).collect();
+        assert_eq!(
+            warnings,
+            [(4, "entry has no summary"), (6, "date heading \"2024-01-06\" has no entries")]
+        );
+    }
+
+    #[test]
+    fn entries_before_any_date_are_warned_about() {
+        let parsed = parse_log("# Ops knowledge\n\n### [FINDING] Undated\n- **Detail**: x\n## 2024-01-05\n### Dated\n");
+        assert_eq!(parsed.entries[0].date, "");
+        assert_eq!(
+            parsed.warnings,
+            [LogWarning { line: 3, message: "entry has no date heading above it".to_string() }]
+        );
+    }
+
+    #[test]
+    fn query_filters_combine() {
+        let entries = parse(
+            "## 2024-01-05\n### [RISK] Old firmware\n- **Detail**: PLC runs v1\n\
+             ## 2024-02-10\n### [RISK] Flat network\n- **Source**: Walkdown\n\
+             ### [DECISION] Segment the network\n\
+             ## Later\n### [RISK] Undated network risk\n",
+        );
+        let search = |query: Query| -> Vec<String> {
+            query.filter(entries.clone()).unwrap().into_iter().map(|e| e.summary).collect()
+        };
+
+        let text = |t: &str| Some(t.to_string());
+        assert_eq!(
+            search(Query { text: text("NETWORK"), entry_type: text("risk"), ..Default::default() }),
+            ["Flat network", "Undated network risk"]
+        );
+        assert_eq!(search(Query { text: text("walkdown"), ..Default::default() }), ["Flat network"]);
+        assert_eq!(
+            search(Query { since: text("Feb 1, 2024"), ..Default::default() }),
+            ["Flat network", "Segment the network"]
+        );
+        assert_eq!(search(Query { until: text("2024-01-05"), ..Default::default() }), ["Old firmware"]);
+        assert!(Query { since: text("soon"), ..Default::default() }.filter(entries).is_err());
+    }
+
+    #[test]
+    fn date_ranges_are_inclusive_and_ordered() {
+        let entries = parse(
+            "## 2024-01-05\n### [RISK] Old firmware\n\
+             ## 2024-02-10\n### [RISK] Flat network\n\
+             ## 2024-03-01\n### [STATUS] Kickoff\n\
+             ## Later\n### [RISK] Undated\n",
+        );
+        let summaries = |entries: &[KnowledgeEntry]| -> Vec<String> {
+            entries.iter().map(|e| e.summary.clone()).collect()
+        };
+
+        let range = in_date_range(entries.clone(), "Jan 5, 2024", "2024-02-10").unwrap();
+        assert_eq!(summaries(&range.entries), ["Old firmware", "Flat network"]);
+        assert_eq!(summaries(&range.undated), ["Undated"]);
+        let err = in_date_range(entries.clone(), "2024-03-01", "2024-02-01").unwrap_err();
+        assert_eq!(err.message(), "Start of range (2024-03-01) is after its end (2024-02-01)");
+        assert!(in_date_range(entries, "last sprint", "2024-02-01").is_err());
+    }
+
+    #[test]
+    fn subsections_are_collected_separately() {
+        let entries = parse(
+            "## 2024-03-01\n\
+             ### [ASSESSMENT] Remote access review\n\
+             - **Detail**: Reviewed the vendor VPN\n\
+             #### Findings\n\
+             Shared accounts.\n\
+             - **No MFA**: on the jump host\n\n\
+             #### Recommendation\n\
+             Per-user accounts with MFA.\n\
+             ### [STATUS] Plain entry\n\
+             - **Detail**: No sections here\n",
+        );
+        let json = serde_json::to_value(&entries[0]).unwrap();
+        assert_eq!(json["detail"], "Reviewed the vendor VPN");
+        assert_eq!(json["sections"]["Findings"], "Shared accounts.\n- **No MFA**: on the jump host");
+        assert_eq!(json["sections"]["Recommendation"], "Per-user accounts with MFA.");
+        assert!(!entries[0].fields.contains_key("no mfa"));
+        assert!(entries[1].sections.is_empty());
+        assert_eq!(entries[1].detail, "No sections here");
+    }
+
+    #[test]
+    fn headers_follow_the_type_grammar() {
+        let header = |h: &str| {
+            let (entry_type, qualifier, summary) = parse_header(h);
+            (entry_type, qualifier.unwrap_or_default(), summary)
+        };
+        let expect = |t: &str, q: &str, s: &str| (t.to_string(), q.to_string(), s.to_string());
+
+        assert_eq!(header("No bracket at all"), expect("", "", "No bracket at all"));
+        assert_eq!(header("[] Empty type"), expect("", "", "Empty type"));
+        assert_eq!(header("[risk: high] Flat network"), expect("RISK", "high", "Flat network"));
+        assert_eq!(header("[RISK:] Bare colon"), expect("RISK", "", "Bare colon"));
+        assert_eq!(header("[NOTE: see 10:30 call] Timing"), expect("NOTE", "see 10:30 call", "Timing"));
+        assert_eq!(header("[DECISION] Use [new] vendor"), expect("DECISION", "", "Use [new] vendor"));
+        assert_eq!(header("[RISK [draft]] Nested"), expect("RISK [DRAFT]", "", "Nested"));
+        assert_eq!(header("[UNCLOSED summary"), expect("", "", "[UNCLOSED summary"));
+    }
+
+    #[test]
+    fn entries_know_where_they_were_read() {
+        let entries = parse(
+            "# Ops\n\n## 2024-01-05\n### [RISK: high]  Flat network \n- **Detail**: one VLAN\n\n### Kickoff\n",
+        );
+        let found: Vec<(&str, usize)> =
+            entries.iter().map(|e| (e.heading.as_str(), e.source_line)).collect();
+        assert_eq!(found, [("[RISK: high]  Flat network", 4), ("Kickoff", 7)]);
+        assert_eq!(entries[0].source_file, Path::new("/repo/eng/ws/KNOWLEDGE_LOG.md"));
+    }
+
+    #[test]
+    fn crlf_and_bom_are_tolerated() {
+        let entries = parse(
+            "\u{feff}## 2024-01-05\r\n### [STATUS] Windows log\r\n- **Detail**: line one\r\r\n  line two\r\n- **Source**: Email\r",
+        );
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].date_iso.as_deref(), Some("2024-01-05"));
+        assert_eq!(entries[0].summary, "Windows log");
+        assert_eq!(entries[0].detail, "line one\nline two");
+        assert_eq!(entries[0].source, "Email");
+    }
+
+    #[test]
+    fn appended_entries_round_trip() {
+        let dir = crate::test_support::TempDir::new("knowledge-append");
+        dir.write("alpha/engagement_config.json", "{}");
+        let existing = dir.write(
+            "alpha/ops/KNOWLEDGE_LOG.md",
+            "# Ops knowledge\n\n## 2024-01-05\n### [STATUS] Kickoff\n- **Detail**: Met the team\n\n## 2024-02-01\n### [RISK] Old\n",
+        );
+        let (repo, launch) = (dir.path().to_string_lossy().to_string(), LaunchConfig::default());
+        let append = |workstream: &str, date: &str, summary: &str| {
+            let entry = NewEntry {
+                date: Some(date.to_string()),
+                entry_type: "finding".to_string(),
+                summary: summary.to_string(),
+                detail: "Line one, with a comma\n\n### not a heading\n**Not**: a field\n- **Nor**: this".to_string(),
+                source: "Walkdown".to_string(),
+            };
+            append_entry(&launch, repo.clone(), "alpha".into(), workstream.into(), entry, u64::MAX)
+        };
+
+        append("ops", "Jan 5, 2024", "Into the existing section").unwrap();
+        append("ops", "2024-03-01", "New section").unwrap();
+        append("historian", "2024-03-01", "New workstream").unwrap();
+        assert!(append("../escape", "2024-03-01", "Nope").is_err());
+
+        let logs = scan_knowledge_logs(&dir.path().join("alpha"), &[], &config::ViewerConfig::default());
+        let mut entries: Vec<(String, String, String)> = Vec::new();
+        for log in &logs {
+            for entry in load_knowledge_log(log, "alpha", u64::MAX).unwrap().entries {
+                entries.push((entry.workstream, entry.date, entry.summary));
+            }
+        }
+        entries.sort();
+        let expect = |ws: &str, date: &str, summary: &str| {
+            (ws.to_string(), date.to_string(), summary.to_string())
+        };
+        assert_eq!(
+            entries,
+            [
+                expect("historian", "2024-03-01", "New workstream"),
+                expect("ops", "2024-01-05", "Into the existing section"),
+                expect("ops", "2024-01-05", "Kickoff"),
+                expect("ops", "2024-02-01", "Old"),
+                expect("ops", "2024-03-01", "New section"),
+            ]
+        );
+
+        let content = std::fs::read_to_string(&existing).unwrap();
+        let parsed = parse(&content);
+        assert_eq!(parsed[0].detail, "Met the team");
+        assert_eq!(parsed[1].entry_type, "FINDING");
+        assert_eq!(parsed[1].detail, "Line one, with a comma\n\n### not a heading\n**Not**: a field\n- **Nor**: this");
+        assert_eq!(parsed[1].source, "Walkdown");
+        assert!(content.starts_with("# Ops knowledge\n\n## 2024-01-05\n"), "{}", content);
+        assert!(content.contains("\n\n## 2024-03-01\n### [FINDING] New section\n"), "{}", content);
+    }
+
+    #[test]
+    fn merged_logs_parse_back_by_date() {
+        let dir = crate::test_support::TempDir::new("knowledge-merge");
+        dir.write("alpha/engagement_config.json", "{}");
+        let ops = dir.write(
+            "alpha/ops/KNOWLEDGE_LOG.md",
+            "# Ops knowledge\n\n## 2024-01-05\n### [STATUS] Kickoff\n- **Detail**: Met the team\n\n\
+             ## 2024-03-01\n### [RISK: high] Flat network\n- **Detail**: Same as IT's\n",
+        );
+        let it = dir.write(
+            "alpha/it/KNOWLEDGE_LOG.md",
+            "## Feb 2, 2024\n### [FINDING] Shared admin account\n- **Source**: Interview\n\
+             - **Owner**: @dana\n#### Notes\nAsked twice\n\n\
+             ## 2024-03-01\n### [RISK: high] Flat network\n- **Detail**: Same as IT's\n\
+             ### [DECISION] Segment the OT VLAN\n",
+        );
+        let (repo, launch) = (dir.path().to_string_lossy().to_string(), LaunchConfig::default());
+
+        let report = merge_logs(&launch, &repo, "alpha", &["it"], "ops", true, u64::MAX).unwrap();
+        assert_eq!(report, MergeReport { entries: 4, duplicates: 1, deleted: vec![it.clone()] });
+        assert!(dir.path().join("alpha/it/KNOWLEDGE_LOG.md.bak").is_file());
+        assert!(!it.exists());
+        assert!(std::fs::read_to_string(dir.path().join("alpha/ops/KNOWLEDGE_LOG.md.bak"))
+            .unwrap()
+            .contains("Kickoff"));
+
+        let content = std::fs::read_to_string(&ops).unwrap();
+        assert!(content.starts_with("# Ops knowledge\n\n## 2024-01-05\n### [STATUS] Kickoff\n"), "{}", content);
+        assert_eq!(content.matches("## 2024-03-01\n").count(), 1, "{}", content);
+        let parsed = parse_log(&content);
+        assert!(parsed.warnings.is_empty(), "{:?}", parsed.warnings);
+        let summary: Vec<(&str, &str, &str)> = parsed
+            .entries
+            .iter()
+            .map(|entry| (entry.date.as_str(), entry.entry_type.as_str(), entry.summary.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("2024-01-05", "STATUS", "Kickoff"),
+                ("2024-02-02", "FINDING", "Shared admin account"),
+                ("2024-03-01", "RISK", "Flat network"),
+                ("2024-03-01", "DECISION", "Segment the OT VLAN"),
+            ]
+        );
+        let finding = &parsed.entries[1];
+        assert_eq!(finding.source, "Interview");
+        assert_eq!(finding.fields["owner"], "@dana");
+        assert_eq!(finding.sections["Notes"], "Asked twice");
+        assert_eq!(parsed.entries[2].qualifier.as_deref(), Some("high"));
+
+        let err = merge_logs(&launch, &repo, "alpha", &["it"], "ops", false, u64::MAX).unwrap_err();
+        assert!(matches!(err, CommandError::NotFound(_)), "{:?}", err);
+    }
+
+    #[test]
+    fn merged_undated_headings_are_not_repeated() {
+        let dir = crate::test_support::TempDir::new("knowledge-merge-weeks");
+        dir.write("alpha/engagement_config.json", "{}");
+        dir.write(
+            "alpha/ops/KNOWLEDGE_LOG.md",
+            "## Week 3\n### [STATUS] Kickoff\n\n## Week 5\n### [STATUS] Walkdown\n",
+        );
+        dir.write("alpha/it/KNOWLEDGE_LOG.md", "## Week 3\n### [FINDING] Shared admin account\n");
+        dir.write("alpha/plant/KNOWLEDGE_LOG.md", "## 2024-01-05\n### [RISK] Flat network\n");
+        let (repo, launch) = (dir.path().to_string_lossy().to_string(), LaunchConfig::default());
+
+        let sources = ["ops", "it", "plant"];
+        let report = merge_logs(&launch, &repo, "alpha", &sources, "merged", false, u64::MAX).unwrap();
+        assert_eq!(report.entries, 4);
+
+        let content = std::fs::read_to_string(dir.path().join("alpha/merged/KNOWLEDGE_LOG.md")).unwrap();
+        let headings: Vec<&str> = content.lines().filter(|line| line.starts_with("## ")).collect();
+        assert_eq!(headings, ["## 2024-01-05", "## Week 3", "## Week 5"], "{}", content);
+        let summaries: Vec<String> = parse_log(&content).entries.into_iter().map(|entry| entry.summary).collect();
+        assert_eq!(summaries, ["Flat network", "Kickoff", "Shared admin account", "Walkdown"]);
+    }
+
+    #[test]
+    fn read_only_refuses_log_writes() {
+        let dir = crate::test_support::TempDir::new("knowledge-read-only");
+        dir.write("alpha/engagement_config.json", "{}");
+        let ops_text = "## 2024-01-05\n### [STATUS] Kickoff\n";
+        let ops = dir.write("alpha/ops/KNOWLEDGE_LOG.md", ops_text);
+        dir.write("alpha/it/KNOWLEDGE_LOG.md", "## 2024-01-05\n### [RISK] Flat network\n");
+        let repo = dir.path().to_string_lossy().to_string();
+        let launch = LaunchConfig { read_only: true, ..LaunchConfig::default() };
+
+        for workstream in ["ops", "historian"] {
+            let entry = NewEntry {
+                date: Some("2024-02-01".to_string()),
+                entry_type: "finding".to_string(),
+                summary: "Not written".to_string(),
+                detail: String::new(),
+                source: String::new(),
+            };
+            let err = append_entry(&launch, repo.clone(), "alpha".into(), workstream.into(), entry, u64::MAX)
+                .unwrap_err();
+            assert!(matches!(err, CommandError::ReadOnly(_)), "{:?}", err);
+        }
+        let err = merge_logs(&launch, &repo, "alpha", &["it"], "ops", true, u64::MAX).unwrap_err();
+        assert!(matches!(err, CommandError::ReadOnly(_)), "{:?}", err);
+
+        assert_eq!(std::fs::read_to_string(&ops).unwrap(), ops_text);
+        assert!(dir.path().join("alpha/it/KNOWLEDGE_LOG.md").is_file());
+        assert!(!dir.path().join("alpha/historian").exists());
+        assert!(!dir.path().join("alpha/ops/KNOWLEDGE_LOG.md.bak").exists());
+        assert!(!dir.path().join("alpha/it/KNOWLEDGE_LOG.md.bak").exists());
+    }
+
+    #[test]
+    fn workstreams_are_listed_with_or_without_logs() {
+        let dir = crate::test_support::TempDir::new("list-workstreams");
+        dir.write("alpha/engagement_config.json", "{}");
+        dir.write("alpha/ops/KNOWLEDGE_LOG.md", "## 2024-01-05\n### [STATUS] Kickoff\n");
+        dir.write("alpha/historian/notes.txt", "no log here");
+        std::fs::create_dir_all(dir.path().join("alpha/.git")).unwrap();
+        let repo = dir.path().to_string_lossy().to_string();
+
+        let workstreams = list_workstreams(&dir.path().join("alpha"), &[], &config::ViewerConfig::default());
+        let found: Vec<(&str, bool)> =
+            workstreams.iter().map(|ws| (ws.name.as_str(), ws.has_knowledge_log)).collect();
+        assert_eq!(found, [("historian", false), ("ops", true)]);
+        assert!(workstreams.iter().all(|ws| ws.last_modified_ms.is_some_and(|ms| ms > 0)));
+
+        assert!(engagement_dir(&repo, "beta").is_err());
+        assert!(engagement_dir(&repo, "../alpha").is_err());
+    }
+
+    #[test]
+    fn engagement_config_places_logs() {
+        let dir = crate::test_support::TempDir::new("declared-logs");
+        dir.write(
+            "alpha/engagement_config.json",
+            r#"{"workstreams": {
+                "ops": {"output_dir": "01-Ops", "knowledge_log": "notes/ops.md"},
+                "it": {"knowledge_log": "notes/it/log.md"},
+                "escape": {"knowledge_log": "../outside.md"}
+            }}"#,
+        );
+        dir.write("alpha/notes/ops.md", "## 2024-01-05\n### [FINDING] Declared\n");
+        dir.write("alpha/01-Ops/KNOWLEDGE_LOG.md", "## 2024-01-05\n### [FINDING] Shadowed\n");
+        dir.write("alpha/historian/KNOWLEDGE_LOG.md", "");
+        dir.write("outside.md", "");
+
+        let engagement = dir.path().join("alpha");
+        let logs = scan_knowledge_logs(&engagement, &[], &config::ViewerConfig::default());
+        let found: Vec<(&str, String)> =
+            logs.iter().map(|log| (log.workstream.as_str(), log.file("alpha"))).collect();
+        assert_eq!(
+            found,
+            [
+                ("historian", "alpha/historian/KNOWLEDGE_LOG.md".to_string()),
+                ("ops", "alpha/notes/ops.md".to_string()),
+            ]
+        );
+        let entries = load_knowledge_log(&logs[1], "alpha", u64::MAX).unwrap().entries;
+        assert_eq!(entries[0].summary, "Declared");
+
+        let append = |workstream: &str| {
+            let entry = NewEntry {
+                date: Some("2024-02-01".to_string()),
+                entry_type: "decision".to_string(),
+                summary: "Logged where declared".to_string(),
+                detail: String::new(),
+                source: String::new(),
+            };
+            let repo = dir.path().to_string_lossy().to_string();
+            append_entry(&LaunchConfig::default(), repo, "alpha".into(), workstream.into(), entry, u64::MAX)
+        };
+        append("it").unwrap();
+        assert!(append("escape").is_err());
+        let logs = scan_knowledge_logs(&engagement, &[], &config::ViewerConfig::default());
+        assert_eq!(logs[1].file("alpha"), "alpha/notes/it/log.md");
+        assert!(!dir.path().join("alpha/it").exists());
+    }
+
+    #[test]
+    fn sources_resolve_to_files_urls_or_neither() {
+        let dir = crate::test_support::TempDir::new("resolve-source");
+        let report = dir.write("repo/docs/report.pdf", "%PDF");
+        let notes = dir.write("repo/docs/notes.md", "# Notes\n");
+        dir.write("outside.md", "not in the repo");
+        std::fs::create_dir_all(dir.path().join("repo/empty")).unwrap();
+        let repo = dir.path().join("repo").to_string_lossy().into_owned();
+        let resolve = |source: &str| resolve(&repo, source);
+
+        let file = |path: &Path, line| ResolvedSource::File { path: path.canonicalize().unwrap(), line };
+        assert_eq!(resolve(" `docs/report.pdf` "), file(&report, None));
+        assert_eq!(resolve("docs/notes.md:42"), file(&notes, Some(42)));
+
+        let url = |url: &str| ResolvedSource::Url { url: url.to_string() };
+        assert_eq!(resolve("https://example.com/a?b=1"), url("https://example.com/a?b=1"));
+        assert_eq!(resolve("<ftp://files.example.com/x>"), url("ftp://files.example.com/x"));
+        assert_eq!(resolve("mailto:ops@example.com"), url("mailto:ops@example.com"));
+
+        for source in ["Site walk with J. Smith", "docs/missing.pdf:3", "empty", "../outside.md", ""] {
+            assert!(
+                matches!(resolve(source), ResolvedSource::Unresolved { .. }),
+                "{:?}",
+                resolve(source)
+            );
+        }
+        let ResolvedSource::Unresolved { reason, .. } = resolve("../outside.md") else {
+            unreachable!()
+        };
+        assert!(reason.contains("escapes"), "{}", reason);
+    }
+
+    #[test]
+    fn entries_format_back_to_markdown() {
+        let content = "## 2024-01-05\n\
+            ### [RISK: high] Pump 3 seal wear\n\
+            - **Detail**: Seen on the walkdown.\n\n  ## not a heading\n  - **Not**: a field\n\
+            * **Owner**: Maintenance\n\
+            - **Source**: Site visit\n\
+            #### Follow-up\n\
+            Order seals\n\n  ### still the section\n\
+            ### Untyped note\n";
+        for original in parse(content) {
+            let json = serde_json::to_value(&original).unwrap();
+            let markdown = format_entry_markdown(json);
+            let reparsed = parse(&format!("## {}\n{}", original.date, markdown));
+            assert_eq!(reparsed.len(), 1, "{}", markdown);
+            let reparsed = &reparsed[0];
+            assert_eq!(
+                (&reparsed.entry_type, &reparsed.qualifier, &reparsed.summary),
+                (&original.entry_type, &original.qualifier, &original.summary),
+                "{}",
+                markdown
+            );
+            assert_eq!(reparsed.fields, original.fields, "{}", markdown);
+            assert_eq!(reparsed.sections, original.sections, "{}", markdown);
+        }
+        let first = format_entry_markdown(serde_json::to_value(&parse(content)[0]).unwrap());
+        assert!(first.starts_with("### [RISK: high] Pump 3 seal wear\n- **Detail**: Seen"), "{}", first);
+    }
+}
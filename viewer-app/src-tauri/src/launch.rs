@@ -0,0 +1,130 @@
+// ── Launch flags (`sl-ot-viewer [--repo <path>] [--read-only] ...`) ────────
+
+use crate::error::CommandError;
+use clap::Parser;
+
+/// The commands that write to disk or start a process, which `--read-only`
+/// turns away.
+pub const MUTATING_COMMANDS: [&str; 14] = [
+    "append_knowledge_entry",
+    "create_engagement",
+    "export_company_bundle",
+    "export_knowledge_csv",
+    "export_knowledge_markdown",
+    "merge_knowledge_logs",
+    "open_in_editor",
+    "rename_engagement",
+    "run_command",
+    "spawn_terminal",
+    "write_company_file",
+    "write_settings",
+    "write_terminal",
+    "write_terminal_file",
+];
+
+/// How the viewer was launched, parsed once at startup.
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize)]
+pub struct LaunchConfig {
+    pub repo: Option<String>,
+    pub read_only: bool,
+    pub theme: Option<String>,
+}
+
+#[derive(Parser)]
+#[command(name = "sl-ot-viewer", about = "SL OT Tools viewer")]
+struct Args {
+    /// Repo to open. A bare path as the first argument also works.
+    #[arg(long)]
+    repo: Option<String>,
+    /// Refuse every command that writes files or starts a process.
+    #[arg(long)]
+    read_only: bool,
+    /// UI theme, e.g. `dark`.
+    #[arg(long)]
+    theme: Option<String>,
+    #[arg(hide = true)]
+    path: Option<String>,
+}
+
+impl LaunchConfig {
+    /// Parse `args` (including the program name). Arguments clap can't make
+    /// sense of are logged and the defaults used, since the OS or a dev
+    /// server may pass flags of its own; `--help` is reported the same way.
+    pub fn parse_from<I, T>(args: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<std::ffi::OsString> + Clone,
+    {
+        match Args::try_parse_from(args) {
+            Ok(args) => LaunchConfig {
+                repo: args.repo.or(args.path),
+                read_only: args.read_only,
+                theme: args.theme,
+            },
+            Err(e) => {
+                tracing::warn!("Ignoring launch arguments: {}", e);
+                LaunchConfig::default()
+            }
+        }
+    }
+
+    /// Called first thing by each of MUTATING_COMMANDS: a ReadOnly error
+    /// naming `command` if the viewer was launched with `--read-only`.
+    pub fn ensure_writable(&self, command: &str) -> Result<(), CommandError> {
+        debug_assert!(MUTATING_COMMANDS.contains(&command), "{} isn't listed", command);
+        if self.read_only {
+            tracing::info!(command, "refused in read-only mode");
+            return Err(CommandError::ReadOnly(format!(
+                "{} is disabled: the viewer was started with --read-only",
+                command
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// The flags the viewer was started with.
+#[tauri::command]
+pub fn get_launch_config(config: tauri::State<'_, LaunchConfig>) -> LaunchConfig {
+    config.inner().clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_and_bare_path_are_parsed() {
+        let parsed =
+            LaunchConfig::parse_from(["viewer", "--repo", "/r", "--read-only", "--theme", "dark"]);
+        assert_eq!(
+            parsed,
+            LaunchConfig {
+                repo: Some("/r".into()),
+                read_only: true,
+                theme: Some("dark".into())
+            }
+        );
+        assert_eq!(
+            LaunchConfig::parse_from(["viewer", "/bare"])
+                .repo
+                .as_deref(),
+            Some("/bare")
+        );
+        assert_eq!(
+            LaunchConfig::parse_from(["viewer", "--bogus"]),
+            LaunchConfig::default()
+        );
+    }
+
+    #[test]
+    fn read_only_refuses_every_mutating_command() {
+        let read_only = LaunchConfig { read_only: true, ..LaunchConfig::default() };
+        for command in MUTATING_COMMANDS {
+            assert_eq!(LaunchConfig::default().ensure_writable(command), Ok(()));
+            let err = read_only.ensure_writable(command).unwrap_err();
+            assert!(matches!(err, CommandError::ReadOnly(_)), "{:?}", err);
+            assert!(err.message().starts_with(command), "{}", err);
+        }
+    }
+}
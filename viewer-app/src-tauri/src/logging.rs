@@ -0,0 +1,166 @@
+// ── Logging (tracing to stderr and a size-capped log file) ─────────────────
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, EnvFilter};
+
+/// The log file is rolled over when it would pass this size...
+const MAX_LOG_BYTES: u64 = 5 * 1024 * 1024;
+/// ...keeping this many old ones beside it.
+const KEPT_LOGS: usize = 3;
+
+/// Where log files go: `SL_OT_LOG_DIR` if set, else the OS data dir (e.g.
+/// `%APPDATA%\sl-ot-viewer\logs`), else next to the exe.
+pub fn log_dir() -> PathBuf {
+    if let Some(dir) = std::env::var_os("SL_OT_LOG_DIR") {
+        return PathBuf::from(dir);
+    }
+    if let Some(dir) = dirs::data_dir() {
+        return dir.join("sl-ot-viewer").join("logs");
+    }
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(PathBuf::from))
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// Send `tracing` output to stderr and to `sl-ot-viewer.log`, which is
+/// rotated at 5 MB keeping the last three (`.log.1` newest). The level
+/// comes from `SL_OT_LOG` in env-filter syntax (`debug`,
+/// `info,sl_ot_viewer::terminal=trace`, ...) and defaults to `info`. Keep the
+/// returned guard alive until exit so buffered lines reach the file.
+pub fn init() -> Option<WorkerGuard> {
+    let filter = EnvFilter::try_from_env("SL_OT_LOG").unwrap_or_else(|_| EnvFilter::new("info"));
+    let stderr = fmt::layer().with_writer(std::io::stderr);
+
+    let dir = log_dir();
+    let appender = std::fs::create_dir_all(&dir).and_then(|()| {
+        RotatingFile::open(dir.join("sl-ot-viewer.log"), MAX_LOG_BYTES, KEPT_LOGS)
+    });
+    let (file, guard) = match appender {
+        Ok(appender) => {
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            let layer = fmt::layer().with_writer(writer).with_ansi(false);
+            (Some(layer), Some(guard))
+        }
+        Err(e) => {
+            eprintln!("Not logging to a file in {}: {}", dir.display(), e);
+            (None, None)
+        }
+    };
+
+    tracing_subscriber::registry().with(filter).with(stderr).with(file).init();
+    tracing::info!(dir = %dir.display(), "logging to file");
+    guard
+}
+
+/// A file appended to until the next write would take it past `max_bytes`,
+/// when it's renamed `<name>.1` (an older `.1` moving to `.2`, and so on) and
+/// a fresh one started. Only `keep` old files are kept.
+pub struct RotatingFile {
+    path: PathBuf,
+    max_bytes: u64,
+    keep: usize,
+    file: File,
+    len: u64,
+}
+
+impl RotatingFile {
+    pub fn open(path: PathBuf, max_bytes: u64, keep: usize) -> io::Result<Self> {
+        let file = append_to(&path)?;
+        let len = file.metadata()?.len();
+        Ok(Self { path, max_bytes, keep, file, len })
+    }
+
+    fn rotated(&self, n: usize) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{}", n));
+        PathBuf::from(name)
+    }
+
+    /// Shift the old files up one, dropping the oldest, then start afresh. If
+    /// a rename fails the current file carries on growing until the next try.
+    fn rotate(&mut self) -> io::Result<()> {
+        let shifted = (|| {
+            if self.keep == 0 {
+                return std::fs::remove_file(&self.path);
+            }
+            match std::fs::remove_file(self.rotated(self.keep)) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                _ => {}
+            }
+            for n in (1..self.keep).rev() {
+                let from = self.rotated(n);
+                if from.exists() {
+                    std::fs::rename(&from, self.rotated(n + 1))?;
+                }
+            }
+            // Windows renames a file that's open for appending only because
+            // std opens files with FILE_SHARE_DELETE.
+            std::fs::rename(&self.path, self.rotated(1))
+        })();
+        if let Err(e) = &shifted {
+            eprintln!("Failed to rotate {}: {}", self.path.display(), e);
+        }
+        self.file = append_to(&self.path)?;
+        self.len = 0;
+        Ok(())
+    }
+}
+
+fn append_to(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.len > 0 && self.len + buf.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+        let written = self.file.write(buf)?;
+        self.len += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn rotates_past_the_size_cap() {
+        let dir = TempDir::new("log-rotation");
+        let path = dir.path().join("viewer.log");
+        let mut log = RotatingFile::open(path.clone(), 100, 2).unwrap();
+        for i in 0..12 {
+            // One write per line, as the non-blocking writer hands over events.
+            let line = format!("line {:02} padded out to thirty b\n", i);
+            log.write_all(line.as_bytes()).unwrap();
+        }
+        log.flush().unwrap();
+
+        let read = |name: &str| std::fs::read_to_string(dir.path().join(name)).unwrap();
+        // Three 31-byte lines fit in 100 bytes, so lines 09-11 are current.
+        assert!(read("viewer.log").starts_with("line 09"));
+        assert!(read("viewer.log.1").starts_with("line 06"));
+        assert!(read("viewer.log.2").starts_with("line 03"));
+        assert!(!dir.path().join("viewer.log.3").exists());
+        for name in ["viewer.log", "viewer.log.1", "viewer.log.2"] {
+            assert_eq!(read(name).len(), 93, "{}", name);
+        }
+
+        // Reopening carries on from the existing file's size.
+        let mut log = RotatingFile::open(path, 100, 2).unwrap();
+        log.write_all(b"ten bytes\n").unwrap();
+        assert!(read("viewer.log.1").starts_with("line 09"));
+    }
+}
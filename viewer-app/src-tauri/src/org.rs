@@ -0,0 +1,267 @@
+// ── Org chart hierarchy (the reporting tree, ready to render) ──────────────
+
+use crate::company;
+use crate::config::ViewerConfig;
+use crate::error::CommandError;
+use crate::telemetry;
+use crate::validate::{self, make_id, strip_note};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+/// One person in the reporting tree, with everyone who reports to them.
+#[derive(Debug, serde::Serialize)]
+pub struct OrgNode {
+    pub id: String,
+    /// `leadership`, `people`, `team`, or `external` for key contacts.
+    pub section: String,
+    /// The person's record from org_chart.json. External contacts get `org`
+    /// and `level` filled in the way the viewer does.
+    pub person: serde_json::Value,
+    pub engagements: Vec<EngagementRef>,
+    pub reports: Vec<OrgNode>,
+}
+
+/// An engagement a person is associated with, and through which of its
+/// workstreams.
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub struct EngagementRef {
+    pub key: String,
+    pub label: String,
+    pub workstreams: Vec<String>,
+}
+
+/// The org chart as a forest of reporting trees, roots in chart order. Each
+/// person hangs under the first-listed person their `reports_to` names;
+/// anyone whose manager isn't in the chart is a root. A reporting loop is cut
+/// at its smallest id, which becomes a root. People are linked to the
+/// engagements whose workstreams list their email in `people_associations`.
+#[tauri::command]
+pub fn get_org_chart_tree(
+    app: tauri::AppHandle,
+    config: tauri::State<'_, ViewerConfig>,
+    repo_path: String,
+) -> Result<Vec<OrgNode>, CommandError> {
+    telemetry::timed(&app, "get_org_chart_tree", || {
+        let company_dir = company::company_dir(Path::new(&repo_path))?;
+        let read = |filename: &str| -> Result<Option<serde_json::Value>, CommandError> {
+            match company::find_company_file(&company_dir, filename) {
+                Some(path) => company::parse_company_file(
+                    &path,
+                    &company::file_name(&path),
+                    config.max_file_bytes,
+                )
+                .map(Some),
+                None => Ok(None),
+            }
+        };
+        let chart = read("org_chart.json")?
+            .ok_or_else(|| {
+                CommandError::NotFound(format!("No org_chart.json found in {}", company_dir.display()))
+            })?;
+        // Without the map the tree is still worth drawing.
+        let map = read("engagement_map.json")
+            .unwrap_or_else(|e| {
+                tracing::warn!("{}", e);
+                None
+            })
+            .unwrap_or_default();
+        Ok(org_tree(&chart, &map))
+    })
+}
+
+fn org_tree(chart: &serde_json::Value, map: &serde_json::Value) -> Vec<OrgNode> {
+    let people = chart_people(chart);
+    let index: HashMap<&str, usize> = people
+        .iter()
+        .enumerate()
+        .map(|(i, (id, _, _))| (id.as_str(), i))
+        .collect();
+
+    let mut manager_of: BTreeMap<&str, String> = BTreeMap::new();
+    for (id, _, person) in &people {
+        let Some(reference) = person["reports_to"].as_str() else {
+            continue;
+        };
+        let target = make_id(strip_note(reference));
+        if index.contains_key(target.as_str()) {
+            manager_of.insert(id, target);
+        }
+    }
+    for cycle in validate::reporting_cycles(&manager_of) {
+        manager_of.remove(cycle[0].as_str());
+    }
+
+    let mut reports: Vec<Vec<usize>> = vec![Vec::new(); people.len()];
+    let mut roots = Vec::new();
+    for (i, (id, _, _)) in people.iter().enumerate() {
+        match manager_of.get(id.as_str()) {
+            Some(manager) => reports[index[manager.as_str()]].push(i),
+            None => roots.push(i),
+        }
+    }
+
+    let engagements = engagements_by_email(map);
+    let mut people: Vec<Option<(String, String, serde_json::Value)>> =
+        people.into_iter().map(Some).collect();
+    roots
+        .into_iter()
+        .map(|i| build_node(i, &mut people, &reports, &engagements))
+        .collect()
+}
+
+/// Detach person `i` and, recursively, their reports. With the loops cut,
+/// every person is reached exactly once.
+fn build_node(
+    i: usize,
+    people: &mut [Option<(String, String, serde_json::Value)>],
+    reports: &[Vec<usize>],
+    engagements: &HashMap<String, Vec<EngagementRef>>,
+) -> OrgNode {
+    let (id, section, person) = people[i].take().unwrap_or_default();
+    let email = person["email"].as_str().unwrap_or_default().to_lowercase();
+    OrgNode {
+        id,
+        section,
+        person,
+        engagements: engagements.get(&email).cloned().unwrap_or_default(),
+        reports: reports[i]
+            .iter()
+            .map(|&child| build_node(child, people, reports, engagements))
+            .collect(),
+    }
+}
+
+/// Everyone in the chart as (id, section, record), in the order the viewer
+/// adds them, keeping the first person with each id.
+pub fn chart_people(chart: &serde_json::Value) -> Vec<(String, String, serde_json::Value)> {
+    let mut people: Vec<(String, String, serde_json::Value)> = Vec::new();
+    let mut add = |section: &str, person: serde_json::Value| {
+        let id = person["name"].as_str().map(make_id).unwrap_or_default();
+        if !id.is_empty() && !people.iter().any(|(seen, _, _)| *seen == id) {
+            people.push((id, section.to_string(), person));
+        }
+    };
+
+    for section in validate::PEOPLE_SECTIONS {
+        for person in chart[section].as_array().into_iter().flatten() {
+            if person.is_object() {
+                add(section, person.clone());
+            }
+        }
+    }
+
+    let orgs: Vec<&serde_json::Value> = match &chart["external_ecosystem"] {
+        serde_json::Value::Object(map) => map.values().collect(),
+        serde_json::Value::Array(list) => list.iter().collect(),
+        _ => Vec::new(),
+    };
+    for org in orgs {
+        let org_name = org.get("org").or_else(|| org.get("name")).cloned();
+        for contact in org["key_contacts"].as_array().into_iter().flatten() {
+            let serde_json::Value::Object(mut person) = contact.clone() else {
+                continue;
+            };
+            if person.get("org").is_none_or(|v| v.is_null()) {
+                if let Some(name) = &org_name {
+                    person.insert("org".to_string(), name.clone());
+                }
+            }
+            if person.get("level").is_none_or(|v| v.is_null()) {
+                person.insert("level".to_string(), "external".into());
+            }
+            add("external", serde_json::Value::Object(person));
+        }
+    }
+    people
+}
+
+/// Engagements per lowercased email, from the workstreams'
+/// `people_associations` in engagement_map.json.
+fn engagements_by_email(map: &serde_json::Value) -> HashMap<String, Vec<EngagementRef>> {
+    let mut by_email: HashMap<String, Vec<EngagementRef>> = HashMap::new();
+    for eng in map["engagements"].as_array().into_iter().flatten() {
+        let Some(key) = eng["key"].as_str() else {
+            continue;
+        };
+        let label = eng["label"].as_str().unwrap_or(key);
+        for ws in eng["workstreams"].as_array().into_iter().flatten() {
+            let Some(ws_key) = ws["key"].as_str() else {
+                continue;
+            };
+            for email in ws["people_associations"].as_array().into_iter().flatten() {
+                let Some(email) = email.as_str().filter(|e| !e.trim().is_empty()) else {
+                    continue;
+                };
+                let refs = by_email.entry(email.trim().to_lowercase()).or_default();
+                match refs.iter_mut().find(|r| r.key == key) {
+                    Some(existing) => existing.workstreams.push(ws_key.to_string()),
+                    None => refs.push(EngagementRef {
+                        key: key.to_string(),
+                        label: label.to_string(),
+                        workstreams: vec![ws_key.to_string()],
+                    }),
+                }
+            }
+        }
+    }
+    by_email
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shape(node: &OrgNode) -> String {
+        if node.reports.is_empty() {
+            return node.id.clone();
+        }
+        let reports: Vec<String> = node.reports.iter().map(shape).collect();
+        format!("{}({})", node.id, reports.join(" "))
+    }
+
+    #[test]
+    fn builds_the_reporting_tree_and_cuts_loops() {
+        let chart = serde_json::json!({
+            "leadership": [{ "name": "Ana", "email": "Ana@acme.com" }],
+            "people": [
+                { "name": "Ben", "reports_to": "Ana (inferred)" },
+                { "name": "Cy", "reports_to": "Ben" },
+                { "name": "Dee", "reports_to": "Eve" },
+                { "name": "Eve", "reports_to": "Dee" },
+                { "name": "Fay", "reports_to": "Nobody" },
+                { "name": "Ben", "reports_to": "Cy" },
+            ],
+            "external_ecosystem": {
+                "vendor": { "name": "Vendor Co", "key_contacts": [{ "name": "Gus", "reports_to": "Ana" }] },
+            },
+        });
+        let map = serde_json::json!({
+            "engagements": [{
+                "key": "pump-upgrade",
+                "label": "Pump Upgrade",
+                "workstreams": [
+                    { "key": "ops", "people_associations": ["ana@acme.com"] },
+                    { "key": "it", "people_associations": ["ana@acme.com", "other@acme.com"] },
+                ],
+            }],
+        });
+        let tree = org_tree(&chart, &map);
+
+        let shapes: Vec<String> = tree.iter().map(shape).collect();
+        assert_eq!(shapes, ["ana(ben(cy) gus)", "dee(eve)", "fay"]);
+        assert_eq!(
+            tree[0].engagements,
+            [EngagementRef {
+                key: "pump-upgrade".into(),
+                label: "Pump Upgrade".into(),
+                workstreams: vec!["ops".into(), "it".into()],
+            }]
+        );
+        let gus = &tree[0].reports[1];
+        assert_eq!(
+            (gus.section.as_str(), &gus.person["org"]),
+            ("external", &serde_json::json!("Vendor Co"))
+        );
+        assert_eq!(gus.person["level"], "external");
+    }
+}
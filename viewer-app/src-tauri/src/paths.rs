@@ -0,0 +1,207 @@
+// ── Path sandboxing ────────────────────────────────────────────────────────
+
+use crate::error::CommandError;
+use std::fs::DirEntry;
+use std::path::{Component, Path, PathBuf};
+
+/// Resolve the user-supplied `relative` path against `base`, refusing
+/// anything that would land outside it: absolute paths, `..` traversal, and
+/// symlinks or reparse points that point elsewhere. The file must exist,
+/// since resolving links requires it.
+pub fn resolve_within(base: &Path, relative: &str) -> Result<PathBuf, CommandError> {
+    let rel = Path::new(relative);
+    if relative.is_empty() {
+        return Err(CommandError::InvalidInput("Path is empty".to_string()));
+    }
+    // has_root catches `\foo` on Windows, which isn't "absolute" there.
+    if rel.is_absolute() || rel.has_root() || rel.components().any(|c| matches!(c, Component::Prefix(_))) {
+        return Err(CommandError::PathTraversal(format!("Absolute paths are not allowed: {}", relative)));
+    }
+
+    let base = base
+        .canonicalize()
+        .map_err(|e| CommandError::io(format_args!("Failed to resolve {}", base.display()), &e))?;
+    let path = base
+        .join(rel)
+        .canonicalize()
+        .map_err(|e| CommandError::io(format_args!("Failed to resolve {}", relative), &e))?;
+
+    if !path.starts_with(&base) {
+        return Err(CommandError::PathTraversal(format!("Path escapes {}: {}", base.display(), relative)));
+    }
+    Ok(path)
+}
+
+/// Check that a user-supplied name is a single plain directory or file name,
+/// so joining it onto a base path can't go anywhere else.
+pub fn plain_name(name: &str) -> Result<&str, CommandError> {
+    let mut components = Path::new(name).components();
+    let plain = matches!(components.next(), Some(Component::Normal(_)))
+        && components.next().is_none()
+        && !name.contains(['/', '\\']);
+    if !plain {
+        return Err(CommandError::PathTraversal(format!("Not a plain name: {:?}", name)));
+    }
+    Ok(name)
+}
+
+/// How a scan of the folder `root` treats symlinks. std's `is_dir` and
+/// `is_file` follow them, so a link could lead a scan out of the repo or
+/// round in a loop; scans ask here instead. Links are skipped unless
+/// following is on, and then they count as what they point to only if that's
+/// inside `root` and isn't a folder the link is already inside.
+pub struct Symlinks {
+    /// `root`, canonical, when links are followed.
+    follow_within: Option<PathBuf>,
+}
+
+impl Symlinks {
+    pub fn new(root: &Path, follow: bool) -> Self {
+        let follow_within = if follow { root.canonicalize().ok() } else { None };
+        Symlinks { follow_within }
+    }
+
+    /// Whether the `entry` read from `dir` is a folder to scan.
+    pub fn is_dir(&self, dir: &Path, entry: &DirEntry) -> bool {
+        match entry.file_type() {
+            Ok(kind) if kind.is_symlink() => self.target(&entry.path()).is_some_and(|target| {
+                // A link to the folder it's in, or anything above, would be
+                // scanned again and again.
+                target.is_dir() && !dir.canonicalize().is_ok_and(|dir| dir.starts_with(&target))
+            }),
+            Ok(kind) => kind.is_dir(),
+            Err(_) => false,
+        }
+    }
+
+    /// Whether `entry` is a file to read.
+    pub fn is_file(&self, entry: &DirEntry) -> bool {
+        match entry.file_type() {
+            Ok(kind) if kind.is_symlink() => self.target(&entry.path()).is_some_and(|target| target.is_file()),
+            Ok(kind) => kind.is_file(),
+            Err(_) => false,
+        }
+    }
+
+    /// Where the link at `path` leads, if it's followed and stays inside.
+    fn target(&self, path: &Path) -> Option<PathBuf> {
+        let root = self.follow_within.as_ref()?;
+        let target = path.canonicalize().ok()?;
+        if !target.starts_with(root) {
+            tracing::debug!(link = %path.display(), "symlink leads out of {}; skipped", root.display());
+            return None;
+        }
+        Some(target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn resolves_nested_files() {
+        let dir = TempDir::new("paths-nested");
+        let file = dir.write("data/nested/config.json", "{}");
+        let resolved = resolve_within(dir.path(), "data/nested/config.json").unwrap();
+        assert_eq!(resolved, file.canonicalize().unwrap());
+    }
+
+    #[test]
+    fn allows_dot_dot_that_stays_inside() {
+        let dir = TempDir::new("paths-inside");
+        dir.write("a/b.json", "{}");
+        dir.write("c/d.json", "{}");
+        assert!(resolve_within(dir.path(), "a/../c/d.json").is_ok());
+    }
+
+    #[test]
+    fn rejects_dot_dot_traversal() {
+        let dir = TempDir::new("paths-traversal");
+        let inner = dir.path().join("exe");
+        std::fs::create_dir_all(&inner).unwrap();
+        dir.write("secret.json", "{}");
+        let err = resolve_within(&inner, "../secret.json").unwrap_err();
+        assert!(matches!(err, CommandError::PathTraversal(_)), "{:?}", err);
+        assert!(err.message().contains("escapes"), "{}", err);
+    }
+
+    #[test]
+    fn rejects_absolute_paths() {
+        let dir = TempDir::new("paths-absolute");
+        let file = dir.write("x.json", "{}");
+        let err = resolve_within(dir.path(), &file.to_string_lossy()).unwrap_err();
+        assert!(err.message().contains("Absolute"), "{}", err);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_symlinks_pointing_outside() {
+        let dir = TempDir::new("paths-symlink");
+        let inner = dir.path().join("exe");
+        std::fs::create_dir_all(&inner).unwrap();
+        dir.write("outside.json", "{}");
+        std::os::unix::fs::symlink(dir.path().join("outside.json"), inner.join("link.json")).unwrap();
+        let err = resolve_within(&inner, "link.json").unwrap_err();
+        assert!(err.message().contains("escapes"), "{}", err);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_are_followed_only_inside_and_never_up() {
+        let dir = TempDir::new("paths-links");
+        let root = dir.path().join("repo");
+        dir.write("repo/shared/ops/KNOWLEDGE_LOG.md", "");
+        dir.write("outside/secret.md", "");
+        dir.write("repo/alpha/engagement_config.json", "{}");
+        let link = |target: &Path, name: &str| std::os::unix::fs::symlink(target, root.join(name)).unwrap();
+        link(&root.join("shared"), "alpha/linked");
+        link(&dir.path().join("outside"), "alpha/escape");
+        link(&root, "alpha/loop");
+        link(&root.join("alpha"), "alpha/self");
+        link(&dir.path().join("outside/secret.md"), "alpha/secret.md");
+
+        let kinds = |links: &Symlinks| -> Vec<(String, bool, bool)> {
+            let alpha = root.join("alpha");
+            let mut kinds: Vec<_> = std::fs::read_dir(&alpha)
+                .unwrap()
+                .flatten()
+                .map(|e| (e.file_name().to_string_lossy().into_owned(), links.is_dir(&alpha, &e), links.is_file(&e)))
+                .collect();
+            kinds.sort();
+            kinds
+        };
+        let kind = |name: &str, is_dir, is_file| (name.to_string(), is_dir, is_file);
+        assert_eq!(
+            kinds(&Symlinks::new(&root, false)),
+            [
+                kind("engagement_config.json", false, true),
+                kind("escape", false, false),
+                kind("linked", false, false),
+                kind("loop", false, false),
+                kind("secret.md", false, false),
+                kind("self", false, false),
+            ]
+        );
+        assert_eq!(
+            kinds(&Symlinks::new(&root, true)),
+            [
+                kind("engagement_config.json", false, true),
+                kind("escape", false, false),
+                kind("linked", true, false),
+                kind("loop", false, false),
+                kind("secret.md", false, false),
+                kind("self", false, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn plain_names_are_single_components() {
+        assert!(plain_name("ops").is_ok());
+        for name in ["", ".", "..", "a/b", "a\\b", "/abs"] {
+            assert!(plain_name(name).is_err(), "{}", name);
+        }
+    }
+}
@@ -0,0 +1,232 @@
+// ── External programs (one-shot commands, and the editor) ──────────────────
+
+use crate::error::CommandError;
+use crate::launch::LaunchConfig;
+use crate::telemetry;
+use std::io::Read;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, serde::Serialize)]
+pub struct CommandResult {
+    stdout: String,
+    stderr: String,
+    /// None if the process was terminated by a signal.
+    code: Option<i32>,
+}
+
+/// Run `program` to completion and return everything it wrote. With
+/// `timeout_ms`, the child is killed and an error returned if it's still
+/// running at the deadline. Runs off the main thread so a slow command
+/// doesn't freeze the window.
+#[tauri::command(async)]
+pub fn run_command(
+    app: tauri::AppHandle,
+    launch: tauri::State<'_, LaunchConfig>,
+    cwd: Option<String>,
+    program: String,
+    args: Vec<String>,
+    timeout_ms: Option<u64>,
+) -> Result<CommandResult, CommandError> {
+    telemetry::timed(&app, "run_command", || {
+        run(&launch, cwd, program, args, timeout_ms)
+    })
+}
+
+fn run(
+    launch: &LaunchConfig,
+    cwd: Option<String>,
+    program: String,
+    args: Vec<String>,
+    timeout_ms: Option<u64>,
+) -> Result<CommandResult, CommandError> {
+    launch.ensure_writable("run_command")?;
+    let mut cmd = Command::new(&program);
+    cmd.args(&args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if let Some(cwd) = &cwd {
+        if !Path::new(cwd).is_dir() {
+            return Err(CommandError::NotFound(format!("Working directory is not a directory: {}", cwd)));
+        }
+        cmd.current_dir(cwd);
+    }
+
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| CommandError::io(format_args!("Failed to run {}", program), &e))?;
+
+    // Drain both pipes concurrently so a chatty stream can't fill its pipe
+    // buffer and deadlock the child.
+    let missing = |stream: &str| CommandError::Internal(format!("Failed to get {}", stream));
+    let mut stdout = child.stdout.take().ok_or_else(|| missing("stdout"))?;
+    let mut stderr = child.stderr.take().ok_or_else(|| missing("stderr"))?;
+    let stdout_thread = std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = stdout.read_to_end(&mut buf);
+        buf
+    });
+    let stderr_thread = std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = stderr.read_to_end(&mut buf);
+        buf
+    });
+
+    let status = match timeout_ms {
+        None => child
+            .wait()
+            .map_err(|e| CommandError::io(format_args!("Failed to wait for {}", program), &e))?,
+        Some(ms) => {
+            let deadline = Instant::now() + Duration::from_millis(ms);
+            loop {
+                match child.try_wait() {
+                    Ok(Some(status)) => break status,
+                    Ok(None) if Instant::now() >= deadline => {
+                        let _ = child.kill();
+                        let _ = child.wait();
+                        return Err(CommandError::Io(format!("{} timed out after {} ms", program, ms)));
+                    }
+                    Ok(None) => std::thread::sleep(Duration::from_millis(10)),
+                    Err(e) => {
+                        return Err(CommandError::io(format_args!("Failed to wait for {}", program), &e))
+                    }
+                }
+            }
+        }
+    };
+
+    let stdout = stdout_thread.join().unwrap_or_default();
+    let stderr = stderr_thread.join().unwrap_or_default();
+    Ok(CommandResult {
+        stdout: String::from_utf8_lossy(&stdout).into_owned(),
+        stderr: String::from_utf8_lossy(&stderr).into_owned(),
+        code: status.code(),
+    })
+}
+
+/// Open `relative_path` (inside the repo) in the user's editor: `$VISUAL`,
+/// else `$EDITOR`, else VS Code's `code`, else whatever the OS opens the file
+/// with. The editor is started and left running; only a failure to start it
+/// is reported. A terminal editor like vim needs a terminal, so `$VISUAL` is
+/// the one to point at a GUI editor.
+#[tauri::command]
+pub fn open_in_editor(
+    app: tauri::AppHandle,
+    launch: tauri::State<'_, LaunchConfig>,
+    repo_path: String,
+    relative_path: String,
+) -> Result<(), CommandError> {
+    telemetry::timed(&app, "open_in_editor", || {
+        open_editor(&launch, repo_path, relative_path)
+    })
+}
+
+fn open_editor(launch: &LaunchConfig, repo_path: String, relative_path: String) -> Result<(), CommandError> {
+    launch.ensure_writable("open_in_editor")?;
+    let path = crate::paths::resolve_within(Path::new(&repo_path), &relative_path)?;
+
+    let mut candidates: Vec<Vec<String>> = ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .map(|value| value.split_whitespace().map(str::to_string).collect::<Vec<_>>())
+        .filter(|words| !words.is_empty())
+        .collect();
+    candidates.push(vec!["code".to_string()]);
+    candidates.push(os_opener());
+
+    for words in &candidates {
+        let mut cmd = Command::new(&words[0]);
+        cmd.args(&words[1..])
+            .arg(&path)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        match cmd.spawn() {
+            Ok(mut child) => {
+                tracing::info!(editor = %words.join(" "), path = %path.display(), "open_in_editor");
+                // Reap it whenever it exits so it doesn't linger as a zombie.
+                std::thread::spawn(move || child.wait());
+                return Ok(());
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(CommandError::io(format_args!("Failed to start {}", words[0]), &e)),
+        }
+    }
+    let message = format!("No editor found to open {} (set $VISUAL or $EDITOR)", relative_path);
+    Err(CommandError::NotFound(message))
+}
+
+/// The command that opens a file in its default application.
+fn os_opener() -> Vec<String> {
+    let words: &[&str] = if cfg!(target_os = "windows") {
+        // `start` treats its first quoted argument as a window title.
+        &["cmd", "/C", "start", ""]
+    } else if cfg!(target_os = "macos") {
+        &["open"]
+    } else {
+        &["xdg-open"]
+    };
+    words.iter().map(|w| w.to_string()).collect()
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn sh(script: &str, timeout_ms: Option<u64>) -> Result<CommandResult, CommandError> {
+        run(&LaunchConfig::default(), None, "sh".into(), vec!["-c".into(), script.into()], timeout_ms)
+    }
+
+    #[test]
+    fn captures_both_streams_and_exit_code() {
+        let result = sh("echo out; echo err >&2; exit 3", None).unwrap();
+        assert_eq!(result.stdout, "out\n");
+        assert_eq!(result.stderr, "err\n");
+        assert_eq!(result.code, Some(3));
+    }
+
+    #[test]
+    fn kills_the_child_on_timeout() {
+        let started = Instant::now();
+        let err = sh("sleep 5", Some(100)).unwrap_err();
+        assert!(err.message().contains("timed out"), "{}", err);
+        assert!(started.elapsed() < Duration::from_secs(4));
+    }
+
+    #[test]
+    fn editor_paths_must_stay_in_the_repo() {
+        let dir = crate::test_support::TempDir::new("open-editor");
+        dir.write("repo/notes.md", "");
+        dir.write("outside.md", "");
+        let repo = dir.path().join("repo").display().to_string();
+        let launch = LaunchConfig::default();
+        let err = open_editor(&launch, repo.clone(), "../outside.md".into()).unwrap_err();
+        assert!(matches!(err, CommandError::PathTraversal(_)), "{:?}", err);
+        assert!(open_editor(&launch, repo, "missing.md".into()).is_err());
+    }
+
+    #[test]
+    fn rejects_missing_working_directory() {
+        let launch = LaunchConfig::default();
+        let err = run(&launch, Some("/no/such/dir".into()), "true".into(), vec![], None).unwrap_err();
+        assert!(err.message().contains("not a directory"), "{}", err);
+    }
+
+    #[test]
+    fn read_only_runs_nothing() {
+        let dir = crate::test_support::TempDir::new("process-read-only");
+        dir.write("repo/notes.md", "");
+        let launch = LaunchConfig { read_only: true, ..LaunchConfig::default() };
+        let marker = dir.path().join("ran");
+        let script = format!("touch '{}'", marker.display());
+        let err = run(&launch, None, "sh".into(), vec!["-c".into(), script], None).unwrap_err();
+        assert!(matches!(err, CommandError::ReadOnly(_)), "{:?}", err);
+        assert!(!marker.exists());
+
+        let repo = dir.path().join("repo").display().to_string();
+        let err = open_editor(&launch, repo, "notes.md".into()).unwrap_err();
+        assert!(matches!(err, CommandError::ReadOnly(_)), "{:?}", err);
+    }
+}
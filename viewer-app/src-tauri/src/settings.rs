@@ -0,0 +1,167 @@
+// ── Per-user settings (settings.json in the OS config dir) ─────────────────
+
+use crate::company;
+use crate::config;
+use crate::error::CommandError;
+use crate::launch::LaunchConfig;
+use crate::telemetry;
+use std::path::{Path, PathBuf};
+
+/// Larger than any settings file a person would write by hand.
+const MAX_SETTINGS_BYTES: u64 = 256 * 1024;
+
+/// One user's overrides, kept out of the shared repo. The UI lays these over
+/// company_config.json; a field left out falls back to the company's value
+/// or the viewer's own default.
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    /// UI theme, e.g. `dark`. `--theme` still wins.
+    pub theme: Option<String>,
+    /// Program spawn_terminal runs instead of the auto-detected shell.
+    pub default_shell: Option<String>,
+    /// Engagement key to select when a repo is opened.
+    pub default_engagement: Option<String>,
+}
+
+/// Where settings live: `SL_OT_SETTINGS_DIR` if set, else the OS config dir
+/// (e.g. `%APPDATA%\sl-ot-viewer\settings.json`). None if the OS has no
+/// config dir.
+pub fn settings_path() -> Option<PathBuf> {
+    let dir = match std::env::var_os("SL_OT_SETTINGS_DIR") {
+        Some(dir) => PathBuf::from(dir),
+        None => dirs::config_dir()?.join("sl-ot-viewer"),
+    };
+    Some(dir.join("settings.json"))
+}
+
+/// The user's settings, written out as defaults first if there are none yet
+/// (unless the viewer is read-only). A settings file that can't be read or
+/// doesn't have the expected shape is logged and the defaults returned, so a
+/// bad hand edit never keeps the viewer from starting.
+#[tauri::command]
+pub fn read_settings(app: tauri::AppHandle, launch: tauri::State<'_, LaunchConfig>) -> Settings {
+    telemetry::timed_value(&app, "read_settings", || match settings_path() {
+        Some(path) => load(&path, !launch.read_only),
+        None => {
+            tracing::warn!("no config dir for settings; using defaults");
+            Settings::default()
+        }
+    })
+}
+
+/// Replace the user's settings with `value`. Fields the viewer doesn't know
+/// are rejected by deserialization before this runs.
+#[tauri::command]
+pub fn write_settings(
+    app: tauri::AppHandle,
+    launch: tauri::State<'_, LaunchConfig>,
+    value: Settings,
+) -> Result<(), CommandError> {
+    telemetry::timed(&app, "write_settings", || store(&launch, settings_path(), &value))
+}
+
+fn store(launch: &LaunchConfig, path: Option<PathBuf>, settings: &Settings) -> Result<(), CommandError> {
+    launch.ensure_writable("write_settings")?;
+    let path =
+        path.ok_or_else(|| CommandError::Internal("No config directory for settings".to_string()))?;
+    save(&path, settings)
+}
+
+fn load(path: &Path, create: bool) -> Settings {
+    if !path.exists() {
+        let defaults = Settings::default();
+        if create {
+            if let Err(e) = save(path, &defaults) {
+                tracing::warn!(path = %path.display(), "not creating settings: {}", e);
+            }
+        }
+        return defaults;
+    }
+    let parsed = config::read_capped(path, "settings.json", MAX_SETTINGS_BYTES).and_then(|text| {
+        serde_json::from_str(company::strip_bom(&text))
+            .map_err(|e| CommandError::Parse(format!("Failed to parse settings.json: {}", e)))
+    });
+    parsed.unwrap_or_else(|e| {
+        tracing::warn!(path = %path.display(), "using default settings: {}", e);
+        Settings::default()
+    })
+}
+
+fn save(path: &Path, settings: &Settings) -> Result<(), CommandError> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| CommandError::io(format_args!("Failed to create {}", dir.display()), &e))?;
+    }
+    let text = serde_json::to_string_pretty(settings)
+        .map_err(|e| CommandError::Internal(format!("Failed to serialize settings: {}", e)))?;
+    config::write_atomic(path, &format!("{}\n", text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn missing_settings_are_created_with_defaults() {
+        let dir = TempDir::new("settings-missing");
+        let path = dir.path().join("sl-ot-viewer").join("settings.json");
+
+        assert_eq!(load(&path, false), Settings::default());
+        assert!(!path.exists());
+
+        assert_eq!(load(&path, true), Settings::default());
+        assert_eq!(load(&path, false), Settings::default());
+        assert!(path.exists());
+    }
+
+    #[test]
+    fn saved_settings_load_back() {
+        let dir = TempDir::new("settings-valid");
+        let path = dir.path().join("settings.json");
+        let settings = Settings {
+            theme: Some("light".into()),
+            default_shell: Some("pwsh".into()),
+            default_engagement: None,
+        };
+        save(&path, &settings).unwrap();
+        assert_eq!(load(&path, true), settings);
+
+        let path = dir.path().join("stored.json");
+        store(&LaunchConfig::default(), Some(path.clone()), &settings).unwrap();
+        assert_eq!(load(&path, false), settings);
+
+        // Fields left out of a hand-written file take their defaults.
+        let path = dir.write("partial.json", "\u{feff}{\"default_engagement\": \"acme\"}");
+        let settings = load(&path, true);
+        assert_eq!(settings.default_engagement.as_deref(), Some("acme"));
+        assert_eq!(settings.theme, None);
+    }
+
+    #[test]
+    fn corrupt_settings_fall_back_to_defaults() {
+        let dir = TempDir::new("settings-corrupt");
+        for (name, text) in [
+            ("truncated.json", "{\"theme\": \"da"),
+            ("wrong-type.json", "{\"theme\": 5}"),
+            ("unknown-field.json", "{\"colour\": \"red\"}"),
+            ("not-an-object.json", "[1, 2]"),
+        ] {
+            let path = dir.write(name, text);
+            assert_eq!(load(&path, true), Settings::default(), "{}", name);
+            // The bad file is left for the user to fix, not overwritten.
+            assert_eq!(std::fs::read_to_string(&path).unwrap(), text);
+        }
+    }
+
+    #[test]
+    fn read_only_does_not_write_settings() {
+        let dir = TempDir::new("settings-read-only");
+        let path = dir.path().join("settings.json");
+        let launch = LaunchConfig { read_only: true, ..LaunchConfig::default() };
+        let err = store(&launch, Some(path.clone()), &Settings::default()).unwrap_err();
+        assert!(matches!(err, CommandError::ReadOnly(_)), "{:?}", err);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,165 @@
+// ── Following a growing file (tail -f for the UI) ──────────────────────────
+
+use crate::error::CommandError;
+use std::collections::HashMap;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::Emitter;
+
+/// How often a tailed file is checked for new data.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Running tails by id. Setting a tail's flag ends its thread at the next
+/// poll.
+pub type TailState = Arc<Mutex<HashMap<String, Arc<AtomicBool>>>>;
+
+static NEXT_TAIL_ID: AtomicU64 = AtomicU64::new(1);
+
+#[derive(Clone, serde::Serialize)]
+struct FileLine {
+    tail_id: String,
+    line: String,
+}
+
+/// Follow `relative_path` (inside the repo) like `tail -f`, emitting a
+/// `file-line` event with `{tail_id, line}` for each line as it's written.
+/// With `from_end` only lines added from now on are sent, otherwise the
+/// whole file first. A file that shrinks (truncated, or replaced by a
+/// rotation) is read again from the start, and one that disappears is
+/// waited for. An unterminated last line is held back until its newline
+/// arrives. Returns the id to pass to stop_tail.
+#[tauri::command]
+pub fn tail_file(
+    state: tauri::State<'_, TailState>,
+    app: tauri::AppHandle,
+    repo_path: String,
+    relative_path: String,
+    from_end: bool,
+) -> Result<String, CommandError> {
+    let path = crate::paths::resolve_within(Path::new(&repo_path), &relative_path)?;
+    if !path.is_file() {
+        return Err(CommandError::InvalidInput(format!("Not a file: {}", relative_path)));
+    }
+    let tail_id = format!("tail-{}", NEXT_TAIL_ID.fetch_add(1, Ordering::SeqCst));
+    let stop = Arc::new(AtomicBool::new(false));
+    state.lock()?.insert(tail_id.clone(), stop.clone());
+
+    let id = tail_id.clone();
+    tracing::info!(tail_id = %id, path = %path.display(), from_end, "tail_file");
+    std::thread::spawn(move || {
+        follow(&path, from_end, &stop, POLL_INTERVAL, |line| {
+            let _ = app.emit(
+                "file-line",
+                FileLine {
+                    tail_id: id.clone(),
+                    line,
+                },
+            );
+        });
+        tracing::debug!(tail_id = %id, "tail stopped");
+    });
+    Ok(tail_id)
+}
+
+/// End the tail started as `tail_id`.
+#[tauri::command]
+pub fn stop_tail(state: tauri::State<'_, TailState>, tail_id: String) -> Result<(), CommandError> {
+    let stop = state
+        .lock()?
+        .remove(&tail_id)
+        .ok_or_else(|| CommandError::NotFound(format!("No tail running for {}", tail_id)))?;
+    stop.store(true, Ordering::SeqCst);
+    Ok(())
+}
+
+/// Poll `path` every `interval` until `stop` is set, handing each complete
+/// line (without its line ending) to `emit`.
+fn follow(
+    path: &Path,
+    from_end: bool,
+    stop: &AtomicBool,
+    interval: Duration,
+    mut emit: impl FnMut(String),
+) {
+    let mut pos = if from_end {
+        std::fs::metadata(path).map_or(0, |m| m.len())
+    } else {
+        0
+    };
+    let mut partial: Vec<u8> = Vec::new();
+    let mut buf = Vec::new();
+    while !stop.load(Ordering::SeqCst) {
+        // Opened afresh each time so a rotated file is picked up by name, and
+        // so the file isn't held open (and locked, on Windows) between polls.
+        if let Ok(mut file) = std::fs::File::open(path) {
+            let len = file.metadata().map_or(0, |m| m.len());
+            if len < pos {
+                tracing::debug!(path = %path.display(), "tailed file shrank; rereading");
+                pos = 0;
+                partial.clear();
+            }
+            if len > pos && file.seek(SeekFrom::Start(pos)).is_ok() {
+                buf.clear();
+                if let Ok(n) = file.take(len - pos).read_to_end(&mut buf) {
+                    pos += n as u64;
+                    partial.extend_from_slice(&buf);
+                    while let Some(end) = partial.iter().position(|&b| b == b'\n') {
+                        let line: Vec<u8> = partial.drain(..=end).collect();
+                        let line = line.strip_suffix(b"\n").unwrap_or(&line);
+                        let line = line.strip_suffix(b"\r").unwrap_or(line);
+                        emit(String::from_utf8_lossy(line).into_owned());
+                    }
+                }
+            }
+        }
+        std::thread::sleep(interval);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+    use std::io::Write;
+    use std::sync::mpsc;
+
+    #[test]
+    fn follows_appends_and_truncation() {
+        let dir = TempDir::new("tail");
+        let path = dir.write("build.log", "old line\n");
+        let stop = Arc::new(AtomicBool::new(false));
+        let (tx, rx) = mpsc::channel();
+        let thread = {
+            let (path, stop) = (path.clone(), stop.clone());
+            std::thread::spawn(move || {
+                follow(&path, true, &stop, Duration::from_millis(5), |line| {
+                    tx.send(line).unwrap()
+                })
+            })
+        };
+        let next = || rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        let append = |text: &str| {
+            let mut file = std::fs::OpenOptions::new()
+                .append(true)
+                .open(&path)
+                .unwrap();
+            file.write_all(text.as_bytes()).unwrap();
+        };
+
+        std::thread::sleep(Duration::from_millis(50));
+        append("first\r\nsecond ha");
+        assert_eq!(next(), "first");
+        append("lf done\n");
+        assert_eq!(next(), "second half done");
+
+        std::fs::write(&path, "after truncation\n").unwrap();
+        assert_eq!(next(), "after truncation");
+
+        stop.store(true, Ordering::SeqCst);
+        thread.join().unwrap();
+        assert!(rx.try_recv().is_err());
+    }
+}
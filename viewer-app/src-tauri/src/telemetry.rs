@@ -0,0 +1,79 @@
+// ── Command timing (how long each command took, for finding slow ones) ─────
+
+use std::convert::Infallible;
+use std::time::Instant;
+use tauri::Emitter;
+
+/// The event a timed command emits when it returns.
+pub const TIMING_EVENT: &str = "command-timing";
+
+/// Payload of a `command-timing` event.
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub struct CommandTiming {
+    pub name: &'static str,
+    /// Wall time of the command, to a microsecond.
+    pub duration_ms: f64,
+    /// Whether it returned Ok.
+    pub ok: bool,
+}
+
+/// Run a command's body, then emit a `command-timing` event with how long it
+/// took and whether it succeeded, and log the same at debug level. Every
+/// command that reads or writes files or runs a process is wrapped in this,
+/// except the terminal, tail and watch commands: write_terminal runs once per
+/// keystroke, and the rest hand off to threads whose time the call wouldn't
+/// show. Commands that only return state already in memory
+/// (get_app_version, get_launch_config, format_entry_markdown and the like)
+/// aren't timed either.
+pub fn timed<T, E>(
+    app: &tauri::AppHandle,
+    name: &'static str,
+    run: impl FnOnce() -> Result<T, E>,
+) -> Result<T, E> {
+    let (result, timing) = measure(name, run);
+    tracing::debug!(command = name, duration_ms = timing.duration_ms, ok = timing.ok, "command timing");
+    let _ = app.emit(TIMING_EVENT, timing);
+    result
+}
+
+/// timed for a command that can't fail, which is always reported `ok`.
+pub fn timed_value<T>(app: &tauri::AppHandle, name: &'static str, run: impl FnOnce() -> T) -> T {
+    match timed(app, name, || Ok::<_, Infallible>(run())) {
+        Ok(value) => value,
+        Err(never) => match never {},
+    }
+}
+
+fn measure<T, E>(name: &'static str, run: impl FnOnce() -> Result<T, E>) -> (Result<T, E>, CommandTiming) {
+    let start = Instant::now();
+    let result = run();
+    let duration_ms = (start.elapsed().as_micros() as f64) / 1000.0;
+    let ok = result.is_ok();
+    (result, CommandTiming { name, duration_ms, ok })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn measures_duration_and_outcome() {
+        let (result, timing) = measure("slow", || {
+            std::thread::sleep(Duration::from_millis(20));
+            Ok::<_, String>(7)
+        });
+        assert_eq!(result, Ok(7));
+        assert!(timing.ok);
+        assert!(timing.duration_ms >= 20.0, "{:?}", timing);
+
+        let (result, timing) = measure("failing", || Err::<(), _>("no"));
+        assert_eq!(result, Err("no"));
+        assert_eq!(timing.name, "failing");
+        assert!(!timing.ok);
+        assert_eq!(
+            serde_json::to_value(&timing).unwrap().as_object().unwrap().keys().collect::<Vec<_>>(),
+            ["duration_ms", "name", "ok"]
+        );
+    }
+}
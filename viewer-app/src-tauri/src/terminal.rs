@@ -0,0 +1,1317 @@
+// ── Terminal (spawn shell in a PTY and pipe I/O) ────────────────────────────
+
+mod output;
+mod wsl;
+
+use crate::error::CommandError;
+use crate::launch::LaunchConfig;
+use output::{OutputDecoder, Scrollback};
+use portable_pty::{native_pty_system, Child, ChildKiller, CommandBuilder, MasterPty, PtySize};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex, MutexGuard, PoisonError};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::Emitter;
+
+pub struct TerminalProcess {
+    writer: Box<dyn Write + Send>,
+    master: Box<dyn MasterPty + Send>,
+    // Cloned from the child so we can kill it while the wait thread owns it.
+    killer: Box<dyn ChildKiller + Send + Sync>,
+    pid: Option<u32>,
+    /// When the shell was spawned, in milliseconds since the Unix epoch.
+    started_at_ms: u64,
+    exited: Arc<AtomicBool>,
+    /// Set by terminal_attach; until then output only goes to the scrollback.
+    attached: Arc<AtomicBool>,
+    scrollback: Arc<Mutex<Scrollback>>,
+    /// Last input, output or ping_terminal, for `SpawnOptions.idle_close_secs`.
+    last_active: Arc<Mutex<Instant>>,
+    // Reader, emitter, wait and idle threads, joined on shutdown.
+    threads: Vec<JoinHandle<()>>,
+}
+
+/// Live terminal sessions keyed by id.
+pub type TerminalState = Arc<Mutex<HashMap<String, TerminalProcess>>>;
+
+/// Lock the session map even if a thread panicked while holding it. The map
+/// itself stays consistent (every update is a single insert or remove), so
+/// one panic shouldn't disable terminals until the app restarts.
+fn lock_sessions(state: &TerminalState) -> MutexGuard<'_, HashMap<String, TerminalProcess>> {
+    state.lock().unwrap_or_else(|poisoned| {
+        tracing::warn!("terminal state lock was poisoned by a panic; recovering");
+        state.clear_poison();
+        poisoned.into_inner()
+    })
+}
+
+#[derive(serde::Serialize)]
+pub struct SpawnedTerminal {
+    id: String,
+    message: String,
+    /// The shell's process id, if the platform reported one. None too when
+    /// the session was already running.
+    pid: Option<u32>,
+}
+
+/// Optional overrides for the shell spawn_terminal launches.
+#[derive(Default, serde::Deserialize)]
+#[serde(default)]
+pub struct SpawnOptions {
+    /// Program to run instead of the auto-detected shell (e.g. `zsh`, `pwsh`).
+    shell: Option<String>,
+    /// Directory to start in; must already exist. Windows and WSL forms
+    /// (`C:\clients\acme`, `/mnt/c/clients/acme`) are converted to whichever
+    /// the shell needs. For WSL, a path inside the distro (`/home/me`) is only
+    /// checked when `distro` is set; a network share is refused, having no
+    /// WSL path.
+    cwd: Option<String>,
+    args: Vec<String>,
+    /// How much output to keep for get_terminal_scrollback (default 256 KB).
+    scrollback_bytes: Option<usize>,
+    /// WSL distro to start instead of the default one (see list_wsl_distros).
+    /// Only valid when the shell is wsl.exe.
+    distro: Option<String>,
+    /// Encoding the shell writes its output in, e.g. `windows-1252` for
+    /// cmd.exe on a Western European install (default UTF-8). See
+    /// OutputDecoder::for_label for the names accepted.
+    encoding: Option<String>,
+    /// Variables to set in the shell's environment, e.g. `AWS_PROFILE`.
+    env: HashMap<String, String>,
+    /// Start from an environment holding only ESSENTIAL_ENV (and `env`)
+    /// instead of the viewer's own.
+    clear_env: bool,
+    /// Whether programs in the shell should color their output: true for
+    /// the live xterm, false for output that will be kept as plain text.
+    /// Unset means true unless the viewer itself runs with `NO_COLOR`. See
+    /// ColorMode; `env` can still override the variables it sets.
+    force_color: Option<bool>,
+    /// Kill the shell once this many seconds pass with no write_terminal, no
+    /// output and no ping_terminal (default never), so a forgotten tab
+    /// doesn't keep it running.
+    idle_close_secs: Option<u64>,
+}
+
+/// What a shell needs to start and find programs, kept even with
+/// `SpawnOptions.clear_env`.
+const ESSENTIAL_ENV: [&str; 9] =
+    ["PATH", "HOME", "USER", "LOGNAME", "LANG", "SystemRoot", "windir", "ComSpec", "USERPROFILE"];
+
+/// Largest script write_terminal_file will send. The whole script is written
+/// with the session map locked, so it has to stay small enough for the shell
+/// to take in promptly.
+const MAX_SCRIPT_BYTES: u64 = 1024 * 1024;
+
+/// How often an idle timeout is checked. Short enough that shutdown_all
+/// finds the idle thread finished within its grace period.
+const IDLE_POLL: Duration = Duration::from_secs(1);
+
+/// How a shell is told whether to color its output, through the variables
+/// most programs look at.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ColorMode {
+    /// `TERM=xterm-256color` (what xterm.js speaks) and `FORCE_COLOR=1`.
+    Rich,
+    /// `TERM=dumb` and `NO_COLOR=1`.
+    Plain,
+}
+
+impl ColorMode {
+    fn new(force_color: Option<bool>) -> Self {
+        let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+        match force_color {
+            Some(true) => ColorMode::Rich,
+            Some(false) => ColorMode::Plain,
+            None if no_color => ColorMode::Plain,
+            None => ColorMode::Rich,
+        }
+    }
+
+    /// The variables to set, and the one to remove so an inherited value
+    /// can't contradict them.
+    fn env(self) -> ([(&'static str, &'static str); 2], &'static str) {
+        match self {
+            ColorMode::Rich => ([("TERM", "xterm-256color"), ("FORCE_COLOR", "1")], "NO_COLOR"),
+            ColorMode::Plain => ([("TERM", "dumb"), ("NO_COLOR", "1")], "FORCE_COLOR"),
+        }
+    }
+}
+
+/// Payload of the `terminal-exit` event. `code` is set when the process
+/// exited normally, `signal` (the signal's name, as the PTY layer reports it)
+/// when it was killed. Both are None if waiting on the process failed.
+/// `idle` is set when it was killed for `SpawnOptions.idle_close_secs`.
+#[derive(Clone, serde::Serialize)]
+struct TerminalExit {
+    id: String,
+    code: Option<i32>,
+    signal: Option<String>,
+    idle: bool,
+}
+
+impl TerminalExit {
+    fn new(id: String, status: Option<&portable_pty::ExitStatus>, idle: bool) -> Self {
+        let signal = status.and_then(|s| s.signal()).map(str::to_string);
+        let code = match status {
+            Some(s) if signal.is_none() => Some(s.exit_code() as i32),
+            _ => None,
+        };
+        TerminalExit { id, code, signal, idle }
+    }
+}
+
+fn touch(last_active: &Mutex<Instant>) {
+    *last_active.lock().unwrap_or_else(PoisonError::into_inner) = Instant::now();
+}
+
+/// Wait until the shell exits (false) or `timeout` passes since
+/// `last_active` (true), checking every `poll`.
+fn wait_idle(exited: &AtomicBool, last_active: &Mutex<Instant>, timeout: Duration, poll: Duration) -> bool {
+    loop {
+        if exited.load(Ordering::SeqCst) {
+            return false;
+        }
+        let idle = last_active.lock().unwrap_or_else(PoisonError::into_inner).elapsed();
+        if idle >= timeout {
+            return true;
+        }
+        std::thread::sleep(poll.min(timeout - idle));
+    }
+}
+
+static NEXT_TERMINAL_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Session ids end up in event names, which Tauri restricts to
+/// alphanumerics, `-`, `/`, `:` and `_`.
+fn validate_id(id: &str) -> Result<(), CommandError> {
+    let valid = !id.is_empty()
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '/' | ':' | '_'));
+    if valid {
+        Ok(())
+    } else {
+        Err(CommandError::InvalidInput(format!("Invalid terminal id: {:?}", id)))
+    }
+}
+
+fn no_session(id: &str) -> CommandError {
+    CommandError::NotFound(format!("No terminal process running for {}", id))
+}
+
+/// First `max` chars of `s` for log lines; slicing by bytes would panic
+/// mid-character.
+fn preview(s: &str, max: usize) -> String {
+    s.chars().take(max).collect()
+}
+
+fn output_event(id: &str) -> String {
+    format!("terminal-output-{}", id)
+}
+
+/// A shell running on the slave side of a fresh PTY.
+struct PtyShell {
+    master: Box<dyn MasterPty + Send>,
+    child: Box<dyn Child + Send + Sync>,
+    reader: Box<dyn Read + Send>,
+    writer: Box<dyn Write + Send>,
+}
+
+/// Spawn `program` inside a new pseudo-terminal of the given size, with
+/// `env` added to the viewer's environment (or, with `clear_env`, to just
+/// ESSENTIAL_ENV). On Windows the native PTY system is ConPTY, so WSL sees a
+/// real TTY.
+fn spawn_pty(
+    program: &str,
+    args: &[String],
+    cwd: Option<&Path>,
+    size: PtySize,
+    env: &HashMap<String, String>,
+    clear_env: bool,
+    color: ColorMode,
+) -> Result<PtyShell, CommandError> {
+    if let Some(key) = env.keys().find(|key| key.is_empty() || key.contains(['=', '\0'])) {
+        return Err(CommandError::InvalidInput(format!("Invalid environment variable name: {:?}", key)));
+    }
+
+    let pair = native_pty_system()
+        .openpty(size)
+        .map_err(|e| CommandError::Io(format!("Failed to open PTY: {}", e)))?;
+
+    let mut cmd = CommandBuilder::new(program);
+    cmd.args(args);
+    if let Some(cwd) = cwd {
+        cmd.cwd(cwd);
+    }
+    if clear_env {
+        cmd.env_clear();
+        for key in ESSENTIAL_ENV {
+            if let Some(value) = std::env::var_os(key) {
+                cmd.env(key, value);
+            }
+        }
+    }
+    let (color_env, contradicting) = color.env();
+    cmd.env_remove(contradicting);
+    for (key, value) in color_env {
+        cmd.env(key, value);
+    }
+    for (key, value) in env {
+        cmd.env(key, value);
+    }
+
+    let child = pair
+        .slave
+        .spawn_command(cmd)
+        .map_err(|e| CommandError::Io(format!("Failed to spawn {}: {}", program, e)))?;
+    // Drop our copy of the slave so the reader sees EOF when the child exits.
+    drop(pair.slave);
+
+    let reader = pair
+        .master
+        .try_clone_reader()
+        .map_err(|e| CommandError::Io(format!("Failed to get PTY reader: {}", e)))?;
+    let writer = pair
+        .master
+        .take_writer()
+        .map_err(|e| CommandError::Io(format!("Failed to get PTY writer: {}", e)))?;
+
+    Ok(PtyShell { master: pair.master, child, reader, writer })
+}
+
+/// Decode PTY output and send it on until the PTY closes.
+fn read_output(mut reader: Box<dyn Read + Send>, mut decoder: OutputDecoder, tx: mpsc::SyncSender<String>) {
+    tracing::debug!("reader thread started");
+    let mut buf = [0u8; 4096];
+    loop {
+        match reader.read(&mut buf) {
+            Ok(0) => {
+                tracing::debug!("PTY EOF");
+                break;
+            }
+            Ok(n) => {
+                let text = decoder.decode(&buf[..n]);
+                if text.is_empty() {
+                    continue;
+                }
+                tracing::trace!(bytes = n, "output {:?}", preview(&text, 100));
+                if tx.send(text).is_err() {
+                    break;
+                }
+            }
+            Err(e) => {
+                // Linux reports EIO once the slave side has closed.
+                tracing::debug!("PTY read ended: {}", e);
+                break;
+            }
+        }
+    }
+    let rest = decoder.finish();
+    if !rest.is_empty() {
+        let _ = tx.send(rest);
+    }
+}
+
+/// WSL if it's installed, else cmd.exe, on Windows; bash everywhere else.
+fn default_shell() -> String {
+    if cfg!(target_os = "windows") {
+        let wsl_path = "C:\\Windows\\System32\\wsl.exe";
+        let wsl_exists = Path::new(wsl_path).exists();
+        tracing::debug!(wsl_exists, "choosing the default Windows shell");
+
+        if wsl_exists {
+            "wsl.exe".to_string()
+        } else {
+            "cmd.exe".to_string()
+        }
+    } else {
+        "bash".to_string()
+    }
+}
+
+fn resolve_cwd(cwd: &str) -> Result<PathBuf, CommandError> {
+    let path = PathBuf::from(cwd);
+    if !path.exists() {
+        return Err(CommandError::NotFound(format!("Working directory does not exist: {}", cwd)));
+    }
+    if !path.is_dir() {
+        return Err(CommandError::InvalidInput(format!("Working directory is not a directory: {}", cwd)));
+    }
+    Ok(path)
+}
+
+/// The installed WSL distros, from `wsl.exe -l -v`. Empty when WSL isn't
+/// installed, including everywhere but Windows.
+#[tauri::command(async)]
+pub fn list_wsl_distros() -> Vec<wsl::WslDistro> {
+    if !cfg!(target_os = "windows") {
+        return Vec::new();
+    }
+    let output = Command::new("wsl.exe")
+        .args(["-l", "-v"])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output();
+    match output {
+        Ok(output) if output.status.success() => wsl::parse_list(&wsl::decode_output(&output.stdout)),
+        Ok(output) => {
+            tracing::debug!(status = ?output.status, "wsl.exe -l -v failed; no distros");
+            Vec::new()
+        }
+        Err(e) => {
+            tracing::debug!("wsl.exe not available: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// A shell spawn_terminal has checked and will start: the program, its
+/// arguments and starting directory as they'll be passed, and the options
+/// still to apply.
+struct SpawnPlan {
+    id: String,
+    program: String,
+    args: Vec<String>,
+    cwd: Option<PathBuf>,
+    decoder: OutputDecoder,
+    options: SpawnOptions,
+}
+
+/// What plan_spawn decided: report the session already running, or start one.
+enum Spawn {
+    Running(SpawnedTerminal),
+    Start(Box<SpawnPlan>),
+}
+
+/// Everything spawn_terminal checks before starting anything: that the
+/// viewer is writable, the id, and the options. A session already running
+/// under the id is reported rather than started twice.
+fn plan_spawn(
+    launch: &LaunchConfig,
+    sessions: &HashMap<String, TerminalProcess>,
+    id: Option<String>,
+    options: Option<SpawnOptions>,
+) -> Result<Spawn, CommandError> {
+    launch.ensure_writable("spawn_terminal")?;
+    let mut options = options.unwrap_or_default();
+    let id = match id {
+        Some(id) => id,
+        None => format!("term-{}", NEXT_TERMINAL_ID.fetch_add(1, Ordering::SeqCst)),
+    };
+    validate_id(&id)?;
+
+    if sessions.contains_key(&id) {
+        return Ok(Spawn::Running(SpawnedTerminal { id, message: "already running".to_string(), pid: None }));
+    }
+
+    let program = options.shell.take().unwrap_or_else(default_shell);
+    let is_wsl = Path::new(&program)
+        .file_stem()
+        .is_some_and(|stem| stem.eq_ignore_ascii_case("wsl"));
+    let mut args = std::mem::take(&mut options.args);
+    let distro = options.distro.take();
+    if let Some(distro) = &distro {
+        if !is_wsl {
+            return Err(CommandError::InvalidInput(format!("A distro can only be chosen for WSL, not {}", program)));
+        }
+        if distro.is_empty() || distro.starts_with('-') {
+            return Err(CommandError::InvalidInput(format!("Invalid WSL distro name: {:?}", distro)));
+        }
+        args.splice(0..0, ["-d".to_string(), distro.clone()]);
+    }
+    let mut cwd = None;
+    match options.cwd.as_deref() {
+        // wsl.exe gets the directory as `--cd` in WSL form, since a path
+        // inside the distro can't be a Windows process's working directory.
+        // A WSL path is checked through its Windows form, which a path
+        // inside the distro only has when the distro is named; otherwise
+        // it's left to wsl.exe to refuse.
+        Some(dir) if is_wsl => {
+            if !dir.starts_with('/') {
+                resolve_cwd(dir)?;
+            } else if cfg!(windows) {
+                if let Some(windows_dir) = wsl::from_wsl_path(dir, distro.as_deref()) {
+                    resolve_cwd(&windows_dir)?;
+                }
+            }
+            let wsl_dir = wsl::to_wsl_path(dir).ok_or_else(|| {
+                CommandError::InvalidInput(format!("WSL can't start in {}; it has no WSL path", dir))
+            })?;
+            args.splice(0..0, ["--cd".to_string(), wsl_dir]);
+        }
+        // And a Windows shell given `/mnt/c/...` gets `C:\...`.
+        Some(dir) if cfg!(windows) => {
+            let dir = wsl::from_wsl_path(dir, None).unwrap_or_else(|| dir.to_string());
+            cwd = Some(resolve_cwd(&dir)?);
+        }
+        Some(dir) => cwd = Some(resolve_cwd(dir)?),
+        None => {}
+    }
+    let decoder = match options.encoding.as_deref() {
+        Some(label) => OutputDecoder::for_label(label)?,
+        None => OutputDecoder::default(),
+    };
+    if options.idle_close_secs == Some(0) {
+        return Err(CommandError::InvalidInput("idle_close_secs must be at least 1".to_string()));
+    }
+
+    Ok(Spawn::Start(Box::new(SpawnPlan { id, program, args, cwd, decoder, options })))
+}
+
+/// Start a shell for session `id`, or a generated id if none is given.
+///
+/// Output is emitted as `terminal-output-{id}`, but only once the frontend
+/// calls terminal_attach: register the listener, wait for `listen` to
+/// resolve, then attach. Everything the shell printed before that (its
+/// banner and first prompt) is kept in the scrollback and emitted first, so
+/// it isn't lost to a listener that wasn't registered yet.
+#[tauri::command]
+pub fn spawn_terminal(
+    state: tauri::State<'_, TerminalState>,
+    launch: tauri::State<'_, LaunchConfig>,
+    app: tauri::AppHandle,
+    id: Option<String>,
+    options: Option<SpawnOptions>,
+) -> Result<SpawnedTerminal, CommandError> {
+    let mut guard = lock_sessions(&state);
+    let plan = match plan_spawn(&launch, &guard, id, options)? {
+        Spawn::Running(running) => return Ok(running),
+        Spawn::Start(plan) => plan,
+    };
+    let SpawnPlan { id, program, args, cwd, decoder, options } = *plan;
+
+    let span = tracing::info_span!("terminal", id = %id);
+    let _entered = span.enter();
+    tracing::info!(%program, ?args, ?cwd, "spawning");
+
+    let size = PtySize::default();
+    let color = ColorMode::new(options.force_color);
+    let shell = spawn_pty(&program, &args, cwd.as_deref(), size, &options.env, options.clear_env, color)
+        .map_err(|e| {
+            tracing::error!("{}", e);
+            e
+        })?;
+    let PtyShell { master, mut child, reader, writer } = shell;
+
+    let pid = child.process_id();
+    let started_at_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64);
+    tracing::info!(?pid, "spawned");
+
+    let exited = Arc::new(AtomicBool::new(false));
+    let attached = Arc::new(AtomicBool::new(false));
+    let scrollback = Arc::new(Mutex::new(Scrollback::new(
+        options.scrollback_bytes.unwrap_or(Scrollback::DEFAULT_CAPACITY),
+    )));
+    let killer = child.clone_killer();
+    let idle_close = options.idle_close_secs.map(|secs| (Duration::from_secs(secs), child.clone_killer()));
+    let last_active = Arc::new(Mutex::new(Instant::now()));
+
+    // Read PTY output (stdout and stderr share it) on one thread and emit it
+    // to the frontend, coalesced, on another.
+    let (tx, rx) = mpsc::sync_channel(output::OUTPUT_QUEUE);
+    let reader_span = span.clone();
+    let reader_thread = std::thread::spawn(move || reader_span.in_scope(|| read_output(reader, decoder, tx)));
+
+    let app_out = app.clone();
+    let event = output_event(&id);
+    let emitter_span = span.clone();
+    let emitter_scrollback = scrollback.clone();
+    let emitter_attached = attached.clone();
+    let emitter_active = last_active.clone();
+    let emitter_thread = std::thread::spawn(move || {
+        let _entered = emitter_span.enter();
+        // The scrollback lock is held while emitting so terminal_attach's
+        // flush can't interleave with (or repeat) live output.
+        let emit = |text: &str| {
+            let mut scrollback = emitter_scrollback.lock().unwrap_or_else(PoisonError::into_inner);
+            scrollback.push(text);
+            if emitter_attached.load(Ordering::SeqCst) {
+                app_out.emit(&event, text)
+            } else {
+                Ok(())
+            }
+        };
+        output::coalesce(rx, output::BATCH_WINDOW, output::BATCH_MAX_BYTES, |batch| {
+            touch(&emitter_active);
+            let result = emit(&batch);
+            tracing::trace!(bytes = batch.len(), ?result, "emit");
+        });
+        let _ = emit("\r\n[Process exited]\r\n");
+    });
+
+    // Wait for child to exit in background and report how it went
+    let app_exit = app.clone();
+    let exit_id = id.clone();
+    let wait_span = span.clone();
+    let wait_exited = exited.clone();
+    let idle_closed = Arc::new(AtomicBool::new(false));
+    let wait_idle_closed = idle_closed.clone();
+    let wait_thread = std::thread::spawn(move || {
+        let _entered = wait_span.enter();
+        let status = match child.wait() {
+            Ok(status) => {
+                tracing::info!(?status, "process exited");
+                Some(status)
+            }
+            Err(e) => {
+                tracing::warn!("wait failed: {}", e);
+                None
+            }
+        };
+        wait_exited.store(true, Ordering::SeqCst);
+        let idle = wait_idle_closed.load(Ordering::SeqCst);
+        let _ = app_exit.emit("terminal-exit", TerminalExit::new(exit_id, status.as_ref(), idle));
+    });
+    let mut threads = vec![reader_thread, emitter_thread, wait_thread];
+
+    if let Some((timeout, mut idle_killer)) = idle_close {
+        let (idle_span, idle_exited, idle_active) = (span.clone(), exited.clone(), last_active.clone());
+        threads.push(std::thread::spawn(move || {
+            let _entered = idle_span.enter();
+            if wait_idle(&idle_exited, &idle_active, timeout, IDLE_POLL) {
+                tracing::info!(?timeout, "idle; closing");
+                idle_closed.store(true, Ordering::SeqCst);
+                if let Err(e) = kill_tree(pid, idle_killer.as_mut()) {
+                    tracing::warn!("failed to kill idle shell: {}", e);
+                }
+            }
+        }));
+    }
+
+    guard.insert(
+        id.clone(),
+        TerminalProcess {
+            writer,
+            master,
+            killer,
+            pid,
+            started_at_ms,
+            exited,
+            attached,
+            scrollback,
+            last_active,
+            threads,
+        },
+    );
+
+    let message = match pid {
+        Some(pid) => format!("spawned {} (pid {})", program, pid),
+        None => format!("spawned {}", program),
+    };
+    Ok(SpawnedTerminal { id, message, pid })
+}
+
+#[tauri::command]
+pub fn write_terminal(
+    state: tauri::State<'_, TerminalState>,
+    launch: tauri::State<'_, LaunchConfig>,
+    id: String,
+    data: String,
+) -> Result<(), CommandError> {
+    write_input(&launch, &state, &id, &data)
+}
+
+fn write_input(launch: &LaunchConfig, state: &TerminalState, id: &str, data: &str) -> Result<(), CommandError> {
+    launch.ensure_writable("write_terminal")?;
+    tracing::trace!(%id, "write_terminal {:?}", preview(data, 50));
+    write_session(&mut lock_sessions(state), id, data)
+}
+
+/// Run a script in session `id`: the file at `path` (relative to the repo at
+/// `repo_path`, and not allowed outside it) is written to the shell as if
+/// typed, with a newline added if it doesn't end in one so the last line
+/// runs too. Scripts over 1 MiB are refused.
+#[tauri::command]
+pub fn write_terminal_file(
+    state: tauri::State<'_, TerminalState>,
+    launch: tauri::State<'_, LaunchConfig>,
+    id: String,
+    repo_path: String,
+    path: String,
+) -> Result<(), CommandError> {
+    write_script(&launch, &state, &id, Path::new(&repo_path), &path)
+}
+
+fn write_script(
+    launch: &LaunchConfig,
+    state: &TerminalState,
+    id: &str,
+    repo: &Path,
+    path: &str,
+) -> Result<(), CommandError> {
+    launch.ensure_writable("write_terminal_file")?;
+    let script = read_script(repo, path)?;
+    tracing::debug!(%id, %path, bytes = script.len(), "write_terminal_file");
+    write_session(&mut lock_sessions(state), id, &script)
+}
+
+fn read_script(repo: &Path, relative: &str) -> Result<String, CommandError> {
+    let path = crate::paths::resolve_within(repo, relative)?;
+    let mut script = crate::config::read_capped(&path, relative, MAX_SCRIPT_BYTES)?;
+    if !script.ends_with('\n') {
+        script.push('\n');
+    }
+    Ok(script)
+}
+
+/// Send `data` to session `id`'s shell. If the shell has exited (noticed by
+/// the wait thread, or as a broken pipe on write) the session is dropped and
+/// TerminalNotRunning returned, rather than leaving a dead session behind.
+fn write_session(
+    sessions: &mut HashMap<String, TerminalProcess>,
+    id: &str,
+    data: &str,
+) -> Result<(), CommandError> {
+    let proc = sessions.get_mut(id).ok_or_else(|| no_session(id))?;
+    touch(&proc.last_active);
+    let result = if proc.exited.load(Ordering::SeqCst) {
+        Err(std::io::Error::from(std::io::ErrorKind::BrokenPipe))
+    } else {
+        proc.writer.write_all(data.as_bytes()).and_then(|()| proc.writer.flush())
+    };
+    match result {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe || proc.exited.load(Ordering::SeqCst) => {
+            sessions.remove(id);
+            tracing::info!(%id, "write_terminal: shell has exited; session removed");
+            Err(CommandError::TerminalNotRunning(format!("The shell in terminal {} has exited", id)))
+        }
+        Err(e) => Err(CommandError::io("Write failed", &e)),
+    }
+}
+
+/// Tell the PTY (and thus the shell) how big the xterm widget is.
+#[tauri::command]
+pub fn resize_terminal(
+    state: tauri::State<'_, TerminalState>,
+    id: String,
+    cols: u16,
+    rows: u16,
+) -> Result<(), CommandError> {
+    let guard = lock_sessions(&state);
+    let proc = guard
+        .get(&id)
+        .ok_or_else(|| no_session(&id))?;
+    proc.master
+        .resize(PtySize { rows, cols, pixel_width: 0, pixel_height: 0 })
+        .map_err(|e| CommandError::Io(format!("Resize failed: {}", e)))
+}
+
+/// Kill the session's shell. wsl.exe and cmd.exe launch their own children;
+/// killing the launcher alone leaves them running, so on Windows take down
+/// the whole tree first.
+fn kill_process_tree(proc: &mut TerminalProcess) -> std::io::Result<()> {
+    kill_tree(proc.pid, proc.killer.as_mut())
+}
+
+fn kill_tree(pid: Option<u32>, killer: &mut (dyn ChildKiller + Send + Sync)) -> std::io::Result<()> {
+    if cfg!(target_os = "windows") {
+        if let Some(pid) = pid {
+            let _ = Command::new("taskkill")
+                .args(["/PID", &pid.to_string(), "/T", "/F"])
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status();
+        }
+    }
+    killer.kill()
+}
+
+/// Count as activity on session `id`, putting off its idle close (see
+/// `SpawnOptions.idle_close_secs`) for a tab that's open but quiet.
+#[tauri::command]
+pub fn ping_terminal(state: tauri::State<'_, TerminalState>, id: String) -> Result<(), CommandError> {
+    let guard = lock_sessions(&state);
+    let proc = guard
+        .get(&id)
+        .ok_or_else(|| no_session(&id))?;
+    touch(&proc.last_active);
+    Ok(())
+}
+
+/// Kill session `id`'s shell and forget the session so the id can be reused.
+/// The reader thread is detached; it hits EOF and exits once the child is
+/// gone. Succeeds if the process has already exited or the id is unknown.
+#[tauri::command]
+pub fn close_terminal(state: tauri::State<'_, TerminalState>, id: String) -> Result<(), CommandError> {
+    let mut guard = lock_sessions(&state);
+    let Some(mut proc) = guard.remove(&id) else {
+        return Ok(());
+    };
+
+    if proc.exited.load(Ordering::SeqCst) {
+        tracing::debug!(%id, "close_terminal: process already exited");
+        return Ok(());
+    }
+
+    if let Err(e) = kill_process_tree(&mut proc) {
+        // The process may have exited between the check above and the kill.
+        if !proc.exited.load(Ordering::SeqCst) {
+            return Err(CommandError::io("Failed to kill terminal", &e));
+        }
+    }
+    tracing::info!(%id, pid = ?proc.pid, "close_terminal: killed");
+    Ok(())
+}
+
+/// How long `shutdown_all` waits for killed sessions' threads to finish.
+pub const SHUTDOWN_GRACE: Duration = Duration::from_secs(2);
+
+/// Kill every session's process tree and wait up to `grace` for the reader,
+/// emitter and wait threads to wind down, so no shell (or `wsl.exe` on
+/// Windows) outlives the app. Called when the app exits; threads still
+/// running at the deadline are left detached and logged. Returns how many
+/// sessions were still running and have been killed.
+pub fn shutdown_all(state: &TerminalState, grace: Duration) -> usize {
+    let sessions: Vec<(String, TerminalProcess)> = lock_sessions(state).drain().collect();
+
+    let mut killed = 0;
+    let mut threads = Vec::new();
+    for (id, mut proc) in sessions {
+        if !proc.exited.load(Ordering::SeqCst) {
+            match kill_process_tree(&mut proc) {
+                Ok(()) => {
+                    killed += 1;
+                    tracing::info!(%id, pid = ?proc.pid, "shutdown: killed")
+                }
+                Err(e) if !proc.exited.load(Ordering::SeqCst) => {
+                    tracing::warn!(%id, "shutdown: failed to kill: {}", e)
+                }
+                Err(_) => {}
+            }
+        }
+        // Dropping the PTY ends the reader on platforms (ConPTY) where the
+        // child exiting alone doesn't.
+        let TerminalProcess { threads: session_threads, .. } = proc;
+        threads.extend(session_threads.into_iter().map(|t| (id.clone(), t)));
+    }
+
+    let deadline = Instant::now() + grace;
+    while threads.iter().any(|(_, t)| !t.is_finished()) && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    for (id, thread) in threads {
+        if thread.is_finished() {
+            let _ = thread.join();
+        } else {
+            tracing::warn!(%id, "shutdown: thread still running after {:?}", grace);
+        }
+    }
+    killed
+}
+
+/// Kill every session, as on app exit, for a "close all terminals" action.
+/// Returns how many shells were still running. Async so the wait for the
+/// sessions' threads doesn't block the main thread.
+#[tauri::command(async)]
+pub fn kill_all_terminals(state: tauri::State<'_, TerminalState>) -> usize {
+    shutdown_all(&state, SHUTDOWN_GRACE)
+}
+
+/// Deliver `signal` (e.g. `"SIGINT"`, `"SIGTERM"`) to session `id`.
+///
+/// On Unix the signal goes to the PTY's foreground process group, so SIGINT
+/// interrupts the running program (`sleep 100`) the way Ctrl+C would rather
+/// than the shell that launched it. ConPTY has no process groups to target,
+/// so on Windows SIGINT is sent as a Ctrl+C keystroke through the console and
+/// SIGTERM kills the session's process tree.
+#[tauri::command]
+pub fn signal_terminal(
+    state: tauri::State<'_, TerminalState>,
+    id: String,
+    signal: String,
+) -> Result<(), CommandError> {
+    let mut guard = lock_sessions(&state);
+    let proc = guard
+        .get_mut(&id)
+        .ok_or_else(|| no_session(&id))?;
+    tracing::info!(%id, %signal, "signal_terminal");
+    send_signal(proc, &signal)
+}
+
+#[cfg(unix)]
+fn send_signal(proc: &mut TerminalProcess, signal: &str) -> Result<(), CommandError> {
+    use nix::sys::signal::{kill, killpg, Signal};
+    use nix::unistd::Pid;
+
+    let sig: Signal = signal
+        .parse()
+        .map_err(|_| CommandError::InvalidInput(format!("Unsupported signal: {}", signal)))?;
+    let failed = |e: nix::Error| CommandError::Io(format!("Failed to send {}: {}", signal, e));
+    if let Some(pgrp) = proc.master.process_group_leader() {
+        return killpg(Pid::from_raw(pgrp), sig).map_err(failed);
+    }
+    let pid = proc
+        .pid
+        .ok_or_else(|| CommandError::Internal("Terminal process id unknown".to_string()))?;
+    kill(Pid::from_raw(pid as i32), sig).map_err(failed)
+}
+
+#[cfg(windows)]
+fn send_signal(proc: &mut TerminalProcess, signal: &str) -> Result<(), CommandError> {
+    match signal {
+        "SIGINT" => {
+            proc.writer.write_all(b"\x03").map_err(|e| CommandError::io("Write failed", &e))?;
+            proc.writer.flush().map_err(|e| CommandError::io("Flush failed", &e))
+        }
+        "SIGTERM" => kill_process_tree(proc).map_err(|e| CommandError::io("Failed to kill terminal", &e)),
+        _ => Err(CommandError::InvalidInput(format!("Unsupported signal: {}", signal))),
+    }
+}
+
+/// Start streaming session `id`'s output: emit what's been buffered since the
+/// spawn as one `terminal-output-{id}` event, then everything after it live.
+/// The buffer is the scrollback, so a shell that prints more than
+/// `SpawnOptions.scrollback_bytes` before anyone attaches loses the oldest
+/// part. Attaching again does nothing; a recreated tab should repaint from
+/// get_terminal_scrollback instead.
+#[tauri::command]
+pub fn terminal_attach(
+    state: tauri::State<'_, TerminalState>,
+    app: tauri::AppHandle,
+    id: String,
+) -> Result<(), CommandError> {
+    let guard = lock_sessions(&state);
+    let proc = guard
+        .get(&id)
+        .ok_or_else(|| no_session(&id))?;
+    let scrollback = proc.scrollback.lock().unwrap_or_else(PoisonError::into_inner);
+    if proc.attached.swap(true, Ordering::SeqCst) {
+        return Ok(());
+    }
+    let pending = scrollback.contents();
+    tracing::debug!(id = %id, bytes = pending.len(), "attached");
+    if pending.is_empty() {
+        return Ok(());
+    }
+    app.emit(&output_event(&id), pending)
+        .map_err(|e| CommandError::Internal(format!("Failed to emit terminal output: {}", e)))
+}
+
+/// Recent output of session `id` (bounded by `SpawnOptions.scrollback_bytes`)
+/// so a recreated tab can repaint its history.
+#[tauri::command]
+pub fn get_terminal_scrollback(
+    state: tauri::State<'_, TerminalState>,
+    id: String,
+) -> Result<String, CommandError> {
+    let guard = lock_sessions(&state);
+    let proc = guard
+        .get(&id)
+        .ok_or_else(|| no_session(&id))?;
+    let scrollback = proc.scrollback.lock().unwrap_or_else(PoisonError::into_inner);
+    Ok(scrollback.contents())
+}
+
+/// Session `id`'s scrollback as a transcript: as the terminal received it, or
+/// with `strip_ansi` as plain text without colors, cursor movement or title
+/// sequences.
+#[tauri::command]
+pub fn get_terminal_transcript(
+    state: tauri::State<'_, TerminalState>,
+    id: String,
+    strip_ansi: bool,
+) -> Result<String, CommandError> {
+    let raw = get_terminal_scrollback(state, id)?;
+    if !strip_ansi {
+        return Ok(raw);
+    }
+    Ok(String::from_utf8_lossy(&output::strip_ansi(raw.as_bytes())).into_owned())
+}
+
+/// Captured terminal output as HTML, styled from its SGR color and text
+/// codes, for showing in a report panel instead of a live terminal. See
+/// output::ansi_to_html.
+#[tauri::command]
+pub fn ansi_to_html(text: String) -> String {
+    output::ansi_to_html(text.as_bytes())
+}
+
+/// Whether a session's process is still alive, for spotting dead tabs.
+#[derive(Debug, serde::Serialize)]
+pub struct TerminalStatus {
+    running: bool,
+    pid: Option<u32>,
+    started_at_ms: u64,
+}
+
+/// Liveness, pid and start time of session `id`. A session whose shell has
+/// exited is still reported (as not running) until it's closed.
+#[tauri::command]
+pub fn terminal_status(
+    state: tauri::State<'_, TerminalState>,
+    id: String,
+) -> Result<TerminalStatus, CommandError> {
+    let guard = lock_sessions(&state);
+    let proc = guard
+        .get(&id)
+        .ok_or_else(|| no_session(&id))?;
+    Ok(TerminalStatus {
+        running: !proc.exited.load(Ordering::SeqCst),
+        pid: proc.pid,
+        started_at_ms: proc.started_at_ms,
+    })
+}
+
+/// Ids of the sessions currently held, sorted.
+#[tauri::command]
+pub fn list_terminals(state: tauri::State<'_, TerminalState>) -> Vec<String> {
+    let guard = lock_sessions(&state);
+    let mut ids: Vec<String> = guard.keys().cloned().collect();
+    ids.sort();
+    ids
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    /// Everything `reader` yields until the shell is gone. The PTY reader
+    /// errors (EIO) rather than returning 0 once the child is gone, so this
+    /// collects until either.
+    fn read_to_eof(mut reader: Box<dyn Read + Send>) -> String {
+        let mut output = Vec::new();
+        let mut buf = [0u8; 4096];
+        while let Ok(n) = reader.read(&mut buf) {
+            if n == 0 {
+                break;
+            }
+            output.extend_from_slice(&buf[..n]);
+        }
+        String::from_utf8_lossy(&output).into_owned()
+    }
+
+    /// spawn_pty at the default size with the viewer's own environment.
+    fn spawn_test_pty(program: &str, args: &[&str], color: ColorMode) -> PtyShell {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        spawn_pty(program, &args, None, PtySize::default(), &HashMap::new(), false, color).unwrap()
+    }
+
+    #[test]
+    fn pty_reports_requested_columns() {
+        let size = PtySize { rows: 24, cols: 120, pixel_width: 0, pixel_height: 0 };
+        let args = vec!["-c".to_string(), "tput cols".to_string()];
+        let PtyShell { master: _master, mut child, reader, writer: _writer } =
+            spawn_pty("bash", &args, None, size, &HashMap::new(), false, ColorMode::Rich).unwrap();
+
+        let output = read_to_eof(reader);
+        child.wait().unwrap();
+
+        assert_eq!(output.trim(), "120");
+    }
+
+    #[test]
+    fn custom_environment_reaches_the_shell() {
+        let run = |clear_env: bool| -> String {
+            let env = HashMap::from([("SL_OT_PROFILE".to_string(), "client-a".to_string())]);
+            let args = vec!["-c".to_string(), "env".to_string()];
+            let size = PtySize::default();
+            let PtyShell { master: _master, mut child, reader, writer: _writer } =
+                spawn_pty("sh", &args, None, size, &env, clear_env, ColorMode::Rich).unwrap();
+            let output = read_to_eof(reader);
+            child.wait().unwrap();
+            output.replace('\r', "")
+        };
+        let has = |output: &str, var: &str| output.lines().any(|line| line.starts_with(&format!("{}=", var)));
+
+        // Cargo sets CARGO_MANIFEST_DIR for test runs, so it's inherited.
+        let inherited = run(false);
+        assert!(inherited.contains("SL_OT_PROFILE=client-a\n"), "{}", inherited);
+        assert!(has(&inherited, "PATH") && has(&inherited, "CARGO_MANIFEST_DIR"));
+
+        let clean = run(true);
+        assert!(clean.contains("SL_OT_PROFILE=client-a\n"), "{}", clean);
+        assert!(has(&clean, "PATH") && has(&clean, "TERM"));
+        assert!(!has(&clean, "CARGO_MANIFEST_DIR"), "{}", clean);
+
+        let bad = HashMap::from([("A=B".to_string(), String::new())]);
+        assert!(spawn_pty("sh", &[], None, PtySize::default(), &bad, false, ColorMode::Rich).is_err());
+    }
+
+    #[test]
+    fn color_mode_reaches_the_shell() {
+        let run = |color: ColorMode| -> String {
+            let PtyShell { master: _master, mut child, reader, writer: _writer } =
+                spawn_test_pty("sh", &["-c", "env"], color);
+            let output = read_to_eof(reader);
+            child.wait().unwrap();
+            output.replace('\r', "")
+        };
+        let var = |output: &str, name: &str| -> Option<String> {
+            output.lines().find_map(|line| line.strip_prefix(&format!("{}=", name)).map(str::to_string))
+        };
+
+        let rich = run(ColorMode::Rich);
+        assert_eq!(var(&rich, "TERM").as_deref(), Some("xterm-256color"));
+        assert_eq!(var(&rich, "FORCE_COLOR").as_deref(), Some("1"));
+        assert_eq!(var(&rich, "NO_COLOR"), None);
+
+        let plain = run(ColorMode::Plain);
+        assert_eq!(var(&plain, "TERM").as_deref(), Some("dumb"));
+        assert_eq!(var(&plain, "NO_COLOR").as_deref(), Some("1"));
+        assert_eq!(var(&plain, "FORCE_COLOR"), None);
+
+        assert_eq!(ColorMode::new(Some(false)), ColorMode::Plain);
+        assert_eq!(ColorMode::new(Some(true)), ColorMode::Rich);
+    }
+
+    #[test]
+    fn poisoned_state_lock_is_recovered() {
+        let state = TerminalState::default();
+        let poisoner = state.clone();
+        let _ = std::thread::spawn(move || {
+            let _guard = poisoner.lock().unwrap();
+            panic!("poison the terminal state");
+        })
+        .join();
+        assert!(state.is_poisoned());
+
+        assert!(lock_sessions(&state).is_empty());
+        assert!(!state.is_poisoned());
+    }
+
+    type ChildAndReader = (Box<dyn Child + Send + Sync>, Box<dyn Read + Send>);
+
+    /// A session around `shell` with no threads, for the test to drive
+    /// through the child and reader handed back.
+    fn session(shell: PtyShell) -> (TerminalProcess, ChildAndReader) {
+        let PtyShell { master, child, reader, writer } = shell;
+        let proc = TerminalProcess {
+            writer,
+            master,
+            killer: child.clone_killer(),
+            pid: child.process_id(),
+            started_at_ms: 0,
+            exited: Arc::new(AtomicBool::new(false)),
+            attached: Arc::new(AtomicBool::new(false)),
+            scrollback: Arc::new(Mutex::new(Scrollback::new(1024))),
+            last_active: Arc::new(Mutex::new(Instant::now())),
+            threads: Vec::new(),
+        };
+        (proc, (child, reader))
+    }
+
+    /// A session running `script` under bash, with a reader draining its
+    /// output and a thread flagging when it exits, as spawn_terminal sets up.
+    fn shell_session(script: &str) -> (TerminalProcess, Arc<AtomicBool>) {
+        let shell = spawn_test_pty("bash", &["-c", script], ColorMode::Rich);
+        let (mut proc, (mut child, reader)) = session(shell);
+        let exited = proc.exited.clone();
+        let wait_exited = exited.clone();
+        proc.threads = vec![
+            std::thread::spawn(move || drop(read_to_eof(reader))),
+            std::thread::spawn(move || {
+                let _ = child.wait();
+                wait_exited.store(true, Ordering::SeqCst);
+            }),
+        ];
+        (proc, exited)
+    }
+
+    #[test]
+    fn shutdown_kills_shells_and_joins_threads() {
+        let (proc, exited) = shell_session("sleep 100");
+        let pid = proc.pid;
+        let state = TerminalState::default();
+        state.lock().unwrap().insert("t1".into(), proc);
+
+        assert_eq!(shutdown_all(&state, Duration::from_secs(5)), 1);
+
+        assert!(state.lock().unwrap().is_empty());
+        assert!(exited.load(Ordering::SeqCst));
+        // The shell has been reaped, so its pid no longer names a process.
+        let pid = nix::unistd::Pid::from_raw(pid.unwrap() as i32);
+        assert!(nix::sys::signal::kill(pid, None).is_err());
+    }
+
+    #[test]
+    fn shutdown_counts_only_live_sessions() {
+        let state = TerminalState::default();
+        let mut pids = Vec::new();
+        let mut flags = Vec::new();
+        for i in 0..3 {
+            let (proc, exited) = shell_session("sleep 100");
+            pids.push(proc.pid.unwrap());
+            flags.push(exited);
+            state.lock().unwrap().insert(format!("t{}", i), proc);
+        }
+        let (done, done_exited) = shell_session("exit 0");
+        while !done_exited.load(Ordering::SeqCst) {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        state.lock().unwrap().insert("done".into(), done);
+
+        assert_eq!(shutdown_all(&state, Duration::from_secs(5)), 3);
+
+        assert!(state.lock().unwrap().is_empty());
+        assert!(flags.iter().all(|exited| exited.load(Ordering::SeqCst)));
+        for pid in pids {
+            let pid = nix::unistd::Pid::from_raw(pid as i32);
+            assert!(nix::sys::signal::kill(pid, None).is_err());
+        }
+    }
+
+    #[test]
+    fn writing_to_an_exited_shell_removes_the_session() {
+        let shell = spawn_test_pty("sh", &["-c", "exit 0"], ColorMode::Rich);
+        let (proc, (mut child, _reader)) = session(shell);
+        child.wait().unwrap();
+        proc.exited.store(true, Ordering::SeqCst);
+        let mut sessions = HashMap::from([("t1".to_string(), proc)]);
+
+        let err = write_session(&mut sessions, "t1", "echo hi\n").unwrap_err();
+        assert!(matches!(err, CommandError::TerminalNotRunning(_)), "{:?}", err);
+        assert!(sessions.is_empty());
+        let err = write_session(&mut sessions, "t1", "echo hi\n").unwrap_err();
+        assert!(matches!(err, CommandError::NotFound(_)), "{:?}", err);
+    }
+
+    #[test]
+    fn script_file_reaches_the_shells_stdin() {
+        let repo = TempDir::new("term-script");
+        std::fs::write(repo.path().join("run.sh"), "echo from-$((40 + 2))\nexit").unwrap();
+        assert!(matches!(read_script(repo.path(), "/etc/profile"), Err(CommandError::PathTraversal(_))));
+
+        let shell = spawn_test_pty("sh", &["-s"], ColorMode::Plain);
+        let (proc, (mut child, reader)) = session(shell);
+        let mut sessions = HashMap::from([("t1".to_string(), proc)]);
+
+        let script = read_script(repo.path(), "run.sh").unwrap();
+        assert_eq!(script, "echo from-$((40 + 2))\nexit\n");
+        write_session(&mut sessions, "t1", &script).unwrap();
+
+        // The added newline runs `exit`, so the shell ends and the reader
+        // stops.
+        let output = read_to_eof(reader);
+        child.wait().unwrap();
+        assert!(output.contains("from-42"), "{:?}", output);
+    }
+
+    #[test]
+    fn wsl_gets_its_directory_as_a_wsl_path() {
+        let launch = LaunchConfig::default();
+        let plan = |cwd: &str, distro: Option<&str>| {
+            let options = SpawnOptions {
+                shell: Some("wsl.exe".into()),
+                cwd: Some(cwd.into()),
+                distro: distro.map(str::to_string),
+                ..SpawnOptions::default()
+            };
+            match plan_spawn(&launch, &HashMap::new(), Some("t1".into()), Some(options))? {
+                Spawn::Start(plan) => Ok(plan.args),
+                Spawn::Running(_) => panic!("no session is running"),
+            }
+        };
+
+        assert_eq!(plan("/home/me", None).unwrap(), ["--cd", "/home/me"]);
+        assert_eq!(plan("/home/me", Some("Ubuntu")).unwrap(), ["--cd", "/home/me", "-d", "Ubuntu"]);
+        // A relative directory exists but has no WSL form, so it isn't
+        // passed to `--cd` as it is.
+        let err = plan(".", None).unwrap_err();
+        assert!(matches!(err, CommandError::InvalidInput(_)), "{:?}", err);
+        assert!(plan("missing-dir", None).is_err());
+    }
+
+    #[test]
+    fn read_only_refuses_to_spawn_or_write() {
+        let read_only = LaunchConfig { read_only: true, ..LaunchConfig::default() };
+        let writable = LaunchConfig::default();
+        let options = || Some(SpawnOptions { shell: Some("sh".into()), ..SpawnOptions::default() });
+        let plan = plan_spawn(&read_only, &HashMap::new(), Some("t1".into()), options());
+        assert!(matches!(plan, Err(CommandError::ReadOnly(_))));
+        let plan = plan_spawn(&writable, &HashMap::new(), Some("t1".into()), options());
+        assert!(matches!(plan, Ok(Spawn::Start(_))));
+
+        let repo = TempDir::new("term-read-only");
+        let marker = repo.path().join("ran");
+        let touch = format!("touch '{}'\n", marker.display());
+        std::fs::write(repo.path().join("run.sh"), &touch).unwrap();
+        let (proc, (mut child, reader)) = session(spawn_test_pty("sh", &["-s"], ColorMode::Plain));
+        let state = TerminalState::default();
+        state.lock().unwrap().insert("t1".into(), proc);
+
+        let err = write_input(&read_only, &state, "t1", &touch).unwrap_err();
+        assert!(matches!(err, CommandError::ReadOnly(_)), "{:?}", err);
+        let err = write_script(&read_only, &state, "t1", repo.path(), "run.sh").unwrap_err();
+        assert!(matches!(err, CommandError::ReadOnly(_)), "{:?}", err);
+        // Read-only is refused before the session is looked up.
+        let err = write_input(&read_only, &state, "missing", "exit\n").unwrap_err();
+        assert!(matches!(err, CommandError::ReadOnly(_)), "{:?}", err);
+
+        write_input(&writable, &state, "t1", "exit\n").unwrap();
+        read_to_eof(reader);
+        child.wait().unwrap();
+        assert!(!marker.exists());
+    }
+
+    #[test]
+    fn idle_timer_fires_unless_kept_active() {
+        let poll = Duration::from_millis(5);
+        let timeout = Duration::from_millis(100);
+        let exited = AtomicBool::new(false);
+        let last_active = Mutex::new(Instant::now());
+
+        let start = Instant::now();
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                for _ in 0..6 {
+                    std::thread::sleep(Duration::from_millis(40));
+                    touch(&last_active);
+                }
+            });
+            assert!(wait_idle(&exited, &last_active, timeout, poll));
+        });
+        assert!(start.elapsed() >= Duration::from_millis(240 + 100), "{:?}", start.elapsed());
+
+        exited.store(true, Ordering::SeqCst);
+        assert!(!wait_idle(&exited, &last_active, Duration::from_secs(60), poll));
+    }
+
+    /// Not a pass/fail test so much as a measurement; run with
+    /// `cargo test -- --ignored --nocapture` to see the numbers.
+    #[test]
+    #[ignore]
+    fn bench_batching_reduces_event_count() {
+        let path = std::env::temp_dir().join("sl-ot-viewer-bench-cat.txt");
+        let line = "the quick brown fox jumps over the lazy dog 0123456789\n";
+        std::fs::write(&path, line.repeat(100_000)).unwrap();
+
+        let PtyShell { master: _master, mut child, reader, writer: _writer } =
+            spawn_test_pty("cat", &[&path.to_string_lossy()], ColorMode::Rich);
+
+        // Count the chunks the reader yields (one event each without
+        // batching) on their way into the batcher.
+        let (raw_tx, raw_rx) = mpsc::sync_channel(output::OUTPUT_QUEUE);
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || read_output(reader, OutputDecoder::default(), raw_tx));
+        let relay = std::thread::spawn(move || {
+            let mut reads = 0;
+            let mut bytes = 0;
+            for chunk in raw_rx {
+                reads += 1;
+                bytes += chunk.len();
+                tx.send(chunk).unwrap();
+            }
+            (reads, bytes)
+        });
+
+        let mut batches = 0;
+        let mut batched_bytes = 0;
+        output::coalesce(rx, output::BATCH_WINDOW, output::BATCH_MAX_BYTES, |batch| {
+            batches += 1;
+            batched_bytes += batch.len();
+        });
+        let (reads, bytes) = relay.join().unwrap();
+        child.wait().unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        println!("per-read events: {}, batched events: {}", reads, batches);
+        assert_eq!(batched_bytes, bytes);
+        assert!(batches * 10 <= reads, "{} batches for {} reads", batches, reads);
+    }
+}
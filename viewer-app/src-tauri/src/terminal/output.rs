@@ -0,0 +1,615 @@
+// ── Terminal output processing ─────────────────────────────────────────────
+
+use crate::error::CommandError;
+use std::collections::VecDeque;
+use std::sync::mpsc::{Receiver, RecvTimeoutError, TryRecvError};
+use std::time::{Duration, Instant};
+
+/// Decodes a byte stream as UTF-8 across read boundaries. A multi-byte
+/// character split between two reads is held back until the rest of it
+/// arrives instead of being turned into replacement glyphs; genuinely invalid
+/// bytes still become U+FFFD.
+#[derive(Default)]
+pub struct Utf8Decoder {
+    pending: Vec<u8>,
+}
+
+impl Utf8Decoder {
+    pub fn decode(&mut self, bytes: &[u8]) -> String {
+        self.pending.extend_from_slice(bytes);
+        let mut out = String::new();
+        let mut input = &self.pending[..];
+        loop {
+            match std::str::from_utf8(input) {
+                Ok(s) => {
+                    out.push_str(s);
+                    input = &[];
+                    break;
+                }
+                Err(e) => {
+                    let (valid, rest) = input.split_at(e.valid_up_to());
+                    out.push_str(std::str::from_utf8(valid).unwrap_or_default());
+                    match e.error_len() {
+                        Some(len) => {
+                            out.push(char::REPLACEMENT_CHARACTER);
+                            input = &rest[len..];
+                        }
+                        // Incomplete sequence at the end: wait for more bytes.
+                        None => {
+                            input = rest;
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        self.pending = input.to_vec();
+        out
+    }
+
+    /// Flush whatever is still held back, e.g. at EOF.
+    pub fn finish(&mut self) -> String {
+        let rest = String::from_utf8_lossy(&self.pending).into_owned();
+        self.pending.clear();
+        rest
+    }
+}
+
+/// Decodes PTY output from the encoding the shell writes: UTF-8 unless a
+/// legacy code page was chosen, as cmd.exe uses on many non-English Windows
+/// installs.
+pub enum OutputDecoder {
+    Utf8(Utf8Decoder),
+    Legacy(encoding_rs::Decoder),
+}
+
+impl Default for OutputDecoder {
+    fn default() -> Self {
+        OutputDecoder::Utf8(Utf8Decoder::default())
+    }
+}
+
+impl OutputDecoder {
+    /// A decoder for the encoding called `label`, which is any name the
+    /// WHATWG Encoding Standard knows it by (`windows-1252`, `cp1252`,
+    /// `latin1`, `ibm866`, `shift_jis`, ...). DOS code pages like 437 and 850
+    /// aren't among them.
+    pub fn for_label(label: &str) -> Result<Self, CommandError> {
+        let encoding = encoding_rs::Encoding::for_label(label.trim().as_bytes())
+            .ok_or_else(|| CommandError::InvalidInput(format!("Unsupported encoding: {}", label)))?;
+        if encoding == encoding_rs::UTF_8 {
+            return Ok(OutputDecoder::default());
+        }
+        Ok(OutputDecoder::Legacy(encoding.new_decoder_without_bom_handling()))
+    }
+
+    pub fn decode(&mut self, bytes: &[u8]) -> String {
+        match self {
+            OutputDecoder::Utf8(decoder) => decoder.decode(bytes),
+            OutputDecoder::Legacy(decoder) => decode_legacy(decoder, bytes, false),
+        }
+    }
+
+    /// Flush whatever is still held back, e.g. at EOF.
+    pub fn finish(&mut self) -> String {
+        match self {
+            OutputDecoder::Utf8(decoder) => decoder.finish(),
+            OutputDecoder::Legacy(decoder) => decode_legacy(decoder, &[], true),
+        }
+    }
+}
+
+fn decode_legacy(decoder: &mut encoding_rs::Decoder, bytes: &[u8], last: bool) -> String {
+    let capacity = decoder.max_utf8_buffer_length(bytes.len()).unwrap_or(bytes.len() * 3 + 16);
+    let mut out = String::with_capacity(capacity);
+    // With room for the worst case, the whole input is consumed in one call.
+    let _ = decoder.decode_to_string(bytes, &mut out, last);
+    out
+}
+
+/// The last `capacity` bytes of a session's output, for repainting a tab
+/// that was recreated after the original output events were gone.
+pub struct Scrollback {
+    buf: VecDeque<u8>,
+    capacity: usize,
+}
+
+impl Scrollback {
+    pub const DEFAULT_CAPACITY: usize = 256 * 1024;
+
+    pub fn new(capacity: usize) -> Self {
+        Scrollback { buf: VecDeque::with_capacity(capacity.min(Self::DEFAULT_CAPACITY)), capacity }
+    }
+
+    pub fn push(&mut self, text: &str) {
+        let bytes = text.as_bytes();
+        let bytes = &bytes[bytes.len().saturating_sub(self.capacity)..];
+        let overflow = (self.buf.len() + bytes.len()).saturating_sub(self.capacity);
+        self.buf.drain(..overflow);
+        self.buf.extend(bytes);
+    }
+
+    pub fn contents(&self) -> String {
+        let bytes: Vec<u8> = self.buf.iter().copied().collect();
+        // Trimming from the front can cut a character in half; drop its
+        // leftover continuation bytes rather than rendering U+FFFD.
+        let start = bytes.iter().take_while(|&&b| b & 0xC0 == 0x80).count();
+        String::from_utf8_lossy(&bytes[start..]).into_owned()
+    }
+}
+
+/// How long output is coalesced before it's emitted: about one frame.
+pub const BATCH_WINDOW: Duration = Duration::from_millis(16);
+
+/// Emit early once a batch reaches this size, and never emit more. PTY reads
+/// already average a couple of KB under heavy output, so a threshold near the
+/// read size would flush on nearly every read and coalesce nothing; this one
+/// is big enough to matter and still small for a single IPC message.
+pub const BATCH_MAX_BYTES: usize = 64 * 1024;
+
+/// How many reads may wait for the emitter before the reader blocks (and with
+/// it the shell, once the PTY buffer fills). With BATCH_MAX_BYTES this bounds
+/// what a terminal holds in memory however its output is shaped.
+pub const OUTPUT_QUEUE: usize = 64;
+
+/// Forward chunks from `rx` to `emit`, merging bursts so a flood of reads
+/// becomes at most one emission per `window` (or per `max_bytes`). A chunk
+/// that arrives after a quiet spell is emitted straight away, so slow,
+/// interactive output behaves exactly as it did per read. No emission is
+/// over `max_bytes`: a batch that outgrows it, like a megabyte of minified
+/// JSON on one line, is cut at a character boundary and the rest starts the
+/// next batch. Returns once the sender hangs up and everything received has
+/// been emitted.
+pub fn coalesce(rx: Receiver<String>, window: Duration, max_bytes: usize, mut emit: impl FnMut(String)) {
+    let mut last_emit: Option<Instant> = None;
+    let mut carry = String::new();
+    loop {
+        let mut batch = if carry.is_empty() {
+            match rx.recv() {
+                Ok(chunk) => chunk,
+                Err(_) => break,
+            }
+        } else {
+            std::mem::take(&mut carry)
+        };
+        let deadline = last_emit.map(|t| t + window);
+        let mut hung_up = false;
+        while batch.len() < max_bytes {
+            let now = Instant::now();
+            let next = match deadline {
+                Some(deadline) if deadline > now => rx.recv_timeout(deadline - now),
+                // Window already passed: take only what's queued right now.
+                _ => rx.try_recv().map_err(|e| match e {
+                    TryRecvError::Empty => RecvTimeoutError::Timeout,
+                    TryRecvError::Disconnected => RecvTimeoutError::Disconnected,
+                }),
+            };
+            match next {
+                Ok(chunk) => batch.push_str(&chunk),
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => {
+                    hung_up = true;
+                    break;
+                }
+            }
+        }
+        if batch.len() > max_bytes {
+            let mut cut = max_bytes;
+            while !batch.is_char_boundary(cut) {
+                cut -= 1;
+            }
+            if cut == 0 {
+                // A limit smaller than one character still has to make progress.
+                cut = batch.chars().next().map_or(0, char::len_utf8);
+            }
+            carry = batch.split_off(cut);
+        }
+        emit(batch);
+        if hung_up && carry.is_empty() {
+            break;
+        }
+        last_emit = Some(Instant::now());
+    }
+}
+
+/// What scan_ansi finds in terminal output.
+enum Token<'a> {
+    /// A byte of text to keep.
+    Text(u8),
+    /// The parameters of an SGR sequence (`ESC [ ... m`), like `1;31`.
+    Sgr(&'a [u8]),
+}
+
+/// Split `bytes` into text and SGR sequences, dropping every other ANSI/VT
+/// escape: the rest of CSI (cursor moves, erases), OSC strings such as window
+/// titles (ended by BEL or `ESC \`), DCS/PM/APC strings, and two- or
+/// three-byte escapes like `ESC ( B`. Other control characters go too, except
+/// tabs and newlines, so `\r\n` line endings become `\n`.
+fn scan_ansi(bytes: &[u8], mut visit: impl FnMut(Token)) {
+    const ESC: u8 = 0x1b;
+    const BEL: u8 = 0x07;
+    let mut i = 0;
+    // Skip past a string sequence's terminator, if it has one.
+    let skip_string = |mut i: usize| {
+        while i < bytes.len() {
+            match bytes[i] {
+                BEL => return i + 1,
+                ESC if bytes.get(i + 1) == Some(&b'\\') => return i + 2,
+                _ => i += 1,
+            }
+        }
+        i
+    };
+    while i < bytes.len() {
+        let b = bytes[i];
+        if b != ESC {
+            if (b >= 0x20 && b != 0x7f) || b == b'\n' || b == b'\t' {
+                visit(Token::Text(b));
+            }
+            i += 1;
+            continue;
+        }
+        i = match bytes.get(i + 1) {
+            // CSI: parameter and intermediate bytes, then one final byte.
+            Some(b'[') => {
+                let mut j = i + 2;
+                while j < bytes.len() && (0x20..=0x3f).contains(&bytes[j]) {
+                    j += 1;
+                }
+                if bytes.get(j) == Some(&b'm') {
+                    visit(Token::Sgr(&bytes[i + 2..j]));
+                }
+                (j + 1).min(bytes.len())
+            }
+            Some(b']' | b'P' | b'X' | b'^' | b'_') => skip_string(i + 2),
+            Some(_) => {
+                let mut j = i + 1;
+                while j < bytes.len() && (0x20..=0x2f).contains(&bytes[j]) {
+                    j += 1;
+                }
+                (j + 1).min(bytes.len())
+            }
+            None => bytes.len(),
+        };
+    }
+}
+
+/// `bytes` with ANSI/VT escape sequences removed, for a plain-text copy of a
+/// session; see scan_ansi for what goes. The sequences are all ASCII, so
+/// valid UTF-8 in stays valid UTF-8 out.
+pub fn strip_ansi(bytes: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(bytes.len());
+    scan_ansi(bytes, |token| {
+        if let Token::Text(b) = token {
+            out.push(b);
+        }
+    });
+    out
+}
+
+/// The 16 basic colors, as VS Code's terminal shows them.
+const ANSI_COLORS: [&str; 16] = [
+    "#000000", "#cd3131", "#0dbc79", "#e5e510", "#2472c8", "#bc3fbc", "#11a8cd", "#e5e5e5",
+    "#666666", "#f14c4c", "#23d18b", "#f5f543", "#3b8eea", "#d670d6", "#29b8db", "#ffffff",
+];
+
+/// The text attributes SGR sequences have set so far.
+#[derive(Clone, Default, PartialEq)]
+struct Style {
+    bold: bool,
+    dim: bool,
+    italic: bool,
+    underline: bool,
+    fg: Option<String>,
+    bg: Option<String>,
+}
+
+impl Style {
+    /// Apply one SGR sequence's parameters. Unknown codes are ignored.
+    fn apply(&mut self, params: &[u8]) {
+        let params = String::from_utf8_lossy(params);
+        // A bare `ESC [ m` is a reset, as is an empty parameter.
+        let mut codes = params.split([';', ':']).map(|code| code.parse::<u16>().unwrap_or(0));
+        while let Some(code) = codes.next() {
+            match code {
+                0 => *self = Style::default(),
+                1 => self.bold = true,
+                2 => self.dim = true,
+                3 => self.italic = true,
+                4 => self.underline = true,
+                22 => (self.bold, self.dim) = (false, false),
+                23 => self.italic = false,
+                24 => self.underline = false,
+                30..=37 => self.fg = Some(ANSI_COLORS[(code - 30) as usize].to_string()),
+                90..=97 => self.fg = Some(ANSI_COLORS[(code - 90 + 8) as usize].to_string()),
+                40..=47 => self.bg = Some(ANSI_COLORS[(code - 40) as usize].to_string()),
+                100..=107 => self.bg = Some(ANSI_COLORS[(code - 100 + 8) as usize].to_string()),
+                38 => self.fg = extended_color(&mut codes),
+                48 => self.bg = extended_color(&mut codes),
+                39 => self.fg = None,
+                49 => self.bg = None,
+                _ => {}
+            }
+        }
+    }
+
+    /// The inline CSS for this style; empty for plain text.
+    fn css(&self) -> String {
+        let mut css = Vec::new();
+        if self.bold {
+            css.push("font-weight:bold".to_string());
+        }
+        if self.dim {
+            css.push("opacity:0.7".to_string());
+        }
+        if self.italic {
+            css.push("font-style:italic".to_string());
+        }
+        if self.underline {
+            css.push("text-decoration:underline".to_string());
+        }
+        if let Some(fg) = &self.fg {
+            css.push(format!("color:{}", fg));
+        }
+        if let Some(bg) = &self.bg {
+            css.push(format!("background-color:{}", bg));
+        }
+        css.join(";")
+    }
+}
+
+/// The color after a 38 or 48: `5;n` from the 256-color palette, or
+/// `2;r;g;b`. None (the default color) if it's malformed.
+fn extended_color(codes: &mut impl Iterator<Item = u16>) -> Option<String> {
+    match codes.next()? {
+        5 => {
+            let n = codes.next()?;
+            match n {
+                0..=15 => Some(ANSI_COLORS[n as usize].to_string()),
+                16..=231 => {
+                    let level = |v: u16| if v == 0 { 0 } else { 55 + v * 40 };
+                    let n = n - 16;
+                    Some(format!("#{:02x}{:02x}{:02x}", level(n / 36), level(n / 6 % 6), level(n % 6)))
+                }
+                232..=255 => {
+                    let gray = 8 + (n - 232) * 10;
+                    Some(format!("#{:02x}{:02x}{:02x}", gray, gray, gray))
+                }
+                _ => None,
+            }
+        }
+        2 => {
+            let (r, g, b) = (codes.next()?, codes.next()?, codes.next()?);
+            Some(format!("#{:02x}{:02x}{:02x}", r.min(255), g.min(255), b.min(255)))
+        }
+        _ => None,
+    }
+}
+
+/// `bytes` as HTML for showing captured output without a terminal: text is
+/// escaped, and each run styled by SGR codes (bold, dim, italic, underline,
+/// 16, 256 and 24-bit colors) is wrapped in a `<span style="...">`. Spans
+/// aren't nested; a change of style closes the open span and starts another
+/// with everything now in effect. Other escapes are dropped as in
+/// strip_ansi. Newlines are kept, for a `<pre>` to show.
+pub fn ansi_to_html(bytes: &[u8]) -> String {
+    let mut out: Vec<u8> = Vec::with_capacity(bytes.len());
+    let mut style = Style::default();
+    // The style of the open span; the default when none is open.
+    let mut open = Style::default();
+    scan_ansi(bytes, |token| match token {
+        Token::Sgr(params) => style.apply(params),
+        Token::Text(b) => {
+            if style != open {
+                if open != Style::default() {
+                    out.extend_from_slice(b"</span>");
+                }
+                if style != Style::default() {
+                    out.extend_from_slice(format!("<span style=\"{}\">", style.css()).as_bytes());
+                }
+                open = style.clone();
+            }
+            match b {
+                b'<' => out.extend_from_slice(b"&lt;"),
+                b'>' => out.extend_from_slice(b"&gt;"),
+                b'&' => out.extend_from_slice(b"&amp;"),
+                b'"' => out.extend_from_slice(b"&quot;"),
+                _ => out.push(b),
+            }
+        }
+    });
+    if open != Style::default() {
+        out.extend_from_slice(b"</span>");
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[test]
+    fn reassembles_characters_split_across_reads() {
+        let text = "┌──┐ héllo │ 日本";
+        let bytes = text.as_bytes();
+        for split in 0..=bytes.len() {
+            let mut decoder = Utf8Decoder::default();
+            let mut out = decoder.decode(&bytes[..split]);
+            out.push_str(&decoder.decode(&bytes[split..]));
+            out.push_str(&decoder.finish());
+            assert_eq!(out, text, "split at byte {}", split);
+        }
+    }
+
+    #[test]
+    fn holds_back_only_the_incomplete_tail() {
+        let mut decoder = Utf8Decoder::default();
+        // "─" is E2 94 80
+        assert_eq!(decoder.decode(b"ab\xe2\x94"), "ab");
+        assert_eq!(decoder.decode(b"\x80c"), "─c");
+    }
+
+    #[test]
+    fn replaces_invalid_bytes() {
+        let mut decoder = Utf8Decoder::default();
+        assert_eq!(decoder.decode(b"a\xffb"), "a\u{fffd}b");
+        assert_eq!(decoder.decode(b"\xe2\x94"), "");
+        assert_eq!(decoder.finish(), "\u{fffd}");
+    }
+
+    #[test]
+    fn decodes_legacy_code_pages() {
+        let mut decoder = OutputDecoder::for_label("CP1252").unwrap();
+        // "Ça coûte 5 €" in Windows-1252.
+        assert_eq!(decoder.decode(b"\xc7a co\xfbte 5 \x80"), "Ça coûte 5 €");
+        assert_eq!(decoder.finish(), "");
+
+        let mut multibyte = OutputDecoder::for_label("shift_jis").unwrap();
+        // "日本" is 93 FA 96 7B; a split character waits for its second byte.
+        assert_eq!(multibyte.decode(b"\x93\xfa\x96"), "日");
+        assert_eq!(multibyte.decode(b"\x7b"), "本");
+
+        assert!(matches!(OutputDecoder::for_label("utf-8"), Ok(OutputDecoder::Utf8(_))));
+        assert!(OutputDecoder::for_label("cp437").is_err());
+    }
+
+    #[test]
+    fn scrollback_keeps_only_the_newest_bytes() {
+        let mut scrollback = Scrollback::new(8);
+        scrollback.push("hello ");
+        scrollback.push("world");
+        assert_eq!(scrollback.contents(), "lo world");
+        scrollback.push("0123456789");
+        assert_eq!(scrollback.contents(), "23456789");
+    }
+
+    #[test]
+    fn scrollback_drops_a_character_cut_by_trimming() {
+        let mut scrollback = Scrollback::new(4);
+        // "é" is two bytes; keeping the last four cuts it in half.
+        scrollback.push("éabc");
+        scrollback.push("");
+        assert_eq!(scrollback.contents(), "abc");
+    }
+
+    #[test]
+    fn coalesces_a_burst_into_few_emissions() {
+        let (tx, rx) = mpsc::channel();
+        for i in 0..1000 {
+            tx.send(format!("{} ", i)).unwrap();
+        }
+        drop(tx);
+
+        let mut emitted = Vec::new();
+        coalesce(rx, BATCH_WINDOW, BATCH_MAX_BYTES, |batch| emitted.push(batch));
+
+        let expected: String = (0..1000).map(|i| format!("{} ", i)).collect();
+        assert_eq!(emitted.concat(), expected);
+        assert!(emitted.len() <= 2, "{} emissions", emitted.len());
+    }
+
+    #[test]
+    fn a_long_line_arrives_in_capped_chunks() {
+        let blob = "{\"k\":\"é\"}".repeat(1024 * 1024 / 12);
+        let (tx, rx) = mpsc::channel();
+        let sender = {
+            let blob = blob.clone();
+            std::thread::spawn(move || {
+                let mut rest = blob.as_str();
+                while !rest.is_empty() {
+                    let mut at = rest.len().min(4096);
+                    while !rest.is_char_boundary(at) {
+                        at -= 1;
+                    }
+                    tx.send(rest[..at].to_string()).unwrap();
+                    rest = &rest[at..];
+                }
+            })
+        };
+
+        let mut emitted = Vec::new();
+        coalesce(rx, BATCH_WINDOW, BATCH_MAX_BYTES, |batch| emitted.push(batch));
+        sender.join().unwrap();
+
+        assert!(emitted.iter().all(|batch| batch.len() <= BATCH_MAX_BYTES));
+        assert!(emitted.len() >= blob.len() / BATCH_MAX_BYTES, "{} emissions", emitted.len());
+        assert_eq!(emitted.concat(), blob);
+
+        // Cuts fall between characters, even with a limit below one.
+        let (tx, rx) = mpsc::channel();
+        tx.send("ééé".to_string()).unwrap();
+        drop(tx);
+        let mut emitted = Vec::new();
+        coalesce(rx, BATCH_WINDOW, 1, |batch| emitted.push(batch));
+        assert_eq!(emitted, ["é", "é", "é"]);
+    }
+
+    #[test]
+    fn emits_slow_output_per_chunk() {
+        let (tx, rx) = mpsc::channel();
+        let sender = std::thread::spawn(move || {
+            for chunk in ["a", "b", "c"] {
+                tx.send(chunk.to_string()).unwrap();
+                std::thread::sleep(BATCH_WINDOW * 4);
+            }
+        });
+
+        let mut emitted = Vec::new();
+        coalesce(rx, BATCH_WINDOW, BATCH_MAX_BYTES, |batch| emitted.push(batch));
+        sender.join().unwrap();
+
+        assert_eq!(emitted, ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn flushes_when_a_batch_reaches_the_size_limit() {
+        let (tx, rx) = mpsc::channel();
+        for _ in 0..10 {
+            tx.send("x".repeat(10)).unwrap();
+        }
+        drop(tx);
+
+        let mut emitted = Vec::new();
+        coalesce(rx, Duration::from_secs(60), 30, |batch| emitted.push(batch));
+
+        assert_eq!(emitted.concat(), "x".repeat(100));
+        assert!(emitted.iter().all(|batch| batch.len() <= 30), "{:?}", emitted);
+    }
+
+    #[test]
+    fn strips_colors_cursor_moves_and_titles() {
+        let raw = b"\x1b]0;user@host: ~\x07\x1b[31merror\x1b[0m: \x1b[1;32mok\x1b[m\r\n\x1b[2K\x1b[10;4Hdone\n";
+        assert_eq!(strip_ansi(raw), b"error: ok\ndone\n");
+    }
+
+    #[test]
+    fn colors_become_spans_and_text_is_escaped() {
+        let raw = b"\x1b[31merror\x1b[0m: a < b && c > \"d\"\r\n\x1b[38;5;196mhot\x1b[39m \x1b[48;2;0;128;255mblue\x1b[m";
+        assert_eq!(
+            ansi_to_html(raw),
+            "<span style=\"color:#cd3131\">error</span>: a &lt; b &amp;&amp; c &gt; &quot;d&quot;\n\
+             <span style=\"color:#ff0000\">hot</span> <span style=\"background-color:#0080ff\">blue</span>"
+        );
+    }
+
+    #[test]
+    fn nested_styles_combine_and_unwind() {
+        let raw = b"\x1b[1mbold \x1b[4;92mboth\x1b[24m still\x1b[22m green\x1b[2K\x1b[3;1H\x1b[0m plain";
+        assert_eq!(
+            ansi_to_html(raw),
+            "<span style=\"font-weight:bold\">bold </span>\
+             <span style=\"font-weight:bold;text-decoration:underline;color:#23d18b\">both</span>\
+             <span style=\"font-weight:bold;color:#23d18b\"> still</span>\
+             <span style=\"color:#23d18b\"> green</span> plain"
+        );
+        // A style with no text under it leaves no empty span.
+        assert_eq!(ansi_to_html(b"\x1b[31m\x1b[0mx\x1b[1m"), "x");
+    }
+
+    #[test]
+    fn strips_st_terminated_strings_and_short_escapes() {
+        let raw = "\x1b]2;title\x1b\\\x1b(Bcaf\u{e9}\x1b=\x1b[?25l\x1b[".as_bytes();
+        assert_eq!(String::from_utf8(strip_ansi(raw)).unwrap(), "caf\u{e9}");
+    }
+}
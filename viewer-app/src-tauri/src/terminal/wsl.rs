@@ -0,0 +1,191 @@
+// ── WSL distributions ──────────────────────────────────────────────────────
+
+/// One installed distro, as `wsl.exe -l -v` lists it.
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct WslDistro {
+    pub name: String,
+    /// `Running`, `Stopped`, ... as WSL reports it (localized).
+    pub state: String,
+    /// 1 or 2, or None if the column didn't parse.
+    pub version: Option<u8>,
+    /// Whether it's the one `wsl.exe` starts without `-d`.
+    pub default: bool,
+}
+
+/// Text printed by wsl.exe, which writes UTF-16LE unless `WSL_UTF8=1` is set
+/// in its environment. Decoded as UTF-16LE when a BOM or the NUL high bytes
+/// of ASCII give it away, else as UTF-8.
+pub fn decode_output(bytes: &[u8]) -> String {
+    let bytes = bytes.strip_prefix(&[0xff, 0xfe]).unwrap_or(bytes);
+    let looks_utf16 = bytes.len() >= 2 && bytes.iter().skip(1).step_by(2).any(|&b| b == 0);
+    let text = if looks_utf16 {
+        let units: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        String::from_utf16_lossy(&units)
+    } else {
+        String::from_utf8_lossy(bytes).into_owned()
+    };
+    text.replace('\0', "")
+}
+
+/// Parse the table `wsl.exe -l -v` prints. The header row is localized, so
+/// it's skipped by position; each row is an optional `*` marking the
+/// default, then the name, state and version columns. Names can't contain
+/// spaces, but the state is read from the right anyway in case a
+/// translation has them.
+pub fn parse_list(text: &str) -> Vec<WslDistro> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .skip(1)
+        .filter_map(|line| {
+            let (default, rest) = match line.strip_prefix('*') {
+                Some(rest) => (true, rest.trim_start()),
+                None => (false, line),
+            };
+            let mut words: Vec<&str> = rest.split_whitespace().collect();
+            if words.len() < 3 {
+                return None;
+            }
+            let version = words.pop().and_then(|v| v.parse().ok());
+            let name = words.remove(0).to_string();
+            Some(WslDistro { name, state: words.join(" "), version, default })
+        })
+        .collect()
+}
+
+/// `path` as a WSL shell sees it: `C:\clients\acme` is
+/// `/mnt/c/clients/acme`, and a path into a distro's own files
+/// (`\\wsl$\Ubuntu\home\me` or `\\wsl.localhost\Ubuntu\home\me`) is
+/// `/home/me`. A path already in WSL form comes back as it is. None for a
+/// relative path or a network share, which WSL has no mount for.
+pub fn to_wsl_path(path: &str) -> Option<String> {
+    if path.starts_with('/') && !path.starts_with("//") {
+        return Some(path.to_string());
+    }
+    // Verbatim paths: `\\?\C:\x` and `\\?\UNC\server\share\x`.
+    let (path, unc) = match path.strip_prefix(r"\\?\").or_else(|| path.strip_prefix("//?/")) {
+        Some(rest) => match rest.strip_prefix(r"UNC\") {
+            Some(share) => (share, true),
+            None => (rest, false),
+        },
+        None => match path.strip_prefix(r"\\").or_else(|| path.strip_prefix("//")) {
+            Some(share) => (share, true),
+            None => (path, false),
+        },
+    };
+    let mut parts = path.split(['\\', '/']).filter(|part| !part.is_empty());
+    let mut wsl = if unc {
+        let host = parts.next()?;
+        if !(host.eq_ignore_ascii_case("wsl$") || host.eq_ignore_ascii_case("wsl.localhost")) {
+            return None;
+        }
+        // The distro; the rest is a path inside it.
+        parts.next()?;
+        String::new()
+    } else {
+        let drive = parts.next()?;
+        match drive.as_bytes() {
+            [letter, b':'] if letter.is_ascii_alphabetic() => {
+                format!("/mnt/{}", letter.to_ascii_lowercase() as char)
+            }
+            _ => return None,
+        }
+    };
+    for part in parts {
+        wsl.push('/');
+        wsl.push_str(part);
+    }
+    if wsl.is_empty() {
+        wsl.push('/');
+    }
+    Some(wsl)
+}
+
+/// The Windows form of a WSL path: `/mnt/c/clients/acme` is
+/// `C:\clients\acme`, and any other absolute path is reached through
+/// `\\wsl.localhost\<distro>` if `distro` is given. A path already in
+/// Windows form comes back as it is. None for a relative path, or a path
+/// inside a distro when `distro` isn't given.
+pub fn from_wsl_path(path: &str, distro: Option<&str>) -> Option<String> {
+    let bytes = path.as_bytes();
+    let is_windows = path.starts_with(r"\\")
+        || (bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':');
+    if is_windows {
+        return Some(path.to_string());
+    }
+    if !path.starts_with('/') {
+        return None;
+    }
+    let parts: Vec<&str> = path.split('/').filter(|part| !part.is_empty()).collect();
+    match parts.as_slice() {
+        ["mnt", drive, rest @ ..] if drive.len() == 1 && drive.as_bytes()[0].is_ascii_alphabetic() => {
+            Some(format!(r"{}:\{}", drive.to_ascii_uppercase(), rest.join(r"\")))
+        }
+        _ => distro.map(|distro| format!(r"\\wsl.localhost\{}\{}", distro, parts.join(r"\"))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16(text: &str) -> Vec<u8> {
+        let mut bytes = vec![0xff, 0xfe];
+        bytes.extend(text.encode_utf16().flat_map(|unit| unit.to_le_bytes()));
+        bytes
+    }
+
+    #[test]
+    fn parses_utf16_distro_list() {
+        let output = "  NAME            STATE           VERSION\r\n\
+                      * Ubuntu-22.04    Running         2\r\n  \
+                      Debian          Stopped         1\r\n\r\n";
+        let distros = parse_list(&decode_output(&utf16(output)));
+        assert_eq!(
+            distros,
+            [
+                WslDistro { name: "Ubuntu-22.04".into(), state: "Running".into(), version: Some(2), default: true },
+                WslDistro { name: "Debian".into(), state: "Stopped".into(), version: Some(1), default: false },
+            ]
+        );
+    }
+
+    #[test]
+    fn utf8_output_and_localized_headers_parse_too() {
+        let output = "  NOM      ÉTAT       VERSION\n* Ubuntu   En cours   2\n";
+        let distros = parse_list(&decode_output(output.as_bytes()));
+        assert_eq!(distros.len(), 1);
+        assert_eq!((distros[0].state.as_str(), distros[0].default), ("En cours", true));
+    }
+
+    #[test]
+    fn converts_between_windows_and_wsl_paths() {
+        assert_eq!(to_wsl_path(r"C:\foo\bar").as_deref(), Some("/mnt/c/foo/bar"));
+        assert_eq!(from_wsl_path("/mnt/c/foo/bar", None).as_deref(), Some(r"C:\foo\bar"));
+        assert_eq!(to_wsl_path(r"D:\").as_deref(), Some("/mnt/d"));
+        assert_eq!(to_wsl_path(r"\\?\C:\clients\acme").as_deref(), Some("/mnt/c/clients/acme"));
+        assert_eq!(to_wsl_path(r"\\wsl$\Ubuntu\home\me").as_deref(), Some("/home/me"));
+        assert_eq!(to_wsl_path(r"\\wsl.localhost\Ubuntu").as_deref(), Some("/"));
+        assert_eq!(to_wsl_path(r"\\fileserver\share\acme"), None);
+        assert_eq!(to_wsl_path(r"clients\acme"), None);
+        assert_eq!(
+            from_wsl_path("/home/me/repo", Some("Ubuntu")).as_deref(),
+            Some(r"\\wsl.localhost\Ubuntu\home\me\repo")
+        );
+        assert_eq!(from_wsl_path("/home/me/repo", None), None);
+    }
+
+    #[test]
+    fn paths_already_in_the_target_form_are_kept() {
+        assert_eq!(to_wsl_path("/mnt/c/foo/bar").as_deref(), Some("/mnt/c/foo/bar"));
+        assert_eq!(to_wsl_path("/home/me/repo").as_deref(), Some("/home/me/repo"));
+        assert_eq!(from_wsl_path(r"C:\foo\bar", None).as_deref(), Some(r"C:\foo\bar"));
+        let unc = r"\\wsl$\Ubuntu\home\me";
+        assert_eq!(from_wsl_path(unc, None).as_deref(), Some(unc));
+        let once = to_wsl_path(r"C:\foo\bar").unwrap();
+        assert_eq!(to_wsl_path(&once), Some(once));
+    }
+}
@@ -0,0 +1,613 @@
+// ── Structural checks on the _company files ────────────────────────────────
+
+use crate::company;
+use crate::config::{self, ViewerConfig};
+use crate::error::CommandError;
+use crate::telemetry;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::Path;
+
+/// One problem found by a check. `path` locates it in the file, e.g.
+/// `people[3].reports_to`.
+#[derive(Debug, serde::Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub path: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A person as the viewer reads them. Every field is optional here so a bad
+/// entry is reported rather than failing the whole file; the display fields
+/// are only deserialized to check they're strings.
+#[derive(Default, serde::Deserialize)]
+struct Person {
+    name: Option<String>,
+    reports_to: Option<String>,
+    dotted_to: Option<String>,
+    #[allow(dead_code)]
+    title: Option<String>,
+    #[allow(dead_code)]
+    level: Option<String>,
+    #[allow(dead_code)]
+    org: Option<String>,
+    #[allow(dead_code)]
+    email: Option<String>,
+}
+
+/// The sections of org_chart.json the viewer draws people from.
+pub const PEOPLE_SECTIONS: [&str; 3] = ["leadership", "people", "team"];
+
+/// Check the repo's org chart: every person needs a name, `reports_to` and
+/// `dotted_to` must name someone in the chart, and the reporting lines must
+/// not loop.
+#[tauri::command]
+pub fn validate_org_chart(
+    app: tauri::AppHandle,
+    config: tauri::State<'_, ViewerConfig>,
+    repo_path: String,
+) -> Result<Vec<Diagnostic>, CommandError> {
+    telemetry::timed(&app, "validate_org_chart", || {
+        let company_dir = company::company_dir(Path::new(&repo_path))?;
+        let path = company::find_company_file(&company_dir, "org_chart.json")
+            .ok_or_else(|| {
+                CommandError::NotFound(format!("No org_chart.json found in {}", company_dir.display()))
+            })?;
+        let name = company::file_name(&path);
+        Ok(
+            match company::parse_company_file(&path, &name, config.max_file_bytes) {
+                Ok(chart) => check_org_chart(&chart),
+                Err(e) => vec![Diagnostic::new(Severity::Error, "", e.message())],
+            },
+        )
+    })
+}
+
+/// The id the viewer gives a person: their name lowercased, with each run of
+/// other characters turned into `_`.
+pub fn make_id(name: &str) -> String {
+    let lower = name.to_lowercase();
+    let mut id = String::new();
+    for c in lower.chars() {
+        if c.is_ascii_lowercase() || c.is_ascii_digit() {
+            id.push(c);
+        } else if !id.ends_with('_') {
+            id.push('_');
+        }
+    }
+    id.trim_matches('_').to_string()
+}
+
+/// A `reports_to` value minus a trailing note like "(inferred)".
+pub fn strip_note(reference: &str) -> &str {
+    let trimmed = reference.trim_end();
+    match trimmed.find('(') {
+        Some(open) if trimmed.ends_with(')') => trimmed[..open].trim_end(),
+        _ => trimmed,
+    }
+}
+
+impl Diagnostic {
+    fn new(severity: Severity, path: &str, message: impl Into<String>) -> Self {
+        Diagnostic {
+            severity,
+            message: message.into(),
+            path: path.to_string(),
+        }
+    }
+}
+
+/// Every person in the chart with where they are, in the order the viewer
+/// adds them. Entries that aren't people are reported instead.
+fn collect_people(
+    chart: &serde_json::Value,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Vec<(String, Person)> {
+    let mut entries: Vec<(String, &serde_json::Value)> = Vec::new();
+    for section in PEOPLE_SECTIONS {
+        match &chart[section] {
+            serde_json::Value::Null => {}
+            serde_json::Value::Array(list) => {
+                entries.extend(
+                    list.iter()
+                        .enumerate()
+                        .map(|(i, value)| (format!("{}[{}]", section, i), value)),
+                );
+            }
+            _ => diagnostics.push(Diagnostic::new(
+                Severity::Error,
+                section,
+                format!("{} should be a list", section),
+            )),
+        }
+    }
+
+    let orgs: Vec<(String, &serde_json::Value)> = match &chart["external_ecosystem"] {
+        serde_json::Value::Object(map) => map
+            .iter()
+            .map(|(key, org)| (format!("external_ecosystem.{}", key), org))
+            .collect(),
+        serde_json::Value::Array(list) => list
+            .iter()
+            .enumerate()
+            .map(|(i, org)| (format!("external_ecosystem[{}]", i), org))
+            .collect(),
+        _ => Vec::new(),
+    };
+    for (org_path, org) in orgs {
+        let Some(contacts) = org.get("key_contacts") else {
+            continue;
+        };
+        match contacts.as_array() {
+            Some(list) => entries.extend(
+                list.iter()
+                    .enumerate()
+                    .map(|(i, value)| (format!("{}.key_contacts[{}]", org_path, i), value)),
+            ),
+            None => diagnostics.push(Diagnostic::new(
+                Severity::Error,
+                &org_path,
+                "key_contacts should be a list",
+            )),
+        }
+    }
+
+    let mut people = Vec::new();
+    for (path, value) in entries {
+        match serde_json::from_value::<Person>(value.clone()) {
+            Ok(person) => people.push((path, person)),
+            Err(e) => diagnostics.push(Diagnostic::new(
+                Severity::Error,
+                &path,
+                format!("Not a valid person: {}", e),
+            )),
+        }
+    }
+    people
+}
+
+fn check_org_chart(chart: &serde_json::Value) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let people = collect_people(chart, &mut diagnostics);
+    let mut report = |severity, path: &str, message: String| {
+        diagnostics.push(Diagnostic::new(severity, path, message));
+    };
+
+    // Ids, keeping the first person with each as the viewer does.
+    let mut ids: HashMap<String, &str> = HashMap::new();
+    let mut named: Vec<(&str, String, &Person)> = Vec::new();
+    for (path, person) in &people {
+        let id = person.name.as_deref().map(make_id).unwrap_or_default();
+        if id.is_empty() {
+            report(Severity::Error, path, "Person has no name".to_string());
+        } else if let Some(first) = ids.get(&id) {
+            report(
+                Severity::Warning,
+                path,
+                format!("Duplicate of {}; only the first is shown", first),
+            );
+        } else {
+            ids.insert(id.clone(), path);
+            named.push((path, id, person));
+        }
+    }
+
+    let mut manager_of: BTreeMap<&str, String> = BTreeMap::new();
+    for (path, id, person) in &named {
+        for (field, reference) in [
+            ("reports_to", &person.reports_to),
+            ("dotted_to", &person.dotted_to),
+        ] {
+            let Some(reference) = reference.as_deref().filter(|r| !r.trim().is_empty()) else {
+                continue;
+            };
+            let target = make_id(strip_note(reference));
+            let field_path = format!("{}.{}", path, field);
+            if !ids.contains_key(&target) {
+                report(
+                    Severity::Error,
+                    &field_path,
+                    format!(
+                        "{} refers to \"{}\", who isn't in the org chart",
+                        field, reference
+                    ),
+                );
+            } else if field == "reports_to" {
+                manager_of.insert(id, target);
+            }
+        }
+    }
+
+    for cycle in reporting_cycles(&manager_of) {
+        let path = ids.get(&cycle[0]).copied().unwrap_or_default();
+        let mut names = cycle.clone();
+        names.push(cycle[0].clone());
+        report(
+            Severity::Error,
+            &format!("{}.reports_to", path),
+            format!("Reporting cycle: {}", names.join(" -> ")),
+        );
+    }
+
+    diagnostics
+}
+
+/// Each loop in the reporting lines, listed once, starting from its smallest
+/// id. Someone reporting to themselves is a loop of one.
+pub fn reporting_cycles(manager_of: &BTreeMap<&str, String>) -> Vec<Vec<String>> {
+    let mut cycles = Vec::new();
+    let mut done: HashSet<&str> = HashSet::new();
+    for &start in manager_of.keys() {
+        let mut chain: Vec<&str> = Vec::new();
+        let mut current = start;
+        while !done.contains(current) {
+            if let Some(pos) = chain.iter().position(|&id| id == current) {
+                let mut cycle: Vec<String> = chain[pos..].iter().map(|id| id.to_string()).collect();
+                let smallest = (0..cycle.len()).min_by_key(|&i| &cycle[i]).unwrap_or(0);
+                cycle.rotate_left(smallest);
+                cycles.push(cycle);
+                break;
+            }
+            chain.push(current);
+            match manager_of.get(current) {
+                Some(next) => current = next,
+                None => break,
+            }
+        }
+        done.extend(chain);
+    }
+    cycles
+}
+
+/// Engagements that disagree between the registry, the engagement map and
+/// the engagement folders on disk, each list sorted.
+#[derive(Debug, Default, PartialEq, serde::Serialize)]
+pub struct EngagementLint {
+    /// Keys in engagement_map.json that aren't in the registry.
+    pub orphaned_map_entries: Vec<String>,
+    /// Engagement folders (relative to the repo) whose key isn't registered.
+    pub unregistered_dirs: Vec<String>,
+    /// Registered keys with no engagement folder.
+    pub missing_on_disk: Vec<String>,
+}
+
+/// Cross-check engagement_registry.json, engagement_map.json and the
+/// engagement folders read_company_data finds. A folder's key is the
+/// `engagement` field of its engagement_config.json, or its name.
+#[tauri::command]
+pub fn lint_engagements(
+    app: tauri::AppHandle,
+    config: tauri::State<'_, ViewerConfig>,
+    repo_path: String,
+) -> Result<EngagementLint, CommandError> {
+    telemetry::timed(&app, "lint_engagements", || {
+        lint_engagements_in(Path::new(&repo_path), &config)
+    })
+}
+
+fn lint_engagements_in(base: &Path, config: &ViewerConfig) -> Result<EngagementLint, CommandError> {
+    let company_dir = company::company_dir(base)?;
+    let read = |filename: &str| -> Result<Option<serde_json::Value>, CommandError> {
+        match company::find_company_file(&company_dir, filename) {
+            Some(path) => company::parse_company_file(
+                &path,
+                &company::file_name(&path),
+                config.max_file_bytes,
+            )
+            .map(Some),
+            None => Ok(None),
+        }
+    };
+    let registry = read("engagement_registry.json")?.ok_or_else(|| {
+        CommandError::NotFound(format!(
+            "No engagement_registry.json found in {}",
+            company_dir.display()
+        ))
+    })?;
+    let map = read("engagement_map.json")?.unwrap_or_default();
+
+    let registered: BTreeSet<String> = registry["engagements"]
+        .as_object()
+        .map(|engagements| engagements.keys().cloned().collect())
+        .unwrap_or_default();
+    let mapped: BTreeSet<String> = map["engagements"]
+        .as_array()
+        .map(|engagements| {
+            engagements
+                .iter()
+                .filter_map(|eng| eng["key"].as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut on_disk: BTreeMap<String, String> = BTreeMap::new();
+    for dir in company::engagement_dirs(base, config) {
+        let key = config::read_capped(
+            &dir.join("engagement_config.json"),
+            "engagement_config.json",
+            config.max_file_bytes,
+        )
+        .ok()
+        .and_then(|content| {
+            serde_json::from_str::<serde_json::Value>(company::strip_bom(&content)).ok()
+        })
+        .and_then(|cfg| cfg["engagement"].as_str().map(str::to_string))
+        .unwrap_or_else(|| company::file_name(&dir));
+        let relative = dir.strip_prefix(base).unwrap_or(&dir);
+        on_disk.insert(key, relative.to_string_lossy().replace('\\', "/"));
+    }
+
+    let mut unregistered_dirs: Vec<String> = on_disk
+        .iter()
+        .filter(|(key, _)| !registered.contains(*key))
+        .map(|(_, dir)| dir.clone())
+        .collect();
+    unregistered_dirs.sort();
+    Ok(EngagementLint {
+        orphaned_map_entries: mapped.difference(&registered).cloned().collect(),
+        unregistered_dirs,
+        missing_on_disk: registered
+            .iter()
+            .filter(|key| !on_disk.contains_key(*key))
+            .cloned()
+            .collect(),
+    })
+}
+
+/// One problem found by validate_repo, and the file it's in (relative to
+/// the repo, `/`-separated).
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct FileDiagnostic {
+    pub file: String,
+    /// Where in the file, as in Diagnostic; empty for the file as a whole.
+    pub path: String,
+    pub message: String,
+}
+
+/// validate_repo's findings, each list in the order the checks ran.
+#[derive(Debug, Default, serde::Serialize)]
+pub struct RepoReport {
+    pub errors: Vec<FileDiagnostic>,
+    pub warnings: Vec<FileDiagnostic>,
+}
+
+impl RepoReport {
+    fn add(&mut self, severity: Severity, file: &str, path: &str, message: impl Into<String>) {
+        let diagnostic = FileDiagnostic {
+            file: file.to_string(),
+            path: path.to_string(),
+            message: message.into(),
+        };
+        match severity {
+            Severity::Error => self.errors.push(diagnostic),
+            Severity::Warning => self.warnings.push(diagnostic),
+        }
+    }
+}
+
+/// Every check on the repo at once: files that can't be read or parsed
+/// (including ones over the size limit) and unrecognized knowledge log dates,
+/// as read_company_data reports them; validate_org_chart's findings;
+/// lint_engagements' mismatches, as warnings; and engagement_config.json
+/// files that aren't valid JSON.
+#[tauri::command(async)]
+pub fn validate_repo(
+    app: tauri::AppHandle,
+    config: tauri::State<'_, ViewerConfig>,
+    repo_path: String,
+) -> Result<RepoReport, CommandError> {
+    telemetry::timed(&app, "validate_repo", || {
+        validate_repo_in(Path::new(&repo_path), &config)
+    })
+}
+
+fn validate_repo_in(base: &Path, config: &ViewerConfig) -> Result<RepoReport, CommandError> {
+    let mut report = RepoReport::default();
+    let (errors, warnings) = company::load_problems(base, config)?;
+    for (severity, problems) in [(Severity::Error, errors), (Severity::Warning, warnings)] {
+        for problem in problems {
+            let file = problem["file"].as_str().unwrap_or_default();
+            let message = problem["message"].as_str().unwrap_or_default();
+            match problem["line"].as_u64() {
+                Some(line) => report.add(severity, file, "", format!("Line {}: {}", line, message)),
+                None => report.add(severity, file, "", message),
+            }
+        }
+    }
+
+    // A chart that failed to load is already reported above.
+    let company_dir = company::company_dir(base)?;
+    if let Some(path) = company::find_company_file(&company_dir, "org_chart.json") {
+        let name = company::file_name(&path);
+        if let Ok(chart) = company::parse_company_file(&path, &name, config.max_file_bytes) {
+            let file = format!("_company/{}", name);
+            for d in check_org_chart(&chart) {
+                report.add(d.severity, &file, &d.path, d.message);
+            }
+        }
+    }
+
+    match lint_engagements_in(base, config) {
+        Ok(lint) => {
+            for key in &lint.orphaned_map_entries {
+                let message = format!("Engagement {} is mapped but not registered", key);
+                report.add(Severity::Warning, "_company/engagement_map.json", "", message);
+            }
+            for dir in &lint.unregistered_dirs {
+                let file = format!("{}/engagement_config.json", dir);
+                report.add(Severity::Warning, &file, "", "Engagement is not registered");
+            }
+            for key in &lint.missing_on_disk {
+                let message = format!("Engagement {} has no folder", key);
+                report.add(Severity::Warning, "_company/engagement_registry.json", "", message);
+            }
+        }
+        Err(e) => report.add(Severity::Warning, "_company", "", e.message()),
+    }
+
+    for dir in company::engagement_dirs(base, config) {
+        let relative = dir.strip_prefix(base).unwrap_or(&dir).to_string_lossy().replace('\\', "/");
+        let file = format!("{}/engagement_config.json", relative);
+        let parsed = config::read_capped(
+            &dir.join("engagement_config.json"),
+            "engagement_config.json",
+            config.max_file_bytes,
+        )
+        .and_then(|content| {
+            serde_json::from_str::<serde_json::Value>(company::strip_bom(&content))
+                .map_err(|e| CommandError::Parse(format!("Failed to parse engagement_config.json: {}", e)))
+        });
+        if let Err(e) = parsed {
+            report.add(Severity::Error, &file, "", e.message());
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn org_chart_problems_are_reported() {
+        let chart = serde_json::json!({
+            "leadership": [
+                { "name": "Ana Ruiz", "reports_to": "Ben Ode (inferred)" },
+                { "name": "Ben Ode", "reports_to": "Ana Ruiz" },
+            ],
+            "people": [
+                { "name": "Cy Lee", "reports_to": "Ana Ruiz", "dotted_to": "Nobody Here" },
+                { "title": "Unnamed" },
+                { "name": "Fay", "title": 7 },
+                { "name": "cy  lee" },
+                { "name": "Dee Self", "reports_to": "Dee Self" },
+                "not a person",
+            ],
+            "external_ecosystem": {
+                "vendor": { "name": "Vendor", "key_contacts": [{ "name": "Eve", "reports_to": "Ghost" }] },
+            },
+        });
+        let found: Vec<(Severity, String, String)> = check_org_chart(&chart)
+            .into_iter()
+            .map(|d| (d.severity, d.path, d.message))
+            .collect();
+        let expect =
+            |severity, path: &str, message: &str| (severity, path.to_string(), message.to_string());
+
+        assert_eq!(
+            found,
+            [
+                expect(
+                    Severity::Error,
+                    "people[2]",
+                    "Not a valid person: invalid type: integer `7`, expected a string",
+                ),
+                expect(
+                    Severity::Error,
+                    "people[5]",
+                    "Not a valid person: invalid type: string \"not a person\", expected struct Person",
+                ),
+                expect(Severity::Error, "people[1]", "Person has no name"),
+                expect(Severity::Warning, "people[3]", "Duplicate of people[0]; only the first is shown"),
+                expect(Severity::Error, "people[0].dotted_to", "dotted_to refers to \"Nobody Here\", who isn't in the org chart"),
+                expect(
+                    Severity::Error,
+                    "external_ecosystem.vendor.key_contacts[0].reports_to",
+                    "reports_to refers to \"Ghost\", who isn't in the org chart",
+                ),
+                expect(Severity::Error, "leadership[0].reports_to", "Reporting cycle: ana_ruiz -> ben_ode -> ana_ruiz"),
+                expect(Severity::Error, "people[4].reports_to", "Reporting cycle: dee_self -> dee_self"),
+            ]
+        );
+    }
+
+    #[test]
+    fn ids_match_the_viewer() {
+        assert_eq!(make_id("  Dr. Ana Ruiz-Ode "), "dr_ana_ruiz_ode");
+        assert_eq!(strip_note("Ben Ode (inferred) "), "Ben Ode");
+        assert_eq!(strip_note("Ben (Benny) Ode"), "Ben (Benny) Ode");
+    }
+
+    #[test]
+    fn engagement_mismatches_are_listed() {
+        let dir = TempDir::new("lint-engagements");
+        dir.write(
+            "_company/engagement_registry.json",
+            r#"{"engagements": {"alpha": {}, "beta-2024": {}, "gone": {}}}"#,
+        );
+        dir.write(
+            "_company/engagement_map.json",
+            r#"{"engagements": [{"key": "alpha"}, {"key": "retired"}]}"#,
+        );
+        dir.write("alpha/engagement_config.json", "{}");
+        dir.write(
+            "beta/engagement_config.json",
+            r#"{"engagement": "beta-2024"}"#,
+        );
+        dir.write("stray/engagement_config.json", "{ broken");
+
+        let lint = lint_engagements_in(dir.path(), &ViewerConfig::default()).unwrap();
+        assert_eq!(
+            lint,
+            EngagementLint {
+                orphaned_map_entries: vec!["retired".to_string()],
+                unregistered_dirs: vec!["stray".to_string()],
+                missing_on_disk: vec!["gone".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn repo_report_covers_every_check() {
+        let dir = TempDir::new("validate-repo");
+        dir.write(
+            "_company/org_chart.json",
+            r#"{"leadership": [{"name": "Ana"}], "people": [{"name": "Ben", "reports_to": "Ghost"}]}"#,
+        );
+        dir.write(
+            "_company/engagement_registry.json",
+            r#"{"engagements": {"alpha": {}, "gone": {}}}"#,
+        );
+        dir.write("_company/engagement_map.json", r#"{"engagements": [{"key": "retired"}]}"#);
+        dir.write("_company/company_config.json", "{ broken");
+        dir.write("_company/file_index.json", &format!("[{}]", "0,".repeat(1000) + "0"));
+        dir.write("alpha/engagement_config.json", "{}");
+        dir.write("alpha/ops/KNOWLEDGE_LOG.md", "## Week 3\n### [STATUS] Undated\n");
+        dir.write("stray/engagement_config.json", "{ broken");
+        let config = ViewerConfig { max_file_bytes: 1024, ..ViewerConfig::default() };
+
+        let report = validate_repo_in(dir.path(), &config).unwrap();
+        let found = |list: &[FileDiagnostic]| -> Vec<(String, String)> {
+            list.iter()
+                .map(|d| (d.file.clone(), d.message.split(':').next().unwrap().to_string()))
+                .collect()
+        };
+        let expect = |file: &str, message: &str| (file.to_string(), message.to_string());
+        assert_eq!(
+            found(&report.errors),
+            [
+                expect("_company/company_config.json", "Failed to parse company_config.json"),
+                expect("_company/file_index.json", "file_index.json exceeds size limit (2003 bytes, limit 1024 bytes)"),
+                expect("_company/org_chart.json", "reports_to refers to \"Ghost\", who isn't in the org chart"),
+                expect("stray/engagement_config.json", "Failed to parse engagement_config.json"),
+            ]
+        );
+        assert_eq!(
+            found(&report.warnings),
+            [
+                expect("alpha/ops/KNOWLEDGE_LOG.md", "Line 1"),
+                expect("_company/engagement_map.json", "Engagement retired is mapped but not registered"),
+                expect("stray/engagement_config.json", "Engagement is not registered"),
+                expect("_company/engagement_registry.json", "Engagement gone has no folder"),
+            ]
+        );
+        assert_eq!(report.errors[2].path, "people[0].reports_to");
+    }
+}
@@ -0,0 +1,227 @@
+// ── Repo watching (push live-update events) ────────────────────────────────
+
+use crate::company;
+use crate::config::ViewerConfig;
+use crate::error::CommandError;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::Emitter;
+
+/// How long a path must be quiet before its burst of changes is reported.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+/// The longest a path that keeps changing (a log being written, say) goes
+/// unreported.
+const MAX_DEBOUNCE_WAIT: Duration = Duration::from_secs(2);
+
+/// Extensions of files the viewer writes itself; see is_own_file.
+const IGNORED_EXTENSIONS: [&str; 3] = ["bak", "log", "tmp"];
+
+/// The active repo watcher, if any. Dropping it stops the watch and ends
+/// its debounce thread.
+pub type WatcherState = Arc<Mutex<Option<RecommendedWatcher>>>;
+
+#[derive(Clone, serde::Serialize)]
+struct CompanyDataChanged {
+    /// The first of `paths`, for listeners that only want one.
+    path: String,
+    paths: Vec<String>,
+}
+
+/// Watch `_company` and every engagement directory under `repo_path`, and
+/// emit one `company-data-changed` with `{path, paths}` for the files touched
+/// once their burst of changes settles. The viewer's own backups, logs and
+/// temporary files are ignored. Replaces any watch already running.
+#[tauri::command]
+pub fn watch_repo(
+    state: tauri::State<'_, WatcherState>,
+    config: tauri::State<'_, ViewerConfig>,
+    app: tauri::AppHandle,
+    repo_path: String,
+) -> Result<(), CommandError> {
+    let base = PathBuf::from(&repo_path);
+    let company_dir = company::company_dir(&base)?;
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)
+        .map_err(|e| CommandError::Io(format!("Failed to start watcher: {}", e)))?;
+    let mut dirs = vec![company_dir];
+    dirs.extend(company::engagement_dirs(&base, &config));
+    for dir in &dirs {
+        watcher
+            .watch(dir, RecursiveMode::Recursive)
+            .map_err(|e| CommandError::Io(format!("Failed to watch {}: {}", dir.display(), e)))?;
+    }
+    tracing::info!(repo = %repo_path, dirs = dirs.len(), "watch_repo: watching");
+
+    std::thread::spawn(move || {
+        debounce(rx, DEBOUNCE, MAX_DEBOUNCE_WAIT, |paths| {
+            let paths: Vec<String> = paths.iter().map(|p| p.to_string_lossy().into_owned()).collect();
+            let payload = CompanyDataChanged { path: paths[0].clone(), paths };
+            let _ = app.emit("company-data-changed", payload);
+        });
+        tracing::debug!("watch_repo: watcher stopped");
+    });
+
+    let mut guard = state.lock()?;
+    *guard = Some(watcher);
+    Ok(())
+}
+
+/// Stop the watch started by watch_repo. A no-op if nothing is watched.
+#[tauri::command]
+pub fn unwatch_repo(state: tauri::State<'_, WatcherState>) -> Result<(), CommandError> {
+    let mut guard = state.lock()?;
+    guard.take();
+    Ok(())
+}
+
+/// Collect changed paths and hand each to `emit` once it has had no events
+/// for `quiet`, so it has finished its burst (an editor's write, truncate,
+/// write again), or once it has been waiting `max_wait`, so a file that never
+/// stops changing is still reported. Paths due at the same time go in one
+/// call. Runs until the watcher (the sender) is dropped.
+fn debounce(
+    rx: mpsc::Receiver<notify::Result<notify::Event>>,
+    quiet: Duration,
+    max_wait: Duration,
+    mut emit: impl FnMut(Vec<PathBuf>),
+) {
+    // Each pending path's first and latest event.
+    let mut pending: BTreeMap<PathBuf, (Instant, Instant)> = BTreeMap::new();
+    let due = |&(first, last): &(Instant, Instant)| (last + quiet).min(first + max_wait);
+    loop {
+        let next = pending.values().map(due).min();
+        let event = match next {
+            None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            Some(at) => rx.recv_timeout(at.saturating_duration_since(Instant::now())),
+        };
+        match event {
+            Ok(event) => {
+                let now = Instant::now();
+                for path in changed_paths(event) {
+                    pending.entry(path).or_insert((now, now)).1 = now;
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+        let now = Instant::now();
+        let ready: Vec<PathBuf> =
+            pending.iter().filter(|(_, times)| due(times) <= now).map(|(path, _)| path.clone()).collect();
+        if !ready.is_empty() {
+            for path in &ready {
+                pending.remove(path);
+            }
+            emit(ready);
+        }
+    }
+}
+
+fn changed_paths(event: notify::Result<notify::Event>) -> Vec<PathBuf> {
+    match event {
+        // Reading the files (as read_company_data does on reload) must not
+        // count as a change, or every reload would trigger another.
+        Ok(event) if matches!(event.kind, EventKind::Access(_)) => Vec::new(),
+        Ok(event) => event.paths.into_iter().filter(|path| !is_own_file(path)).collect(),
+        Err(e) => {
+            tracing::warn!("watch_repo: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Files the viewer writes itself: write_company_file's backups, the log,
+/// and config::write_atomic's temporaries. Reloading for them would have the
+/// viewer chase its own tail.
+fn is_own_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| IGNORED_EXTENSIONS.iter().any(|ignored| ext.eq_ignore_ascii_case(ignored)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{DataChange, ModifyKind};
+
+    #[test]
+    fn a_burst_of_saves_is_one_emission() {
+        let (tx, rx) = mpsc::channel();
+        let (emitted_tx, emitted) = mpsc::channel();
+        let thread = std::thread::spawn(move || {
+            let (quiet, max_wait) = (Duration::from_millis(100), Duration::from_secs(5));
+            debounce(rx, quiet, max_wait, |paths| emitted_tx.send(paths).unwrap())
+        });
+        let modify = |path: &str| {
+            let kind = EventKind::Modify(ModifyKind::Data(DataChange::Content));
+            Ok(notify::Event::new(kind).add_path(PathBuf::from(path)))
+        };
+        for path in [
+            "/repo/_company/org_chart.json",
+            "/repo/_company/org_chart.json.bak",
+            "/repo/_company/org_chart.json",
+            "/repo/_company/.org_chart.json.42.tmp",
+            "/repo/alpha/ops/KNOWLEDGE_LOG.md",
+            "/repo/sl-ot-viewer.log",
+            "/repo/_company/org_chart.json",
+        ] {
+            tx.send(modify(path)).unwrap();
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        tx.send(Ok(notify::Event::new(EventKind::Access(notify::event::AccessKind::Any))
+            .add_path(PathBuf::from("/repo/alpha/ops/KNOWLEDGE_LOG.md"))))
+            .unwrap();
+
+        // Each path settles on its own, so they may come in one call or two,
+        // but each comes once.
+        let mut paths = emitted.recv_timeout(Duration::from_secs(5)).unwrap();
+        while let Ok(more) = emitted.recv_timeout(Duration::from_millis(300)) {
+            paths.extend(more);
+        }
+        paths.sort();
+        assert_eq!(
+            paths,
+            [PathBuf::from("/repo/_company/org_chart.json"), PathBuf::from("/repo/alpha/ops/KNOWLEDGE_LOG.md")]
+        );
+        drop(tx);
+        thread.join().unwrap();
+    }
+
+    #[test]
+    fn a_noisy_path_does_not_hold_back_others() {
+        let (tx, rx) = mpsc::channel();
+        let (emitted_tx, emitted) = mpsc::channel();
+        let quiet = Duration::from_millis(150);
+        let max_wait = Duration::from_millis(600);
+        let thread = std::thread::spawn(move || {
+            debounce(rx, quiet, max_wait, |paths| emitted_tx.send((Instant::now(), paths)).unwrap())
+        });
+        let modify = |path: &str| {
+            let kind = EventKind::Modify(ModifyKind::Data(DataChange::Content));
+            Ok(notify::Event::new(kind).add_path(PathBuf::from(path)))
+        };
+
+        let start = Instant::now();
+        let noise_for = Duration::from_millis(1500);
+        tx.send(modify("/repo/_company/org_chart.json")).unwrap();
+        while start.elapsed() < noise_for {
+            tx.send(modify("/repo/alpha/ops/build.txt")).unwrap();
+            std::thread::sleep(Duration::from_millis(20));
+        }
+
+        let (at, paths) = emitted.try_recv().unwrap();
+        assert_eq!(paths, [PathBuf::from("/repo/_company/org_chart.json")]);
+        assert!(at - start < max_wait, "{:?}", at - start);
+        // The noisy file is still reported, at most max_wait apart, while it
+        // goes on changing.
+        let (at, paths) = emitted.try_recv().unwrap();
+        assert_eq!(paths, [PathBuf::from("/repo/alpha/ops/build.txt")]);
+        assert!(at - start < noise_for, "{:?}", at - start);
+        drop(tx);
+        thread.join().unwrap();
+    }
+}